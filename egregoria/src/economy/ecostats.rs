@@ -1,4 +1,4 @@
-use crate::economy::{ItemID, ItemRegistry, Money, Trade, TradeTarget};
+use crate::economy::{ItemID, ItemRegistry, Market, Money, Trade};
 use serde::{Deserialize, Serialize};
 use serde_big_array::BigArray;
 use std::collections::BTreeMap;
@@ -15,6 +15,7 @@ pub const LEVEL_NAMES: [&str; 4] = ["10m", "1h", "10h", "50h"];
 pub struct ItemHistoryLevel {
     #[serde(with = "BigArray")]
     pub past_ring_items: [i64; HISTORY_SIZE],
+    /// What the buyers paid, tariffs excluded
     #[serde(with = "BigArray")]
     pub past_ring_money: [Money; HISTORY_SIZE],
 }
@@ -39,11 +40,38 @@ pub struct ItemHistories {
     cursors: [usize; LEVEL_FREQS.len()],
 }
 
+/// Market price of one item at one frequency level, taken at the end of each bin
+#[derive(Serialize, Deserialize)]
+pub struct PriceHistoryLevel {
+    #[serde(with = "BigArray")]
+    pub past_ring: [Money; HISTORY_SIZE],
+}
+
+impl Default for PriceHistoryLevel {
+    fn default() -> Self {
+        Self {
+            past_ring: [Money::ZERO; HISTORY_SIZE],
+        }
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct PriceHistory {
+    levels: [PriceHistoryLevel; LEVEL_FREQS.len()],
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct PriceHistories {
+    m: BTreeMap<ItemID, PriceHistory>,
+    cursors: [usize; LEVEL_FREQS.len()],
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct EcoStats {
     pub exports: ItemHistories,
     pub imports: ItemHistories,
     pub internal_trade: ItemHistories,
+    pub prices: PriceHistories,
 }
 
 impl ItemHistories {
@@ -82,7 +110,7 @@ impl ItemHistories {
             let lvl = unsafe { level.past_ring_items.get_unchecked_mut(*cursor) };
             *lvl = lvl.saturating_add(trade.qty as i64);
             let lvl = unsafe { level.past_ring_money.get_unchecked_mut(*cursor) };
-            lvl.0 = lvl.0.saturating_add(trade.money_delta.0);
+            lvl.0 = lvl.0.saturating_add(trade.cost.0);
         }
     }
 
@@ -99,12 +127,53 @@ impl ItemHistories {
     }
 }
 
+impl PriceHistories {
+    pub fn new(registry: &ItemRegistry) -> Self {
+        Self {
+            m: registry
+                .iter()
+                .map(|item| (item.id, PriceHistory::default()))
+                .collect(),
+            cursors: [0; LEVEL_FREQS.len()],
+        }
+    }
+
+    pub fn cursors(&self) -> &[usize] {
+        &self.cursors
+    }
+
+    pub fn iter_histories(
+        &self,
+        level: usize,
+    ) -> impl Iterator<Item = (ItemID, &PriceHistoryLevel)> {
+        self.m
+            .iter()
+            .filter_map(move |(id, history)| Some((*id, history.levels.get(level)?)))
+    }
+
+    /// Overwrites the current bin of each level with the current prices
+    pub fn advance(&mut self, tick: u32, market: &Market) {
+        for (c, freq) in self.cursors.iter_mut().zip(&LEVEL_FREQS) {
+            if tick.is_multiple_of(*freq) {
+                *c = (*c + 1) % HISTORY_SIZE;
+            }
+        }
+        for (id, h) in self.m.iter_mut() {
+            let price = market.price(*id);
+            for (level, cursor) in h.levels.iter_mut().zip(&self.cursors) {
+                level.past_ring[*cursor] = price;
+            }
+        }
+    }
+}
+
 impl EcoStats {
     pub fn new(registry: &ItemRegistry) -> Self {
         Self {
             exports: ItemHistories::new(registry),
             imports: ItemHistories::new(registry),
             internal_trade: ItemHistories::new(registry),
+            prices: PriceHistories::new(registry),
         }
     }
 
@@ -114,11 +183,11 @@ impl EcoStats {
         self.internal_trade.advance(tick);
 
         for trade in trades {
-            if trade.buyer == TradeTarget::ExternalTrade {
+            if trade.buyer.is_external() {
                 self.exports.handle_trade(trade);
                 continue;
             }
-            if trade.seller == TradeTarget::ExternalTrade {
+            if trade.seller.is_external() {
                 self.imports.handle_trade(trade);
                 continue;
             }
@@ -0,0 +1,183 @@
+//! Connections of the city to the outside world, through which goods are imported and exported.
+//!
+//! Each connection has its own prices, a daily throughput and a transport cost depending on the
+//! distance the goods travel to reach it, so the place of a trade matters.
+
+use crate::economy::{Money, TradeTarget};
+use crate::map::{BuildingID, BuildingKind, Map};
+use geom::Vec2;
+use ordered_float::OrderedFloat;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum ExternalTradeKind {
+    Rail,
+    Highway,
+    Port,
+}
+
+debug_inspect_impl!(ExternalTradeKind);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExternalConnection {
+    pub kind: ExternalTradeKind,
+    pub building: BuildingID,
+    pub pos: Vec2,
+    /// Share of the external value added to the import price
+    pub import_offset: f64,
+    /// Share of the external value removed from the export price
+    pub export_offset: f64,
+    /// Quantity of goods that can go through the connection each day
+    pub daily_capacity: u32,
+    /// Share of the external value paid to carry goods over each km to the connection
+    pub transport_cost: f64,
+    /// Quantity of goods that went through the connection today
+    pub used: u32,
+}
+
+impl ExternalConnection {
+    pub fn new(kind: ExternalTradeKind, building: BuildingID, pos: Vec2) -> Self {
+        let (import_offset, export_offset, daily_capacity, transport_cost) = match kind {
+            ExternalTradeKind::Rail => (0.0, 0.0, 2000, 0.01),
+            ExternalTradeKind::Highway => (0.1, 0.1, 500, 0.04),
+            // ships bring goods cheaply but the port takes its cut on what leaves
+            ExternalTradeKind::Port => (-0.1, 0.05, 5000, 0.02),
+        };
+        Self {
+            kind,
+            building,
+            pos,
+            import_offset,
+            export_offset,
+            daily_capacity,
+            transport_cost,
+            used: 0,
+        }
+    }
+
+    pub fn target(&self) -> TradeTarget {
+        TradeTarget::ExternalTrade(self.building, self.kind)
+    }
+
+    pub fn remaining(&self) -> u32 {
+        self.daily_capacity.saturating_sub(self.used)
+    }
+
+    fn transport_share(&self, pos: Vec2) -> f64 {
+        self.transport_cost * self.pos.distance(pos) as f64 / 1000.0
+    }
+
+    /// Price paid for each unit imported through this connection and carried to `pos`
+    pub fn import_price(&self, import_price: Money, pos: Vec2) -> Money {
+        let share = 1.0 + self.import_offset + self.transport_share(pos);
+        Money::new_inner((import_price.inner() as f64 * share) as i64)
+    }
+
+    /// Price received for each unit carried from `pos` and exported through this connection
+    pub fn export_price(&self, export_price: Money, pos: Vec2) -> Money {
+        let share = (1.0 - self.export_offset - self.transport_share(pos)).max(0.0);
+        Money::new_inner((export_price.inner() as f64 * share) as i64)
+    }
+}
+
+/// All the external trade connections of the city, by the building standing for them
+#[derive(Default, Serialize, Deserialize)]
+pub struct ExternalTrades {
+    connections: BTreeMap<BuildingID, ExternalConnection>,
+    /// Day the throughput of the connections was last reset
+    day: i32,
+}
+
+impl ExternalTrades {
+    pub fn add(&mut self, kind: ExternalTradeKind, building: BuildingID, pos: Vec2) {
+        self.connections
+            .insert(building, ExternalConnection::new(kind, building, pos));
+    }
+
+    /// Adds the external trading buildings of the map that have no connection yet, as rail
+    /// connections since it was the only kind before. Used for saves made before connections
+    /// were stored.
+    pub fn add_missing(&mut self, map: &Map) {
+        let Some(buildings) = map.bkinds.get(&BuildingKind::ExternalTrading) else {
+            return;
+        };
+        for &id in buildings {
+            if self.connections.contains_key(&id) {
+                continue;
+            }
+            let Some(b) = map.buildings().get(id) else {
+                continue;
+            };
+            self.add(ExternalTradeKind::Rail, id, b.obb.center());
+        }
+    }
+
+    pub fn remove(&mut self, building: BuildingID) {
+        self.connections.remove(&building);
+    }
+
+    pub fn get(&self, building: BuildingID) -> Option<&ExternalConnection> {
+        self.connections.get(&building)
+    }
+
+    pub fn get_mut(&mut self, building: BuildingID) -> Option<&mut ExternalConnection> {
+        self.connections.get_mut(&building)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ExternalConnection> {
+        self.connections.values()
+    }
+
+    pub fn nearest(&self, kind: ExternalTradeKind, pos: Vec2) -> Option<&ExternalConnection> {
+        self.connections
+            .values()
+            .filter(|c| c.kind == kind)
+            .min_by_key(|c| OrderedFloat(c.pos.distance2(pos)))
+    }
+
+    /// Resets the throughput of the connections when the day changes
+    pub fn new_day(&mut self, day: i32) {
+        if day == self.day {
+            return;
+        }
+        self.day = day;
+        for c in self.connections.values_mut() {
+            c.used = 0;
+        }
+    }
+
+    /// Imports `qty` goods to `pos` through the cheapest connection that can still carry them.
+    /// Returns the connection and the price of each unit.
+    pub(crate) fn import(
+        &mut self,
+        import_price: Money,
+        pos: Vec2,
+        qty: u32,
+    ) -> Option<(TradeTarget, Money)> {
+        let c = self
+            .connections
+            .values_mut()
+            .filter(|c| c.remaining() >= qty)
+            .min_by_key(|c| c.import_price(import_price, pos))?;
+        c.used += qty;
+        Some((c.target(), c.import_price(import_price, pos)))
+    }
+
+    /// Exports `qty` goods from `pos` through the best paying connection that can still carry
+    /// them. Returns the connection and the price of each unit.
+    pub(crate) fn export(
+        &mut self,
+        export_price: Money,
+        pos: Vec2,
+        qty: u32,
+    ) -> Option<(TradeTarget, Money)> {
+        let c = self
+            .connections
+            .values_mut()
+            .filter(|c| c.remaining() >= qty)
+            .max_by_key(|c| c.export_price(export_price, pos))?;
+        c.used += qty;
+        Some((c.target(), c.export_price(export_price, pos)))
+    }
+}
@@ -1,28 +1,95 @@
-use crate::economy::Money;
+use crate::economy::{BudgetLedger, LedgerCategory, Money};
 use crate::engine_interaction::WorldCommand;
 use crate::map::{LanePattern, MapProject, MAX_ZONE_AREA};
 use crate::{BuildingKind, Egregoria, GoodsCompanyRegistry};
+use egui_inspect::Inspect;
 use serde::{Deserialize, Serialize};
 
 /// The government represents the player.
 #[derive(Serialize, Deserialize)]
 pub struct Government {
     pub money: Money,
+    pub taxes: TaxRates,
 }
 
 impl Default for Government {
     fn default() -> Self {
         Self {
             money: Money::new_bucks(150_000),
+            taxes: TaxRates::default(),
         }
     }
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Tax {
+    /// On the wages of the humans
+    Income,
+    /// On the profit of the companies
+    Corporate,
+    /// On the imports and exports
+    Tariff,
+}
+
+/// Tax rates set by the player, in [0; 1]
+#[derive(Inspect, Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TaxRates {
+    pub income: f32,
+    pub corporate: f32,
+    pub tariff: f32,
+}
+
+impl Default for TaxRates {
+    fn default() -> Self {
+        Self {
+            income: 0.1,
+            corporate: 0.2,
+            tariff: 0.05,
+        }
+    }
+}
+
+impl TaxRates {
+    pub fn get(&self, tax: Tax) -> f32 {
+        match tax {
+            Tax::Income => self.income,
+            Tax::Corporate => self.corporate,
+            Tax::Tariff => self.tariff,
+        }
+    }
+
+    pub fn set(&mut self, tax: Tax, rate: f32) {
+        let rate = rate.clamp(0.0, 1.0);
+        match tax {
+            Tax::Income => self.income = rate,
+            Tax::Corporate => self.corporate = rate,
+            Tax::Tariff => self.tariff = rate,
+        }
+    }
+
+    /// The tax taken on the given amount
+    pub fn levy(&self, tax: Tax, amount: Money) -> Money {
+        Money::new_inner((amount.inner() as f64 * self.get(tax) as f64) as i64)
+    }
+}
+
 impl Government {
+    pub fn earn(&mut self, ledger: &mut BudgetLedger, cat: LedgerCategory, amount: Money) {
+        self.money += amount;
+        ledger.record(cat, amount);
+    }
+
+    pub fn spend(&mut self, ledger: &mut BudgetLedger, cat: LedgerCategory, amount: Money) {
+        self.money -= amount;
+        ledger.record(cat, amount);
+    }
+
     pub fn action_cost(action: &WorldCommand, goria: &Egregoria) -> Money {
         Money::new_bucks(match action {
             WorldCommand::MapBuildHouse(_) => 100,
             WorldCommand::AddTrain { n_wagons, .. } => 1000 + 100 * (*n_wagons as i64),
+            WorldCommand::AddBusStop(_) => 200,
+            WorldCommand::AddBusLine { stops, .. } => 500 + 100 * stops.len() as i64,
             WorldCommand::MapMakeConnection { from, to, pat, .. } => {
                 Self::connection_cost(from, to, pat)
             }
@@ -66,10 +133,30 @@ impl Government {
                 BuildingKind::TrainStation => 1000,
                 _ => 0,
             },
+            WorldCommand::MapBuildExternalConnection { .. } => 5000,
             _ => 0,
         })
     }
 
+    /// Where the cost of an action goes in the [`BudgetLedger`]
+    pub fn action_category(action: &WorldCommand) -> LedgerCategory {
+        match action {
+            WorldCommand::MapBuildHouse(_) => LedgerCategory::Housing,
+            WorldCommand::AddTrain { .. }
+            | WorldCommand::AddBusStop(_)
+            | WorldCommand::AddBusLine { .. }
+            | WorldCommand::MapBuildSpecialBuilding {
+                kind: BuildingKind::RailFreightStation | BuildingKind::TrainStation,
+                ..
+            } => LedgerCategory::Transit,
+            WorldCommand::UpdateZone { .. } | WorldCommand::MapBuildSpecialBuilding { .. } => {
+                LedgerCategory::Companies
+            }
+            WorldCommand::MapBuildExternalConnection { .. } => LedgerCategory::ExternalTrade,
+            _ => LedgerCategory::Construction,
+        }
+    }
+
     fn connection_cost(p1: &MapProject, p2: &MapProject, pat: &LanePattern) -> i64 {
         let dist = p1.pos.distance(p2.pos);
         50 + ((0.03 * dist) as i64).max(1)
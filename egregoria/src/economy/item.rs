@@ -5,12 +5,12 @@ use slotmapd::{new_key_type, SlotMap};
 use std::ops::Index;
 
 /// ItemDefinition is the definition of an item, as read from the items.json file.
-#[derive(Serialize, Deserialize)]
-struct ItemDefinition {
-    name: String,
-    label: String,
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct ItemDefinition {
+    pub name: String,
+    pub label: String,
     #[serde(default)]
-    optout_exttrade: bool,
+    pub optout_exttrade: bool,
 }
 
 /// Item is the runtime representation of an item, such as meat, wood, etc.
@@ -70,7 +70,15 @@ impl ItemRegistry {
                 return;
             }
         };
+        self.add_definitions(definitions);
+    }
+
+    pub(crate) fn add_definitions(&mut self, definitions: Vec<ItemDefinition>) {
         for definition in definitions {
+            if self.item_names.contains_key(&definition.name) {
+                log::error!("item {} is defined twice", definition.name);
+                continue;
+            }
             let name = definition.name.clone();
             let id = self.items.insert_with_key(move |id| Item {
                 id,
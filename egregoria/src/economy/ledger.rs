@@ -0,0 +1,195 @@
+use crate::economy::{Money, HISTORY_SIZE, LEVEL_FREQS};
+use serde::{Deserialize, Serialize};
+use serde_big_array::BigArray;
+use std::collections::BTreeMap;
+
+/// Where the money of the government comes from or goes to
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum LedgerCategory {
+    IncomeTax,
+    CorporateTax,
+    Tariffs,
+    /// Goods sold by the stations
+    Sales,
+    /// Roads and rails
+    Construction,
+    /// Upkeep of the lanes and of the stations
+    Maintenance,
+    /// Paid to the humans without a job
+    Benefits,
+    /// Goods bought by the stations
+    Purchases,
+    Housing,
+    /// Company buildings and their zones
+    Companies,
+    /// Stations, bus stops, bus lines and trains
+    Transit,
+    /// Connections to the outside
+    ExternalTrade,
+    /// Wages of the warehouse workers
+    Warehouses,
+}
+
+impl LedgerCategory {
+    pub const ALL: [LedgerCategory; 13] = [
+        LedgerCategory::IncomeTax,
+        LedgerCategory::CorporateTax,
+        LedgerCategory::Tariffs,
+        LedgerCategory::Sales,
+        LedgerCategory::Construction,
+        LedgerCategory::Housing,
+        LedgerCategory::Companies,
+        LedgerCategory::Transit,
+        LedgerCategory::ExternalTrade,
+        LedgerCategory::Maintenance,
+        LedgerCategory::Benefits,
+        LedgerCategory::Warehouses,
+        LedgerCategory::Purchases,
+    ];
+
+    pub fn is_revenue(&self) -> bool {
+        matches!(
+            self,
+            LedgerCategory::IncomeTax
+                | LedgerCategory::CorporateTax
+                | LedgerCategory::Tariffs
+                | LedgerCategory::Sales
+        )
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            LedgerCategory::IncomeTax => "Income tax",
+            LedgerCategory::CorporateTax => "Corporate tax",
+            LedgerCategory::Tariffs => "Tariffs",
+            LedgerCategory::Sales => "Sales",
+            LedgerCategory::Construction => "Roads",
+            LedgerCategory::Maintenance => "Maintenance",
+            LedgerCategory::Benefits => "Benefits",
+            LedgerCategory::Purchases => "Purchases",
+            LedgerCategory::Housing => "Housing",
+            LedgerCategory::Companies => "Companies",
+            LedgerCategory::Transit => "Transit",
+            LedgerCategory::ExternalTrade => "External trade",
+            LedgerCategory::Warehouses => "Warehouses",
+        }
+    }
+}
+
+/// Money of one category at one frequency level
+#[derive(Serialize, Deserialize)]
+pub struct LedgerHistoryLevel {
+    #[serde(with = "BigArray")]
+    pub past_ring: [Money; HISTORY_SIZE],
+}
+
+impl Default for LedgerHistoryLevel {
+    fn default() -> Self {
+        Self {
+            past_ring: [Money::ZERO; HISTORY_SIZE],
+        }
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct LedgerHistory {
+    levels: [LedgerHistoryLevel; LEVEL_FREQS.len()],
+    /// Since the start of the game
+    total: Money,
+}
+
+/// Revenue and spending of the government by category
+#[derive(Default, Serialize, Deserialize)]
+pub struct BudgetLedger {
+    m: BTreeMap<LedgerCategory, LedgerHistory>,
+    cursors: [usize; LEVEL_FREQS.len()],
+}
+
+impl BudgetLedger {
+    pub fn cursors(&self) -> &[usize] {
+        &self.cursors
+    }
+
+    pub fn iter_histories(
+        &self,
+        level: usize,
+    ) -> impl Iterator<Item = (LedgerCategory, &LedgerHistoryLevel)> {
+        self.m
+            .iter()
+            .filter_map(move |(cat, history)| Some((*cat, history.levels.get(level)?)))
+    }
+
+    pub fn total(&self, cat: LedgerCategory) -> Money {
+        self.m.get(&cat).map_or(Money::ZERO, |h| h.total)
+    }
+
+    /// Records money earned or spent, the category tells which one it is
+    pub fn record(&mut self, cat: LedgerCategory, amount: Money) {
+        if amount == Money::ZERO {
+            return;
+        }
+        let h = self.m.entry(cat).or_default();
+        h.total += amount;
+        for (level, cursor) in h.levels.iter_mut().zip(&self.cursors) {
+            level.past_ring[*cursor] += amount;
+        }
+    }
+
+    pub fn advance(&mut self, tick: u32) {
+        for (c_i, (c, freq)) in self.cursors.iter_mut().zip(&LEVEL_FREQS).enumerate() {
+            if tick.is_multiple_of(*freq) {
+                *c = (*c + 1) % HISTORY_SIZE;
+                self.m.values_mut().for_each(|h| {
+                    h.levels[c_i].past_ring[*c] = Money::ZERO;
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BudgetLedger, LedgerCategory};
+    use crate::economy::{Government, Money, Tax};
+    use crate::tests::TestCtx;
+    use crate::{BuildingKind, WorldCommand};
+    use geom::vec2;
+
+    #[test]
+    fn test_income_tax_follows_rate() {
+        let mut test = TestCtx::new();
+
+        test.build_house_with_resident();
+        let bakery = test.company_kind("Bakery");
+        let total = |test: &TestCtx, cat| test.g.read::<BudgetLedger>().total(cat);
+        let companies = total(&test, LedgerCategory::Companies);
+        let roads = total(&test, LedgerCategory::Construction);
+        test.apply(&[
+            TestCtx::build_special(BuildingKind::GoodsCompany(bakery), vec2(50.0, -550.0)),
+            WorldCommand::SetTaxRate {
+                tax: Tax::Income,
+                rate: 0.5,
+            },
+        ]);
+        // the store is paid for as a company, not as roads
+        assert!(total(&test, LedgerCategory::Companies) > companies);
+        assert_eq!(total(&test, LedgerCategory::Construction), roads);
+        assert_eq!(test.g.read::<Government>().taxes.income, 0.5);
+
+        for _ in 0..500 {
+            test.tick();
+        }
+
+        let wages: Money = test
+            .g
+            .world()
+            .companies
+            .values()
+            .map(|c| c.comp.pnl.wages)
+            .sum();
+        assert!(wages > Money::ZERO);
+
+        let ledger = test.g.read::<BudgetLedger>();
+        assert_eq!(ledger.total(LedgerCategory::IncomeTax), wages / 2);
+    }
+}
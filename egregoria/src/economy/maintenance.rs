@@ -0,0 +1,134 @@
+use crate::economy::{BudgetLedger, Government, LedgerCategory, Money};
+use crate::map::{BuildingKind, Map, MIN_CONDITION};
+use crate::utils::resources::Resources;
+use crate::utils::time::GameTime;
+use crate::World;
+use serde::{Deserialize, Serialize};
+
+/// Daily cost of each meter of lane, rails included
+const COST_PER_LANE_METER: Money = Money::new_cents(1);
+/// Condition lost by the lanes each day the maintenance isn't paid
+const WEAR_PER_DAY: f32 = 0.1;
+/// Condition recovered by the lanes each day the maintenance is paid
+const REPAIR_PER_DAY: f32 = 0.2;
+
+/// Daily cost of the buildings run by the government
+fn building_cost(kind: BuildingKind) -> Money {
+    match kind {
+        BuildingKind::RailFreightStation | BuildingKind::TrainStation => Money::new_bucks(20),
+        BuildingKind::House | BuildingKind::GoodsCompany(_) | BuildingKind::ExternalTrading => {
+            Money::ZERO
+        }
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct Maintenance {
+    /// Last day the maintenance was due
+    last_day: Option<i32>,
+    /// What the last day of maintenance cost
+    pub daily_cost: Money,
+    /// Number of days in a row the treasury couldn't pay the maintenance
+    pub unpaid_days: u32,
+}
+
+pub fn maintenance_cost(map: &Map) -> Money {
+    let lanes: f32 = map.lanes().values().map(|l| l.points.length()).sum();
+    let buildings: Money = map
+        .buildings()
+        .values()
+        .map(|b| building_cost(b.kind))
+        .sum();
+    COST_PER_LANE_METER * lanes as i64 + buildings
+}
+
+/// Charges the maintenance of the infrastructure once per day. When the treasury can't pay
+/// for it, the lanes wear and get driven slower until the maintenance is paid again.
+pub fn maintenance_system(_: &mut World, resources: &mut Resources) {
+    let day = resources.get::<GameTime>().unwrap().daytime.day;
+    let mut maintenance = resources.get_mut::<Maintenance>().unwrap();
+    match maintenance.last_day {
+        Some(last) if last >= day => return,
+        None => {
+            maintenance.last_day = Some(day);
+            return;
+        }
+        Some(_) => maintenance.last_day = Some(day),
+    }
+
+    let mut map = resources.get_mut::<Map>().unwrap();
+    let mut gvt = resources.get_mut::<Government>().unwrap();
+    let mut ledger = resources.get_mut::<BudgetLedger>().unwrap();
+
+    let cost = maintenance_cost(&map);
+    maintenance.daily_cost = cost;
+
+    let change = if gvt.money >= cost {
+        gvt.spend(&mut ledger, LedgerCategory::Maintenance, cost);
+        maintenance.unpaid_days = 0;
+        REPAIR_PER_DAY
+    } else {
+        maintenance.unpaid_days += 1;
+        -WEAR_PER_DAY
+    };
+
+    for lane in map.lanes.values_mut() {
+        lane.condition = (lane.condition + change).clamp(MIN_CONDITION, 1.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Maintenance;
+    use crate::economy::{BudgetLedger, Government, LedgerCategory, Money};
+    use crate::map::MIN_CONDITION;
+    use crate::tests::TestCtx;
+    use crate::utils::time::GameTime;
+    use geom::vec3;
+
+    #[test]
+    fn test_unpaid_maintenance_wears_lanes() {
+        let mut test = TestCtx::new();
+        test.build_roads(&[vec3(0.0, -500.0, 0.0), vec3(100.0, -500.0, 0.0)]);
+        test.tick();
+
+        let next_day = |test: &mut TestCtx| {
+            let day = test.g.read::<GameTime>().daytime.day;
+            test.g.write::<Maintenance>().last_day = Some(day - 1);
+            test.tick();
+        };
+        let min_condition = |test: &TestCtx| {
+            test.g
+                .map()
+                .lanes()
+                .values()
+                .map(|l| l.condition)
+                .fold(1.0, f32::min)
+        };
+
+        test.g.write::<Government>().money = Money::new_bucks(-1);
+        for _ in 0..20 {
+            next_day(&mut test);
+        }
+        assert_eq!(test.g.read::<Maintenance>().unpaid_days, 20);
+        assert_eq!(min_condition(&test), MIN_CONDITION);
+        assert_eq!(
+            test.g
+                .read::<BudgetLedger>()
+                .total(LedgerCategory::Maintenance),
+            Money::ZERO
+        );
+
+        test.g.write::<Government>().money = Money::new_bucks(100_000);
+        for _ in 0..10 {
+            next_day(&mut test);
+        }
+        assert_eq!(min_condition(&test), 1.0);
+        assert!(
+            test.g
+                .read::<BudgetLedger>()
+                .total(LedgerCategory::Maintenance)
+                > Money::ZERO
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use crate::economy::{Item, ItemID, ItemRegistry, Money, WORKER_CONSUMPTION_PER_SECOND};
+use crate::economy::{ExternalTradeKind, ExternalTrades, Item, ItemID, ItemRegistry, Money};
 use crate::map::BuildingID;
 use crate::map_dynamic::BuildingInfos;
 use crate::souls::goods_company::GoodsCompanyID;
@@ -23,6 +23,11 @@ pub struct BuyOrder {
     pub qty: u32,
 }
 
+/// Share of the external value that exporters get, the rest pays for the transport
+const EXPORT_SHARE: f64 = 0.8;
+/// How much the price moves at each update when there is only demand or only supply
+const PRICE_ADJUSTMENT: f64 = 0.05;
+
 #[derive(Serialize, Deserialize)]
 pub struct SingleMarket {
     // todo: change i32 to Quantity
@@ -30,6 +35,12 @@ pub struct SingleMarket {
     buy_orders: BTreeMap<SoulID, BuyOrder>,
     sell_orders: BTreeMap<SoulID, SellOrder>,
     pub ext_value: Money,
+    /// Price of the trades between souls, follows supply and demand between the export and
+    /// the import prices
+    pub price: Money,
+    /// Quantities asked and offered since the last price update
+    demand: u64,
+    supply: u64,
     optout_exttrade: bool,
 }
 
@@ -40,10 +51,36 @@ impl SingleMarket {
             buy_orders: Default::default(),
             sell_orders: Default::default(),
             ext_value,
+            price: ext_value,
+            demand: 0,
+            supply: 0,
             optout_exttrade,
         }
     }
 
+    /// Price paid for the goods bought outside
+    pub fn import_price(&self) -> Money {
+        self.ext_value
+    }
+
+    /// Price received for the goods sold outside
+    pub fn export_price(&self) -> Money {
+        Money::new_inner((self.ext_value.inner() as f64 * EXPORT_SHARE) as i64)
+    }
+
+    /// Moves the price towards the side with the most orders since the last update
+    fn update_price(&mut self) {
+        let demand = std::mem::take(&mut self.demand) as f64;
+        let supply = std::mem::take(&mut self.supply) as f64;
+        if demand + supply == 0.0 {
+            return;
+        }
+        let imbalance = (demand - supply) / (demand + supply);
+        let price = self.price.inner() as f64 * (1.0 + PRICE_ADJUSTMENT * imbalance);
+        self.price =
+            Money::new_inner(price.round() as i64).clamp(self.export_price(), self.import_price());
+    }
+
     pub fn capital(&self, soul: SoulID) -> Option<i32> {
         self.capital.get(&soul).copied()
     }
@@ -60,8 +97,10 @@ impl SingleMarket {
 }
 
 /// Market handles good exchanging between souls themselves and the external market.
-/// When goods are exchanges between souls, money is not involved.
-/// When goods are exchanged with the external market, money is involved.
+/// Goods are exchanged between souls at the market price, which rises when demand exceeds supply
+/// and falls otherwise. Goods are only bought outside when it is not more expensive
+/// than the market price, and only sold outside when the market pays less than the outside.
+/// External trades go through the best external connection that isn't full for the day.
 #[derive(Serialize, Deserialize)]
 pub struct Market {
     markets: BTreeMap<ItemID, SingleMarket>,
@@ -76,7 +115,8 @@ pub struct Market {
 #[derive(PartialOrd, Ord, PartialEq, Eq, Copy, Clone, Debug, Serialize, Deserialize)]
 pub enum TradeTarget {
     Soul(SoulID),
-    ExternalTrade,
+    /// The connection the goods went through
+    ExternalTrade(BuildingID, ExternalTradeKind),
 }
 
 debug_inspect_impl!(TradeTarget);
@@ -85,9 +125,13 @@ impl TradeTarget {
     pub(crate) fn soul(self) -> SoulID {
         match self {
             TradeTarget::Soul(soul) => soul,
-            TradeTarget::ExternalTrade => panic!("Cannot get soul from external trade"),
+            TradeTarget::ExternalTrade(..) => panic!("Cannot get soul from external trade"),
         }
     }
+
+    pub fn is_external(self) -> bool {
+        matches!(self, TradeTarget::ExternalTrade(..))
+    }
 }
 
 #[derive(Inspect, Copy, Clone, Debug, Serialize, Deserialize)]
@@ -96,7 +140,8 @@ pub struct Trade {
     pub seller: TradeTarget,
     pub qty: i32,
     pub kind: ItemID,
-    pub money_delta: Money, // money delta from the govt point of view, positive means we gained money
+    /// What the buyer pays for the whole quantity
+    pub cost: Money,
 }
 
 pub fn find_trade_place(
@@ -107,15 +152,18 @@ pub fn find_trade_place(
 ) -> Option<BuildingID> {
     match target {
         TradeTarget::Soul(id) => binfos.building_owned_by(id),
-        TradeTarget::ExternalTrade => {
-            map.bkinds
-                .get(&BuildingKind::RailFreightStation)
-                .and_then(|b| {
-                    b.iter()
-                        .filter_map(|&bid| map.buildings.get(bid))
-                        .min_by_key(|&b| OrderedFloat(b.door_pos.xy().distance2(pos)))
-                        .map(|x| x.id)
-                })
+        // goods going by rail are loaded on a train at a freight station
+        TradeTarget::ExternalTrade(_, ExternalTradeKind::Rail) => map
+            .bkinds
+            .get(&BuildingKind::RailFreightStation)
+            .and_then(|b| {
+                b.iter()
+                    .filter_map(|&bid| map.buildings.get(bid))
+                    .min_by_key(|&b| OrderedFloat(b.door_pos.xy().distance2(pos)))
+                    .map(|x| x.id)
+            }),
+        TradeTarget::ExternalTrade(building, _) => {
+            map.buildings.contains_key(building).then_some(building)
         }
     }
 }
@@ -141,6 +189,21 @@ impl Market {
         self.markets.iter()
     }
 
+    /// Market price of the item
+    pub fn price(&self, kind: ItemID) -> Money {
+        self.markets
+            .get(&kind)
+            .map(|m| m.price)
+            .unwrap_or(Money::ZERO)
+    }
+
+    /// Adjusts the prices to the orders seen since the last call
+    pub fn update_prices(&mut self) {
+        for market in self.markets.values_mut() {
+            market.update_price();
+        }
+    }
+
     /// Called when an agent tells the world it wants to sell something
     /// If an order is already placed, it will be updated.
     /// Beware that you need capital to sell anything, using produce.
@@ -183,6 +246,16 @@ impl Market {
             .insert(soul, BuyOrder { pos: near, qty });
     }
 
+    /// Removes the buy order of an agent, if any
+    pub fn cancel_buy(&mut self, soul: SoulID, kind: ItemID) {
+        self.m(kind).buy_orders.remove(&soul);
+    }
+
+    /// Removes the sell order of an agent, if any
+    pub fn cancel_sell(&mut self, soul: SoulID, kind: ItemID) {
+        self.m(kind).sell_orders.remove(&soul);
+    }
+
     pub fn buy_until(&mut self, soul: SoulID, near: Vec2, kind: ItemID, qty: u32) {
         let c = self.capital(soul, kind);
         if c >= qty as i32 {
@@ -215,10 +288,22 @@ impl Market {
     /// A trade updates the buy and sell orders from the market, and the capital of the buyers and sellers.
     /// A trade can only be completed if the seller has enough capital.
     /// Please do not keep the trades around much, it needs to be destroyed by the next time you call this function.
-    pub fn make_trades(&mut self) -> &[Trade] {
+    pub fn make_trades(&mut self, external: &mut ExternalTrades) -> &[Trade] {
         self.all_trades.clear();
 
         for (&kind, market) in &mut self.markets {
+            market.demand += market
+                .buy_orders
+                .values()
+                .map(|o| o.qty as u64)
+                .sum::<u64>();
+            market.supply += market
+                .sell_orders
+                .values()
+                .map(|o| o.qty as u64)
+                .sum::<u64>();
+            let price = market.price;
+
             // Naive O(n²) alg
             // We don't immediatly apply the trades, because we want to find the nearest-positioned trades
             for (&seller, sorder) in &market.sell_orders {
@@ -248,7 +333,7 @@ impl Market {
                             seller: TradeTarget::Soul(seller),
                             qty: qty_buy,
                             kind,
-                            money_delta: Money::ZERO,
+                            cost: price * qty_buy as i64,
                         },
                         score,
                     ))
@@ -256,12 +341,13 @@ impl Market {
             }
             self.potential
                 .sort_unstable_by_key(|(_, x)| OrderedFloat(*x));
+            let import_price = market.import_price();
+            let export_price = market.export_price();
             let SingleMarket {
                 buy_orders,
                 sell_orders,
                 capital,
                 optout_exttrade,
-                ext_value,
                 ..
             } = market;
 
@@ -310,24 +396,31 @@ impl Market {
                 }));
 
             // External trading
-            if !*optout_exttrade {
-                // All buyers can fullfil since they can buy externally
-                let btaken = std::mem::take(buy_orders);
-                self.all_trades.reserve(btaken.len());
-                for (buyer, order) in btaken {
+            // All buyers can fullfil since they can buy externally, once the market price
+            // is as high as the import price, as long as a connection can carry their goods
+            if !*optout_exttrade && price >= import_price {
+                let all_trades = &mut self.all_trades;
+                buy_orders.retain(|&buyer, order| {
+                    let imported = external.import(import_price, order.pos, order.qty);
+                    let Some((seller, unit_price)) = imported else {
+                        return true;
+                    };
                     let qty_buy = order.qty as i32;
                     *capital.entry(buyer).or_default() += qty_buy;
 
-                    self.all_trades.push(Trade {
+                    all_trades.push(Trade {
                         buyer: TradeTarget::Soul(buyer),
-                        seller: TradeTarget::ExternalTrade,
+                        seller,
                         qty: qty_buy,
                         kind,
-                        money_delta: -(*ext_value * qty_buy as i64), // we buy from external so we pay
+                        cost: unit_price * qty_buy as i64,
                     });
-                }
+                    false
+                });
+            }
 
-                // Seller surplus goes to external trading
+            // Seller surplus goes to external trading once the market pays no more than outside
+            if !*optout_exttrade && price <= export_price {
                 for (&seller, order) in sell_orders.iter_mut() {
                     let qty_sell = order.qty as i32 - order.stock as i32;
                     if qty_sell <= 0 {
@@ -338,15 +431,19 @@ impl Market {
                         log::warn!("{:?} is selling more than it has: {:?}", &seller, qty_sell);
                         continue;
                     }
+                    let exported = external.export(export_price, order.pos, qty_sell as u32);
+                    let Some((buyer, unit_price)) = exported else {
+                        continue;
+                    };
                     *cap -= qty_sell;
                     order.qty -= qty_sell as u32;
 
                     self.all_trades.push(Trade {
-                        buyer: TradeTarget::ExternalTrade,
+                        buyer,
                         seller: TradeTarget::Soul(seller),
                         qty: qty_sell,
                         kind,
-                        money_delta: *ext_value * qty_sell as i64,
+                        cost: unit_price * qty_sell as i64,
                     });
                 }
             }
@@ -365,10 +462,13 @@ fn calculate_prices(
     companies: &GoodsCompanyRegistry,
     price_multiplier: f32,
 ) -> BTreeMap<ItemID, Money> {
-    let mut item_graph: BTreeMap<ItemID, Vec<GoodsCompanyID>> = BTreeMap::new();
+    // the companies and their recipe producing each item
+    let mut item_graph: BTreeMap<ItemID, Vec<(GoodsCompanyID, usize)>> = BTreeMap::new();
     for (id, company) in companies.descriptions.iter() {
-        for (itemid, _) in &company.recipe.production {
-            item_graph.entry(*itemid).or_default().push(id);
+        for (i, recipe) in company.recipes.iter().enumerate() {
+            for (itemid, _) in &recipe.production {
+                item_graph.entry(*itemid).or_default().push((id, i));
+            }
         }
     }
 
@@ -376,7 +476,7 @@ fn calculate_prices(
     fn calculate_price_inner(
         registry: &ItemRegistry,
         companies: &GoodsCompanyRegistry,
-        item_graph: &BTreeMap<ItemID, Vec<GoodsCompanyID>>,
+        item_graph: &BTreeMap<ItemID, Vec<(GoodsCompanyID, usize)>>,
         item: &Item,
         prices: &mut BTreeMap<ItemID, Money>,
         price_multiplier: f32,
@@ -386,10 +486,11 @@ fn calculate_prices(
         }
 
         let mut minprice = None;
-        for &comp in item_graph.get(&item.id).unwrap_or(&vec![]) {
+        for &(comp, i) in item_graph.get(&item.id).unwrap_or(&vec![]) {
             let company = &companies.descriptions[comp];
+            let recipe = &company.recipes[i];
             let mut price_consumption = Money::ZERO;
-            for &(itemid, qty) in &company.recipe.consumption {
+            for &(itemid, qty) in &recipe.consumption {
                 calculate_price_inner(
                     registry,
                     companies,
@@ -400,16 +501,14 @@ fn calculate_prices(
                 );
                 price_consumption += prices[&itemid] * qty as i64;
             }
-            let qty = company
-                .recipe
+            let qty = recipe
                 .production
                 .iter()
                 .find_map(|x| (x.0 == item.id).then_some(x.1))
                 .unwrap_or(0) as i64;
 
-            let price_workers = company.recipe.complexity as i64
-                * company.n_workers as i64
-                * WORKER_CONSUMPTION_PER_SECOND;
+            let price_workers =
+                recipe.complexity as i64 * company.n_workers as i64 * company.skill.wage();
 
             let newprice = (price_consumption
                 + Money::new_inner((price_workers.inner() as f32 * price_multiplier) as i64))
@@ -437,11 +536,16 @@ fn calculate_prices(
 
 #[cfg(test)]
 mod tests {
-    use super::Market;
-    use crate::economy::{ItemRegistry, WORKER_CONSUMPTION_PER_SECOND};
+    use super::{Market, TradeTarget};
+    use crate::economy::{
+        ExternalTradeKind, ExternalTrades, ItemRegistry, Money, WORKER_CONSUMPTION_PER_SECOND,
+    };
+    use crate::map::{BuildingID, BuildingKind};
     use crate::souls::goods_company::{CompanyKind, GoodsCompanyDescription, Recipe};
+    use crate::tests::TestCtx;
     use crate::world::CompanyID;
-    use crate::{map::BuildingGen, GoodsCompanyRegistry, SoulID};
+    use crate::{map::BuildingGen, Egregoria, GoodsCompanyRegistry, SoulID};
+    use common::saveload::{Bincode, Encoder};
     use geom::{vec2, Vec2};
 
     fn mk_ent(id: u64) -> CompanyID {
@@ -482,7 +586,7 @@ mod tests {
         m.sell(seller, Vec2::X, cereal, 3, 5);
         m.sell(seller_far, vec2(10.0, 10.0), cereal, 3, 5);
 
-        let trades = m.make_trades();
+        let trades = m.make_trades(&mut ExternalTrades::default());
 
         assert_eq!(trades.len(), 1);
         let t0 = trades[0];
@@ -491,6 +595,135 @@ mod tests {
         assert_eq!(t0.qty, 2);
     }
 
+    #[test]
+    fn test_surplus_lowers_price_until_export() {
+        let seller = SoulID::GoodsCompany(mk_ent((1 << 32) | 1));
+
+        let mut registry = ItemRegistry::default();
+        registry.load_item_definitions(
+            r#"
+          [{
+            "name": "cereal",
+            "label": "Cereal"
+          }]
+        "#,
+        );
+        let cereal = registry.id("cereal");
+
+        let mut m = Market::new(&registry, &GoodsCompanyRegistry::default());
+        m.m(cereal).ext_value = Money::new_bucks(1);
+        m.m(cereal).price = Money::new_bucks(1);
+        let export_price = m.m(cereal).export_price();
+
+        let mut external = ExternalTrades::default();
+        let rail = BuildingID::default();
+        external.add(ExternalTradeKind::Rail, rail, Vec2::ZERO);
+
+        m.produce(seller, cereal, 10);
+        m.sell(seller, Vec2::ZERO, cereal, 10, 0);
+
+        let mut last_price = m.price(cereal);
+        for _ in 0..100 {
+            let trades = m.make_trades(&mut external);
+            if let Some(t) = trades.first() {
+                assert_eq!(
+                    t.buyer,
+                    TradeTarget::ExternalTrade(rail, ExternalTradeKind::Rail)
+                );
+                assert_eq!(t.qty, 10);
+                assert_eq!(t.cost, export_price * 10);
+                assert!(m.price(cereal) <= export_price);
+                return;
+            }
+            m.update_prices();
+            assert!(m.price(cereal) < last_price);
+            assert!(m.price(cereal) >= export_price);
+            last_price = m.price(cereal);
+        }
+        panic!("surplus was never exported");
+    }
+
+    #[test]
+    fn test_import_through_best_connection() {
+        let buyer = SoulID::GoodsCompany(mk_ent((1 << 32) | 1));
+
+        let mut registry = ItemRegistry::default();
+        registry.load_item_definitions(
+            r#"
+          [{
+            "name": "cereal",
+            "label": "Cereal"
+          }]
+        "#,
+        );
+        let cereal = registry.id("cereal");
+
+        let mut m = Market::new(&registry, &GoodsCompanyRegistry::default());
+        m.m(cereal).ext_value = Money::new_bucks(1);
+        m.m(cereal).price = Money::new_bucks(1);
+
+        // the rail is the cheapest but far away, the highway is next to the buyer
+        let rail = BuildingID::from(slotmapd::KeyData::from_ffi((1 << 32) | 1));
+        let highway = BuildingID::from(slotmapd::KeyData::from_ffi((1 << 32) | 2));
+        let mut external = ExternalTrades::default();
+        external.add(ExternalTradeKind::Rail, rail, vec2(20000.0, 0.0));
+        external.add(ExternalTradeKind::Highway, highway, vec2(100.0, 0.0));
+        external.get_mut(highway).unwrap().daily_capacity = 15;
+
+        let import = |m: &mut Market, external: &mut ExternalTrades| {
+            m.buy(buyer, Vec2::ZERO, cereal, 10);
+            m.make_trades(external).first().map(|t| (t.seller, t.cost))
+        };
+
+        let (seller, cost) = import(&mut m, &mut external).unwrap();
+        assert_eq!(
+            seller,
+            TradeTarget::ExternalTrade(highway, ExternalTradeKind::Highway)
+        );
+        let highway_price = external
+            .get(highway)
+            .unwrap()
+            .import_price(Money::new_bucks(1), Vec2::ZERO);
+        assert_eq!(cost, highway_price * 10);
+        assert!(cost > Money::new_bucks(10));
+
+        // the highway can only carry 5 more today
+        let (seller, cost) = import(&mut m, &mut external).unwrap();
+        assert_eq!(
+            seller,
+            TradeTarget::ExternalTrade(rail, ExternalTradeKind::Rail)
+        );
+        assert!(cost > highway_price * 10);
+
+        external.get_mut(rail).unwrap().daily_capacity = 0;
+        assert_eq!(import(&mut m, &mut external), None);
+
+        external.new_day(1);
+        let (seller, _) = import(&mut m, &mut external).unwrap();
+        assert_eq!(
+            seller,
+            TradeTarget::ExternalTrade(highway, ExternalTradeKind::Highway)
+        );
+    }
+
+    #[test]
+    fn test_loading_adds_missing_external_connections() {
+        let mut test = TestCtx::new();
+        // built like the external trading of an old save, without its connection
+        test.apply(&[TestCtx::build_special(
+            BuildingKind::ExternalTrading,
+            vec2(50.0, -450.0),
+        )]);
+        let building = test.g.map().bkinds[&BuildingKind::ExternalTrading][0];
+        assert!(test.g.read::<ExternalTrades>().get(building).is_none());
+
+        let ser = Bincode::encode(&test.g).unwrap();
+        let loaded: Egregoria = Bincode::decode(&ser).unwrap();
+        let external = loaded.read::<ExternalTrades>();
+        let c = external.get(building).unwrap();
+        assert_eq!(c.kind, ExternalTradeKind::Rail);
+    }
+
     #[test]
     fn calculate_prices() {
         let mut registry = ItemRegistry::default();
@@ -520,17 +753,20 @@ mod tests {
                 name: "Cereal farm".to_string(),
                 bgen: BuildingGen::House,
                 kind: CompanyKind::Store,
-                recipe: Recipe {
+                recipes: vec![Recipe {
                     production: vec![(cereal, 3)],
                     complexity: 3,
                     consumption: vec![],
                     storage_multiplier: 5,
-                },
+                }],
+                storage: vec![],
                 n_workers: 2,
                 size: 0.0,
                 asset_location: "".to_string(),
                 price: 0,
                 zone: None,
+                schedule: Default::default(),
+                skill: Default::default(),
             });
 
         companies
@@ -540,17 +776,20 @@ mod tests {
                 name: "Wheat factory".to_string(),
                 bgen: BuildingGen::House,
                 kind: CompanyKind::Store,
-                recipe: Recipe {
+                recipes: vec![Recipe {
                     production: vec![(wheat, 2)],
                     complexity: 10,
                     consumption: vec![(cereal, 2)],
                     storage_multiplier: 5,
-                },
+                }],
+                storage: vec![],
                 n_workers: 5,
                 size: 0.0,
                 asset_location: "".to_string(),
                 price: 0,
                 zone: None,
+                schedule: Default::default(),
+                skill: Default::default(),
             });
 
         let prices = super::calculate_prices(&registry, &companies, 1.0);
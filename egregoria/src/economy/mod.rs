@@ -7,6 +7,8 @@
 //! - The market, which is the place where goods are exchanged.
 //! - The government, which is the entity representing the player
 //!
+use crate::mods::Mods;
+use crate::souls::goods_company::CompanyKind;
 use crate::utils::resources::Resources;
 use crate::World;
 use crate::{GoodsCompanyRegistry, SoulID};
@@ -16,20 +18,33 @@ use std::collections::BTreeMap;
 use std::fmt::{Debug, Display, Formatter};
 use std::iter::Sum;
 use std::ops::{Add, AddAssign, Div, Mul, Neg, SubAssign};
+use std::path::Path;
 
 mod ecostats;
+mod external;
 mod government;
 mod item;
+mod ledger;
+mod maintenance;
 mod market;
+mod validation;
+mod wallet;
 
-use crate::utils::time::{Tick, TICKS_PER_SECOND};
+use crate::utils::time::{GameTime, Tick, TICKS_PER_SECOND};
 use crate::world::HumanID;
 pub use ecostats::*;
+pub use external::*;
 pub use government::*;
 pub use item::*;
+pub use ledger::*;
+pub use maintenance::*;
 pub use market::*;
+pub use validation::*;
+pub use wallet::*;
 
-const WORKER_CONSUMPTION_PER_SECOND: Money = Money::new_cents(1);
+/// Paid each second to the workers of basic jobs before the income tax,
+/// and by the government to the unemployed
+pub(crate) const WORKER_CONSUMPTION_PER_SECOND: Money = Money::new_cents(1);
 
 /// Money in cents, can be negative when expressing debt.
 #[derive(Default, Copy, Clone, Serialize, Deserialize, Eq, PartialEq, Ord, PartialOrd)]
@@ -164,25 +179,24 @@ pub struct Bought(pub BTreeMap<ItemID, Vec<Trade>>);
 #[derive(Inspect, Debug, Default, Serialize, Deserialize)]
 pub struct Workers(pub Vec<HumanID>);
 
+/// The directory containing `assets` and `mods`
 #[cfg(not(test))]
-const ITEMS_PATH: &str = "assets/items.json";
-#[cfg(not(test))]
-const COMPANIES_PATH: &str = "assets/companies.json";
-
-#[cfg(test)]
-const ITEMS_PATH: &str = "../assets/items.json";
+const DATA_ROOT: &str = ".";
 #[cfg(test)]
-const COMPANIES_PATH: &str = "../assets/companies.json";
+const DATA_ROOT: &str = "..";
 
 pub fn init_market(_: &mut World, res: &mut Resources) {
+    let mut mods = Mods::load(Path::new(DATA_ROOT));
+    let data = mods.load_data();
+    res.insert(mods);
+
     res.get_mut::<ItemRegistry>()
         .unwrap()
-        .load_item_definitions(&common::saveload::load_string(ITEMS_PATH).unwrap());
+        .add_definitions(data.items);
 
-    res.get_mut::<GoodsCompanyRegistry>().unwrap().load(
-        &common::saveload::load_string(COMPANIES_PATH).unwrap(),
-        &res.get::<ItemRegistry>().unwrap(),
-    );
+    res.get_mut::<GoodsCompanyRegistry>()
+        .unwrap()
+        .add_descriptions(data.companies, &res.get::<ItemRegistry>().unwrap());
 
     let market = Market::new(
         &res.get::<ItemRegistry>().unwrap(),
@@ -195,59 +209,185 @@ pub fn init_market(_: &mut World, res: &mut Resources) {
 
 #[profiling::function]
 pub fn market_update(world: &mut World, resources: &mut Resources) {
-    let n_workers = world.humans.len();
-
     let mut m = resources.get_mut::<Market>().unwrap();
-    let job_opening = resources.get::<ItemRegistry>().unwrap().id("job-opening");
+    let mut external = resources.get_mut::<ExternalTrades>().unwrap();
     let mut gvt = resources.get_mut::<Government>().unwrap();
+    let mut ledger = resources.get_mut::<BudgetLedger>().unwrap();
     let tick = resources.get::<Tick>().unwrap().0;
+    let rates = gvt.taxes;
+
+    external.new_day(resources.get::<GameTime>().unwrap().daytime.day);
+
+    ledger.advance(tick);
 
     if tick % TICKS_PER_SECOND == 0 {
-        gvt.money -= n_workers as i64 * WORKER_CONSUMPTION_PER_SECOND;
+        let benefits = WORKER_CONSUMPTION_PER_SECOND;
+
+        let mut n_unemployed = 0;
+        for h in world.humans.values_mut() {
+            if h.desires.work.is_none() {
+                n_unemployed += 1;
+                h.wallet.earn(benefits);
+            }
+        }
+        gvt.spend(
+            &mut ledger,
+            LedgerCategory::Benefits,
+            n_unemployed * benefits,
+        );
+
+        let mut income_tax = Money::ZERO;
+        let mut corporate_tax = Money::ZERO;
+        for c in world.companies.values_mut() {
+            let wage = c.comp.skill.wage();
+            let tax = rates.levy(Tax::Income, wage);
+            let wages = c.workers.0.len() as i64 * wage;
+            // the city runs the warehouses, they don't make anything to pay their workers with
+            if matches!(c.comp.kind, CompanyKind::Warehouse { .. }) {
+                gvt.spend(&mut ledger, LedgerCategory::Warehouses, wages);
+            } else {
+                c.comp.pay_wages(wages);
+            }
+            for &worker in &c.workers.0 {
+                if let Some(h) = world.humans.get_mut(worker) {
+                    income_tax += tax;
+                    h.wallet.earn(wage - tax);
+                }
+            }
+            corporate_tax += c.comp.pay_corporate_tax(&rates);
+        }
+        gvt.earn(&mut ledger, LedgerCategory::IncomeTax, income_tax);
+        gvt.earn(&mut ledger, LedgerCategory::CorporateTax, corporate_tax);
+
+        m.update_prices();
     }
 
-    let trades = m.make_trades();
+    let mut stats = resources.get_mut::<EcoStats>().unwrap();
+    stats.prices.advance(tick, &m);
 
-    resources
-        .get_mut::<EcoStats>()
-        .unwrap()
-        .advance(tick, trades);
+    let trades = m.make_trades(&mut external);
+
+    stats.advance(tick, trades);
 
     for &trade in trades.iter() {
         log::debug!("A trade was made! {:?}", trade);
 
-        if trade.kind == job_opening {
-            if let SoulID::GoodsCompany(id) = trade.seller.soul() {
-                let comp = world.companies.get_mut(id).unwrap();
-                comp.workers.0.push(trade.buyer.soul().try_into().unwrap())
+        // exporters get the cost minus the tariff, importers pay it on top of the cost
+        let tariff = if trade.buyer.is_external() || trade.seller.is_external() {
+            rates.levy(Tax::Tariff, trade.cost)
+        } else {
+            Money::ZERO
+        };
+        let price = match trade.seller {
+            TradeTarget::ExternalTrade(..) => trade.cost + tariff,
+            TradeTarget::Soul(_) => trade.cost,
+        };
+
+        // the buyer pays first, so that the seller only gets what was actually paid
+        let paid = match trade.buyer {
+            TradeTarget::Soul(SoulID::Human(id)) => match world.humans.get_mut(id) {
+                Some(h) => {
+                    let spending = if trade.kind == h.desires.food.item() {
+                        Spending::Food
+                    } else {
+                        Spending::Goods
+                    };
+                    h.bought.0.entry(trade.kind).or_default().push(trade);
+                    h.wallet.pay(spending, price)
+                }
+                None => Money::ZERO,
+            },
+            TradeTarget::Soul(SoulID::GoodsCompany(id)) => match world.companies.get_mut(id) {
+                Some(c) => {
+                    c.comp.pay(price);
+                    c.bought.0.entry(trade.kind).or_default().push(trade);
+                    price
+                }
+                None => Money::ZERO,
+            },
+            TradeTarget::Soul(SoulID::FreightStation(_) | SoulID::TrainStation(_)) => {
+                gvt.spend(&mut ledger, LedgerCategory::Purchases, price);
+                price
             }
-        }
-        gvt.money += trade.money_delta;
+            TradeTarget::ExternalTrade(..) => trade.cost,
+        };
+
+        let (tariff, received) = match (trade.buyer, trade.seller) {
+            (TradeTarget::ExternalTrade(..), _) => (tariff, paid - tariff),
+            // the tariff is taken first on what an importer could pay
+            (_, TradeTarget::ExternalTrade(..)) => (paid.min(tariff), Money::ZERO),
+            _ => (Money::ZERO, paid),
+        };
+        gvt.earn(&mut ledger, LedgerCategory::Tariffs, tariff);
 
         match trade.seller {
             TradeTarget::Soul(id) => {
-                if trade.kind != job_opening {
-                    if let SoulID::GoodsCompany(id) = id {
-                        world.companies.get_mut(id).unwrap().sold.0.push(trade);
+                if let SoulID::GoodsCompany(id) = id {
+                    if let Some(c) = world.companies.get_mut(id) {
+                        c.comp.earn(received);
+                        c.sold.0.push(trade);
                     }
+                } else {
+                    gvt.earn(&mut ledger, LedgerCategory::Sales, received);
                 }
             }
-            TradeTarget::ExternalTrade => {}
+            TradeTarget::ExternalTrade(..) => {}
         }
+    }
+}
 
-        match trade.buyer {
-            TradeTarget::Soul(SoulID::Human(id)) => {
-                if let Some(h) = world.humans.get_mut(id) {
-                    h.bought.0.entry(trade.kind).or_default().push(trade);
-                }
-            }
-            TradeTarget::Soul(SoulID::GoodsCompany(id)) => {
-                if let Some(c) = world.companies.get_mut(id) {
-                    c.bought.0.entry(trade.kind).or_default().push(trade)
-                }
-            }
-            TradeTarget::Soul(SoulID::FreightStation(_)) => {}
-            TradeTarget::ExternalTrade => {}
+#[cfg(test)]
+mod tests {
+    use super::{market_update, Government, ItemRegistry, Market, Money, TradeTarget, Wallet};
+    use crate::tests::TestCtx;
+    use crate::utils::time::Tick;
+    use crate::{BuildingKind, Egregoria, SoulID};
+    use geom::vec2;
+
+    fn total_money(goria: &Egregoria) -> Money {
+        let humans: Money = goria
+            .world()
+            .humans
+            .values()
+            .map(|h| h.wallet.total())
+            .sum();
+        let companies: Money = goria.world().companies.values().map(|c| c.comp.money).sum();
+        humans + companies + goria.read::<Government>().money
+    }
+
+    #[test]
+    fn test_short_buyer_does_not_create_money() {
+        let mut test = TestCtx::new();
+
+        let (_, human) = test.build_house_with_resident();
+        let bakery = test.company_kind("Bakery");
+        test.apply(&[TestCtx::build_special(
+            BuildingKind::GoodsCompany(bakery),
+            vec2(50.0, -550.0),
+        )]);
+        test.tick();
+        let company = test.g.world().companies.keys().next().unwrap();
+
+        let bread = test.g.read::<ItemRegistry>().id("bread");
+        test.g.world.humans.get_mut(human).unwrap().wallet = Wallet::new(Money::new_cents(1));
+        {
+            let mut market = test.g.write::<Market>();
+            let seller = SoulID::GoodsCompany(company);
+            market.produce(seller, bread, 1);
+            market.sell(seller, vec2(50.0, -550.0), bread, 1, 1);
+            market.buy(SoulID::Human(human), vec2(50.0, -450.0), bread, 1);
+            assert!(market.price(bread) > Money::new_cents(1));
         }
+        // not a whole second, so no wages nor benefits
+        *test.g.write::<Tick>() = Tick(1);
+
+        let before = total_money(&test.g);
+        let (world, res) = test.g.world_res();
+        market_update(world, res);
+
+        let c = &test.g.world().companies[company];
+        assert_eq!(c.sold.0.len(), 1);
+        assert_eq!(c.sold.0[0].buyer, TradeTarget::Soul(SoulID::Human(human)));
+        assert_eq!(total_money(&test.g), before);
     }
 }
@@ -0,0 +1,328 @@
+//! Checks of the items.json and companies.json data files.
+//!
+//! Loading the data only logs and skips what it can't use, so a mistake in a modded file shows
+//! up as a missing company in game. The validation lists every problem at once instead.
+
+use crate::economy::ItemDefinition;
+use crate::mods::Mods;
+use crate::souls::goods_company::{GoodsCompanyDescriptionJSON, RecipeDescription};
+use common::saveload::Encoder;
+use std::collections::BTreeSet;
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+
+pub const ITEMS_FILE: &str = "assets/items.json";
+pub const COMPANIES_FILE: &str = "assets/companies.json";
+
+/// Items the simulation looks up by name, so they must always be defined
+const REQUIRED_ITEMS: [&str; 2] = ["bread", "electricity"];
+
+/// Items no longer used, still defined so that the ids of the next items stay the same in saves
+const PLACEHOLDER_ITEMS: [&str; 1] = ["job-opening"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DataError {
+    /// The file couldn't be read or isn't valid json
+    Parse {
+        file: String,
+        error: String,
+    },
+    DuplicateItem(String),
+    DuplicateCompany(String),
+    MissingRequiredItem(String),
+    UnknownItem {
+        company: String,
+        item: String,
+    },
+    InvalidQuantity {
+        company: String,
+        item: String,
+        qty: i32,
+    },
+    NoRecipe(String),
+    /// A warehouse that doesn't store anything
+    NoStorage(String),
+    UnknownCompanyKind {
+        company: String,
+        kind: String,
+    },
+    MissingTrucks(String),
+    InvalidBuildingGen {
+        company: String,
+        kind: String,
+    },
+    /// A schedule without shifts or days, or with hours that aren't in a day
+    InvalidSchedule(String),
+    MissingAsset {
+        company: String,
+        asset: String,
+    },
+    UnproducibleItem(String),
+    /// A mod refers to something that doesn't exist
+    Mod {
+        name: String,
+        error: String,
+    },
+}
+
+impl Display for DataError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DataError::Parse { file, error } => write!(f, "couldn't parse {file}: {error}"),
+            DataError::DuplicateItem(item) => write!(f, "item {item} is defined twice"),
+            DataError::DuplicateCompany(company) => {
+                write!(f, "company {company} is defined twice")
+            }
+            DataError::MissingRequiredItem(item) => {
+                write!(f, "item {item} is required by the game but isn't defined")
+            }
+            DataError::UnknownItem { company, item } => {
+                write!(f, "company {company} uses the unknown item {item}")
+            }
+            DataError::InvalidQuantity { company, item, qty } => {
+                write!(
+                    f,
+                    "company {company} uses {qty} {item}, it must be positive"
+                )
+            }
+            DataError::NoRecipe(company) => write!(f, "company {company} has no recipe"),
+            DataError::NoStorage(company) => write!(f, "warehouse {company} stores nothing"),
+            DataError::UnknownCompanyKind { company, kind } => {
+                write!(f, "company {company} has the unknown kind {kind}")
+            }
+            DataError::MissingTrucks(company) => {
+                write!(f, "company {company} has trucks but no n_trucks")
+            }
+            DataError::InvalidBuildingGen { company, kind } => write!(
+                f,
+                "company {company} has the unknown building gen {kind} or is missing its parameters"
+            ),
+            DataError::InvalidSchedule(company) => write!(
+                f,
+                "company {company} has a schedule without shifts or days, or with invalid hours"
+            ),
+            DataError::MissingAsset { company, asset } => {
+                write!(f, "company {company} uses the missing asset {asset}")
+            }
+            DataError::UnproducibleItem(item) => write!(f, "no recipe produces item {item}"),
+            DataError::Mod { name, error } => write!(f, "mod {name}: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for DataError {}
+
+/// Validates the data files found under the `root` directory, the one containing `assets`,
+/// with the mods found there applied
+pub fn validate_data_files(root: &Path) -> Vec<DataError> {
+    validate_mods(&mut Mods::load(root))
+}
+
+/// Validates the data of the game once the mods are applied, the conflicts between the mods
+/// are left in `mods`
+pub fn validate_mods(mods: &mut Mods) -> Vec<DataError> {
+    let data = mods.load_data();
+    let mut errors = std::mem::take(&mut mods.errors);
+    errors.extend(validate_definitions(&data.items, &data.companies, mods));
+    errors
+}
+
+/// Validates the content of items.json and companies.json.
+/// Assets are looked up under `root`, the .glb models being in assets/models.
+pub fn validate_data(items: &str, companies: &str, root: &Path) -> Vec<DataError> {
+    let mut errors = vec![];
+
+    let items: Vec<ItemDefinition> = match common::saveload::JSON::decode(items.as_ref()) {
+        Ok(x) => x,
+        Err(e) => {
+            errors.push(DataError::Parse {
+                file: ITEMS_FILE.to_string(),
+                error: e.to_string(),
+            });
+            vec![]
+        }
+    };
+    let companies: Vec<GoodsCompanyDescriptionJSON> =
+        match common::saveload::JSON::decode(companies.as_ref()) {
+            Ok(x) => x,
+            Err(e) => {
+                errors.push(DataError::Parse {
+                    file: COMPANIES_FILE.to_string(),
+                    error: e.to_string(),
+                });
+                vec![]
+            }
+        };
+
+    errors.extend(validate_definitions(&items, &companies, &Mods::load(root)));
+    errors
+}
+
+fn validate_definitions(
+    items: &[ItemDefinition],
+    companies: &[GoodsCompanyDescriptionJSON],
+    mods: &Mods,
+) -> Vec<DataError> {
+    let mut errors = vec![];
+
+    let mut item_names = BTreeSet::new();
+    for item in items {
+        if !item_names.insert(item.name.as_str()) {
+            errors.push(DataError::DuplicateItem(item.name.clone()));
+        }
+    }
+    for required in REQUIRED_ITEMS.into_iter().chain(PLACEHOLDER_ITEMS) {
+        if !item_names.contains(required) {
+            errors.push(DataError::MissingRequiredItem(required.to_string()));
+        }
+    }
+
+    let mut company_names = BTreeSet::new();
+    let mut produced: BTreeSet<&str> = PLACEHOLDER_ITEMS.into_iter().collect();
+    for descr in companies {
+        let company = &descr.name;
+        if !company_names.insert(company.as_str()) {
+            errors.push(DataError::DuplicateCompany(company.clone()));
+        }
+
+        match descr.kind.as_ref() {
+            "store" | "network" => {}
+            "factory" | "warehouse" => {
+                if descr.n_trucks.is_none() {
+                    errors.push(DataError::MissingTrucks(company.clone()));
+                }
+            }
+            _ => errors.push(DataError::UnknownCompanyKind {
+                company: company.clone(),
+                kind: descr.kind.clone(),
+            }),
+        }
+
+        if descr.bgen.resolve().is_none() {
+            errors.push(DataError::InvalidBuildingGen {
+                company: company.clone(),
+                kind: descr.bgen.kind.clone(),
+            });
+        }
+
+        let schedule = descr.schedule.as_ref();
+        if schedule.is_some_and(|s| s.resolve().is_none()) {
+            errors.push(DataError::InvalidSchedule(company.clone()));
+        }
+
+        let recipes: Vec<&RecipeDescription> = descr.recipe.iter().chain(&descr.recipes).collect();
+        if descr.kind == "warehouse" {
+            if descr.storage.is_empty() {
+                errors.push(DataError::NoStorage(company.clone()));
+            }
+        } else if recipes.is_empty() {
+            errors.push(DataError::NoRecipe(company.clone()));
+        }
+
+        let quantities = recipes
+            .iter()
+            .flat_map(|r| r.consumption.iter().chain(&r.production))
+            .chain(&descr.storage);
+        for (item, qty) in quantities {
+            if !item_names.contains(item.as_str()) {
+                errors.push(DataError::UnknownItem {
+                    company: company.clone(),
+                    item: item.clone(),
+                });
+            }
+            if *qty <= 0 {
+                errors.push(DataError::InvalidQuantity {
+                    company: company.clone(),
+                    item: item.clone(),
+                    qty: *qty,
+                });
+            }
+        }
+        for recipe in recipes {
+            produced.extend(recipe.production.iter().map(|(item, _)| item.as_str()));
+        }
+
+        let zone_assets = descr
+            .zone
+            .iter()
+            .flat_map(|z| [z.floor.as_str(), z.filler.as_str()]);
+        for asset in std::iter::once(descr.asset_location.as_str()).chain(zone_assets) {
+            if !mods.asset_path(asset).is_file() {
+                errors.push(DataError::MissingAsset {
+                    company: company.clone(),
+                    asset: asset.to_string(),
+                });
+            }
+        }
+    }
+
+    for item in items {
+        if !produced.contains(item.name.as_str()) {
+            errors.push(DataError::UnproducibleItem(item.name.clone()));
+        }
+    }
+
+    errors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{validate_data, validate_data_files, DataError};
+    use std::path::Path;
+
+    #[test]
+    fn test_validate_data() {
+        let root = Path::new("..");
+        assert_eq!(validate_data_files(root), vec![]);
+
+        let items = r#"[
+            {"name": "job-opening", "label": "Job opening", "optout_exttrade": true},
+            {"name": "bread", "label": "Bread"},
+            {"name": "bread", "label": "Bread"},
+            {"name": "cereal", "label": "Cereal"}
+        ]"#;
+        let companies = r#"[{
+            "name": "Bakery",
+            "bgen": {"kind": "centered_door"},
+            "kind": "factory",
+            "recipe": {
+                "consumption": [["flour", 1]],
+                "production": [["bread", -1]],
+                "complexity": 100,
+                "storage_multiplier": 5
+            },
+            "n_workers": 3,
+            "size": 10.0,
+            "asset_location": "missing.glb",
+            "price": 1000,
+            "schedule": {"shifts": [[22, 24]]}
+        }]"#;
+
+        let errors = validate_data(items, companies, root);
+        let expected = [
+            DataError::DuplicateItem("bread".to_string()),
+            DataError::MissingRequiredItem("electricity".to_string()),
+            DataError::MissingTrucks("Bakery".to_string()),
+            DataError::InvalidBuildingGen {
+                company: "Bakery".to_string(),
+                kind: "centered_door".to_string(),
+            },
+            DataError::InvalidSchedule("Bakery".to_string()),
+            DataError::UnknownItem {
+                company: "Bakery".to_string(),
+                item: "flour".to_string(),
+            },
+            DataError::InvalidQuantity {
+                company: "Bakery".to_string(),
+                item: "bread".to_string(),
+                qty: -1,
+            },
+            DataError::MissingAsset {
+                company: "Bakery".to_string(),
+                asset: "missing.glb".to_string(),
+            },
+            DataError::UnproducibleItem("cereal".to_string()),
+        ];
+        assert_eq!(errors, expected);
+    }
+}
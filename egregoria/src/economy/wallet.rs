@@ -0,0 +1,121 @@
+use crate::economy::Money;
+use egui_inspect::Inspect;
+use serde::{Deserialize, Serialize};
+
+/// Savings a new household starts with
+pub const STARTING_SAVINGS: Money = Money::new_bucks(10);
+
+/// What a household spends its money on
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Spending {
+    Food,
+    /// Everything else, like the utilities of the house
+    Goods,
+}
+
+/// How a household splits its income, what is left is saved
+#[derive(Inspect, Debug, Copy, Clone, Serialize, Deserialize)]
+pub struct Budget {
+    /// Share of the income set aside for food, in [0; 1]
+    pub food: f32,
+    /// Share of the income set aside for other goods, in [0; 1]
+    pub goods: f32,
+}
+
+impl Default for Budget {
+    fn default() -> Self {
+        Self {
+            food: 0.5,
+            goods: 0.3,
+        }
+    }
+}
+
+/// Money of a household, split according to its [`Budget`].
+/// Food is essential so it can also be paid from the savings when its share runs out.
+#[derive(Inspect, Debug, Clone, Serialize, Deserialize)]
+pub struct Wallet {
+    pub budget: Budget,
+    pub savings: Money,
+    /// What is left to spend on food
+    pub food: Money,
+    /// What is left to spend on other goods
+    pub goods: Money,
+}
+
+impl Wallet {
+    pub fn new(savings: Money) -> Self {
+        Self {
+            budget: Budget::default(),
+            savings,
+            food: Money::ZERO,
+            goods: Money::ZERO,
+        }
+    }
+
+    pub fn total(&self) -> Money {
+        self.savings + self.food + self.goods
+    }
+
+    pub fn earn(&mut self, income: Money) {
+        let share = |s: f32| Money::new_inner((income.inner() as f64 * s as f64) as i64);
+        let food = share(self.budget.food);
+        let goods = share(self.budget.goods);
+        self.food += food;
+        self.goods += goods;
+        self.savings += income - food - goods;
+    }
+
+    /// What can be spent right now for the given kind of spending
+    pub fn available(&self, kind: Spending) -> Money {
+        match kind {
+            Spending::Food => self.food + self.savings,
+            Spending::Goods => self.goods,
+        }
+    }
+
+    pub fn can_afford(&self, kind: Spending, cost: Money) -> bool {
+        self.available(kind) >= cost
+    }
+
+    /// Pays from the share of the given kind of spending, the rest comes from the savings.
+    /// The price can go up between the order and the trade, so what the wallet can't cover is
+    /// written off rather than putting it in debt. Returns what was actually paid.
+    pub fn pay(&mut self, kind: Spending, cost: Money) -> Money {
+        let share = match kind {
+            Spending::Food => &mut self.food,
+            Spending::Goods => &mut self.goods,
+        };
+        let from_share = cost.min(*share).max(Money::ZERO);
+        *share -= from_share;
+        let from_savings = (cost - from_share).min(self.savings).max(Money::ZERO);
+        self.savings -= from_savings;
+        from_share + from_savings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Spending, Wallet};
+    use crate::economy::Money;
+
+    #[test]
+    fn test_pay_never_goes_in_debt() {
+        let mut w = Wallet::new(Money::new_bucks(10));
+        w.earn(Money::new_bucks(10));
+        assert_eq!(w.goods, Money::new_bucks(3));
+
+        assert_eq!(
+            w.pay(Spending::Goods, Money::new_bucks(5)),
+            Money::new_bucks(5)
+        );
+        assert_eq!(w.goods, Money::ZERO);
+        assert_eq!(w.savings, Money::new_bucks(10));
+
+        assert_eq!(
+            w.pay(Spending::Food, Money::new_bucks(100)),
+            Money::new_bucks(15)
+        );
+        assert_eq!(w.total(), Money::ZERO);
+    }
+}
@@ -1,14 +1,18 @@
-use crate::economy::Government;
+use crate::economy::{
+    BudgetLedger, ExternalTradeKind, ExternalTrades, Government, ItemID, Market, Tax,
+};
 use crate::map::procgen::{load_parismap, load_testfield};
 use crate::map::{
     BuildingGen, BuildingID, BuildingKind, IntersectionID, LaneID, LanePattern, LanePatternBuilder,
-    LightPolicy, LotID, Map, MapProject, ProjectKind, RoadID, Terrain, TurnPolicy, Zone,
+    LaneTurns, LightPolicy, LotID, Map, MapProject, ProjectKind, RoadID, Terrain, TurnPolicy, Zone,
 };
-use crate::map_dynamic::BuildingInfos;
+use crate::map_dynamic::{evict_occupants, BuildingInfos};
+use crate::transportation::bus::{remove_buses, BusLineID, BusLines, BusStopID};
 use crate::transportation::train::{spawn_train, RailWagonKind};
 use crate::utils::time::{GameTime, Tick};
-use crate::{Egregoria, EgregoriaOptions, Replay};
-use geom::{vec3, Vec2, OBB};
+use crate::world::HumanEnt;
+use crate::{Egregoria, EgregoriaOptions, ParCommandBuffer, Replay, SoulID};
+use geom::{vec3, Vec2, Vec3, OBB};
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 use std::time::Instant;
@@ -32,6 +36,8 @@ pub enum WorldCommand {
         dist: f32,
         n_wagons: u32,
         lane: LaneID,
+        #[serde(default)]
+        kind: RailWagonKind,
     },
     MapMakeConnection {
         from: MapProject,
@@ -48,6 +54,17 @@ pub enum WorldCommand {
         turn: TurnPolicy,
         light: LightPolicy,
     },
+    /// Sets the light offsets along a chain of intersections for a platoon going at `speed` m/s
+    MapGreenWave {
+        path: Vec<IntersectionID>,
+        speed: f32,
+    },
+    /// Restricts where vehicles coming from `lane` may go at `inter`
+    MapSetLaneTurns {
+        inter: IntersectionID,
+        lane: LaneID,
+        turns: LaneTurns,
+    },
     MapBuildSpecialBuilding {
         pos: OBB,
         kind: BuildingKind,
@@ -55,6 +72,12 @@ pub enum WorldCommand {
         #[serde(default)]
         zone: Option<Zone>,
     },
+    /// Builds a connection to the outside through which goods are imported and exported
+    MapBuildExternalConnection {
+        pos: OBB,
+        kind: ExternalTradeKind,
+        gen: BuildingGen,
+    },
     MapLoadParis,
     MapLoadTestField {
         pos: Vec2,
@@ -67,6 +90,28 @@ pub enum WorldCommand {
     },
     ResetSave,
     SetGameTime(GameTime),
+    AddBusStop(Vec3),
+    RemoveBusStop(BusStopID),
+    AddBusLine {
+        stops: Vec<BusStopID>,
+        interval: f64,
+    },
+    RemoveBusLine(BusLineID),
+    SetTaxRate {
+        tax: Tax,
+        rate: f32,
+    },
+    /// Picks the recipe of the company in the building, None lets it choose
+    SetCompanyRecipe {
+        building: BuildingID,
+        recipe: Option<usize>,
+    },
+    /// Sets how much of an item the warehouse in the building can store
+    SetWarehouseCapacity {
+        building: BuildingID,
+        item: ItemID,
+        capacity: i32,
+    },
 }
 
 impl AsRef<[WorldCommand]> for WorldCommands {
@@ -112,11 +157,12 @@ impl WorldCommands {
         self.commands.push(SetGameTime(gt))
     }
 
-    pub fn add_train(&mut self, dist: f32, n_wagons: u32, laneid: LaneID) {
+    pub fn add_train(&mut self, dist: f32, n_wagons: u32, laneid: LaneID, kind: RailWagonKind) {
         self.commands.push(AddTrain {
             dist,
             n_wagons,
             lane: laneid,
+            kind,
         })
     }
 
@@ -135,6 +181,19 @@ impl WorldCommands {
         })
     }
 
+    pub fn map_build_external_connection(
+        &mut self,
+        obb: OBB,
+        kind: ExternalTradeKind,
+        gen: BuildingGen,
+    ) {
+        self.commands.push(MapBuildExternalConnection {
+            pos: obb,
+            kind,
+            gen,
+        })
+    }
+
     pub fn map_remove_intersection(&mut self, id: IntersectionID) {
         self.commands.push(MapRemoveIntersection(id))
     }
@@ -166,6 +225,22 @@ impl WorldCommands {
         })
     }
 
+    pub fn add_bus_stop(&mut self, pos: Vec3) {
+        self.commands.push(AddBusStop(pos))
+    }
+
+    pub fn remove_bus_stop(&mut self, id: BusStopID) {
+        self.commands.push(RemoveBusStop(id))
+    }
+
+    pub fn add_bus_line(&mut self, stops: Vec<BusStopID>, interval: f64) {
+        self.commands.push(AddBusLine { stops, interval })
+    }
+
+    pub fn remove_bus_line(&mut self, id: BusLineID) {
+        self.commands.push(RemoveBusLine(id))
+    }
+
     pub fn map_update_intersection_policy(
         &mut self,
         id: IntersectionID,
@@ -178,6 +253,30 @@ impl WorldCommands {
             light: lp,
         })
     }
+
+    pub fn map_green_wave(&mut self, path: Vec<IntersectionID>, speed: f32) {
+        self.commands.push(MapGreenWave { path, speed })
+    }
+
+    pub fn map_set_lane_turns(&mut self, inter: IntersectionID, lane: LaneID, turns: LaneTurns) {
+        self.commands.push(MapSetLaneTurns { inter, lane, turns })
+    }
+
+    pub fn set_tax_rate(&mut self, tax: Tax, rate: f32) {
+        self.commands.push(SetTaxRate { tax, rate })
+    }
+
+    pub fn set_company_recipe(&mut self, building: BuildingID, recipe: Option<usize>) {
+        self.commands.push(SetCompanyRecipe { building, recipe })
+    }
+
+    pub fn set_warehouse_capacity(&mut self, building: BuildingID, item: ItemID, capacity: i32) {
+        self.commands.push(SetWarehouseCapacity {
+            building,
+            item,
+            capacity,
+        })
+    }
 }
 
 impl WorldCommand {
@@ -187,14 +286,23 @@ impl WorldCommand {
             self,
             MapBuildHouse(_)
                 | MapUpdateIntersectionPolicy { .. }
+                | MapGreenWave { .. }
+                | MapSetLaneTurns { .. }
                 | UpdateZone { .. }
                 | SetGameTime(_)
+                | SetTaxRate { .. }
+                | SetCompanyRecipe { .. }
+                | SetWarehouseCapacity { .. }
         )
     }
 
     pub fn apply(&self, goria: &mut Egregoria) {
         let cost = Government::action_cost(self, goria);
-        goria.write::<Government>().money -= cost;
+        let mut ledger = goria.write::<BudgetLedger>();
+        goria
+            .write::<Government>()
+            .spend(&mut ledger, Government::action_category(self), cost);
+        drop(ledger);
 
         let mut rep = goria.resources.get_mut::<Replay>().unwrap();
         if rep.enabled {
@@ -206,7 +314,7 @@ impl WorldCommand {
         match *self {
             MapRemoveIntersection(id) => goria.map_mut().remove_intersection(id),
             MapRemoveRoad(id) => drop(goria.map_mut().remove_road(id)),
-            MapRemoveBuilding(id) => drop(goria.map_mut().remove_building(id)),
+            MapRemoveBuilding(id) => remove_building(goria, id),
             MapBuildHouse(id) => {
                 if let Some(build) = goria.map_mut().build_house(id) {
                     let mut infos = goria.write::<BuildingInfos>();
@@ -248,11 +356,31 @@ impl WorldCommand {
             MapUpdateIntersectionPolicy {
                 inter: id,
                 turn: tp,
-                light: lp,
-            } => goria.map_mut().update_intersection(id, move |i| {
-                i.light_policy = lp;
-                i.turn_policy = tp;
-            }),
+                light: ref lp,
+            } => {
+                if matches!(lp, LightPolicy::Custom(phases) if phases.is_empty()) {
+                    log::warn!("custom light plan of {:?} has no phase", id);
+                    return;
+                }
+                goria.map_mut().update_intersection(id, move |i| {
+                    i.light_policy = lp.clone();
+                    i.turn_policy = tp;
+                })
+            }
+            MapGreenWave { ref path, speed } => goria.map_mut().set_green_wave(path, speed),
+            MapSetLaneTurns { inter, lane, turns } => {
+                if turns == LaneTurns::NONE {
+                    log::warn!("lane turns of {:?} would allow no direction", lane);
+                    return;
+                }
+                goria.map_mut().update_intersection(inter, move |i| {
+                    if turns == LaneTurns::ALL {
+                        i.lane_turns.remove(&lane);
+                    } else {
+                        i.lane_turns.insert(lane, turns);
+                    }
+                })
+            }
             MapBuildSpecialBuilding {
                 pos: obb,
                 kind,
@@ -267,13 +395,21 @@ impl WorldCommand {
                     goria.write::<BuildingInfos>().insert(id);
                 }
             }
+            MapBuildExternalConnection {
+                pos: obb,
+                kind,
+                gen,
+            } => {
+                build_external_connection(goria, &obb, kind, gen);
+            }
             SetGameTime(gt) => *goria.write::<GameTime>() = gt,
             AddTrain {
                 dist,
                 n_wagons,
                 lane,
+                kind,
             } => {
-                spawn_train(goria, dist, n_wagons, lane, RailWagonKind::Freight);
+                spawn_train(goria, dist, n_wagons, lane, kind);
             }
             MapLoadParis => load_parismap(&mut goria.map_mut()),
             MapLoadTestField { pos, size, spacing } => {
@@ -304,6 +440,60 @@ impl WorldCommand {
 
                 map.update_zone(building, move |z| *z = zone.clone());
             }
+            AddBusStop(pos) => {
+                let map = goria.map();
+                goria.write::<BusLines>().add_stop(&map, pos);
+            }
+            RemoveBusStop(id) => {
+                let buses = goria.write::<BusLines>().remove_stop(id);
+                remove_buses(goria, &buses);
+            }
+            AddBusLine {
+                ref stops,
+                interval,
+            } => {
+                goria.write::<BusLines>().add_line(stops.clone(), interval);
+            }
+            SetTaxRate { tax, rate } => goria.write::<Government>().taxes.set(tax, rate),
+            SetCompanyRecipe { building, recipe } => {
+                let owner = goria.read::<BuildingInfos>().owner(building);
+                let Some(SoulID::GoodsCompany(id)) = owner else {
+                    return;
+                };
+                let door = goria.map().buildings().get(building).map(|b| b.door_pos);
+                let Some(door) = door else { return };
+                let (world, res) = goria.world_res();
+                let Some(c) = world.companies.get_mut(id) else {
+                    return;
+                };
+                c.comp.auto_recipe = recipe.is_none();
+                if let Some(i) = recipe {
+                    let market = &mut res.get_mut::<Market>().unwrap();
+                    c.comp
+                        .set_recipe(i, SoulID::GoodsCompany(id), door.xy(), market);
+                }
+            }
+            SetWarehouseCapacity {
+                building,
+                item,
+                capacity,
+            } => {
+                let owner = goria.read::<BuildingInfos>().owner(building);
+                let Some(SoulID::GoodsCompany(id)) = owner else {
+                    return;
+                };
+                let Some(c) = goria.world.companies.get_mut(id) else {
+                    return;
+                };
+                c.comp.set_capacity(item, capacity);
+                goria
+                    .write::<Market>()
+                    .register(SoulID::GoodsCompany(id), item);
+            }
+            RemoveBusLine(id) => {
+                let buses = goria.write::<BusLines>().remove_line(id);
+                remove_buses(goria, &buses);
+            }
         }
     }
 }
@@ -329,22 +519,45 @@ fn generate_terrain(goria: &mut Egregoria, size: u32) {
         &pat,
     );
 
-    if goria
-        .map_mut()
-        .build_special_building(
-            &obb,
-            BuildingKind::ExternalTrading,
-            BuildingGen::NoWalkway {
-                door_pos: Vec2::ZERO,
-            },
-            None,
-        )
-        .is_none()
-    {
+    let gen = BuildingGen::NoWalkway {
+        door_pos: Vec2::ZERO,
+    };
+    if !build_external_connection(goria, &obb, ExternalTradeKind::Rail, gen) {
         log::error!("failed to build external trading");
     }
 }
 
+fn build_external_connection(
+    goria: &mut Egregoria,
+    obb: &OBB,
+    kind: ExternalTradeKind,
+    gen: BuildingGen,
+) -> bool {
+    let Some(id) =
+        goria
+            .map_mut()
+            .build_special_building(obb, BuildingKind::ExternalTrading, gen, None)
+    else {
+        return false;
+    };
+    goria.write::<BuildingInfos>().insert(id);
+    goria.write::<ExternalTrades>().add(kind, id, obb.center());
+    true
+}
+
+/// Removes a building along with what refers to it, the humans inside are put out on the street
+pub(crate) fn remove_building(goria: &mut Egregoria, id: BuildingID) {
+    let door_pos = goria.map().buildings().get(id).map(|b| b.door_pos);
+    if let Some(door_pos) = door_pos {
+        let (world, res) = goria.world_res();
+        let cbuf = res.get::<ParCommandBuffer<HumanEnt>>().unwrap();
+        evict_occupants(world, &cbuf, id, door_pos);
+    }
+    drop(goria.map_mut().remove_building(id));
+    goria.write::<ExternalTrades>().remove(id);
+    goria.write::<BuildingInfos>().remove(id);
+}
+
 impl FromIterator<WorldCommands> for WorldCommands {
     fn from_iter<T: IntoIterator<Item = WorldCommands>>(iter: T) -> Self {
         Self {
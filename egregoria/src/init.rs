@@ -1,13 +1,23 @@
-use crate::economy::{init_market, market_update, EcoStats, Government, ItemRegistry, Market};
+use crate::economy::{
+    init_market, maintenance_system, market_update, BudgetLedger, EcoStats, ExternalTrades,
+    Government, ItemRegistry, Maintenance, Market,
+};
 use crate::map::Map;
 use crate::map_dynamic::{
-    dispatch_system, itinerary_update, routing_changed_system, routing_update_system,
-    BuildingInfos, Dispatcher, ParkingManagement,
+    actuated_lights_system, congestion_system, dispatch_system, itinerary_update,
+    routing_changed_system, routing_update_system, ActuatedLights, BuildingInfos, Dispatcher,
+    ParkingManagement,
 };
+use crate::mods::Mods;
 use crate::physics::coworld_synchronize;
 use crate::souls::freight_station::freight_station_system;
 use crate::souls::goods_company::{company_system, GoodsCompanyRegistry};
 use crate::souls::human::update_decision_system;
+use crate::souls::labor::{labor_market_system, LaborMarket};
+use crate::souls::population::{population_system, PopulationStats};
+use crate::souls::satisfaction::{satisfaction_system, SatisfactionStats};
+use crate::souls::train_station::train_station_system;
+use crate::transportation::bus::{bus_spawn_system, bus_system, BusLines};
 use crate::transportation::pedestrian_decision_system;
 use crate::transportation::road::{vehicle_decision_system, vehicle_state_update_system};
 use crate::transportation::train::{
@@ -15,7 +25,9 @@ use crate::transportation::train::{
 };
 use crate::utils::resources::Resources;
 use crate::utils::time::Tick;
-use crate::world::{CompanyEnt, FreightStationEnt, HumanEnt, TrainEnt, VehicleEnt, WagonEnt};
+use crate::world::{
+    CompanyEnt, FreightStationEnt, HumanEnt, TrainEnt, TrainStationEnt, VehicleEnt, WagonEnt,
+};
 use crate::World;
 use crate::{
     add_souls_to_empty_buildings, utils, CollisionWorld, Egregoria, EgregoriaOptions, GameTime,
@@ -25,35 +37,57 @@ use crate::{
 use common::saveload::{Bincode, Encoder};
 use serde::de::DeserializeOwned;
 use serde::Serialize;
+use std::sync::Once;
 
+/// Registers the systems and resources. They go in global lists, so only the first call does
+/// anything, tests running in parallel would otherwise run every system several times a tick.
 pub fn init() {
+    static INIT: Once = Once::new();
+    INIT.call_once(register_all);
+}
+
+fn register_all() {
     register_system("dispatch_system", dispatch_system);
     register_system("update_decision_system", update_decision_system);
     register_system("company_system", company_system);
     register_system("pedestrian_decision_system", pedestrian_decision_system);
     register_system("coworld_synchronize", coworld_synchronize);
     register_system("locomotive_system", locomotive_system);
+    register_system("actuated_lights_system", actuated_lights_system);
     register_system("vehicle_decision_system", vehicle_decision_system);
     register_system("vehicle_state_update_system", vehicle_state_update_system);
     register_system("routing_changed_system", routing_changed_system);
     register_system("routing_update_system", routing_update_system);
     register_system("itinerary_update", itinerary_update);
+    register_system("congestion_system", congestion_system);
     register_system("market_update", market_update);
+    register_system("maintenance_system", maintenance_system);
     register_system("train_reservations_update", train_reservations_update);
     register_system("freight_station", freight_station_system);
+    register_system("bus_system", bus_system);
+    register_system("train_station", train_station_system);
+    register_system("satisfaction_system", satisfaction_system);
+    register_system("labor_market_system", labor_market_system);
 
     register_system_goria("add_souls_to_empty_buildings", add_souls_to_empty_buildings);
+    register_system_goria("bus_spawn_system", bus_spawn_system);
+    register_system_goria("population_system", population_system);
 
     register_resource_noserialize::<GoodsCompanyRegistry>();
     register_resource_noserialize::<ItemRegistry>();
+    register_resource_noserialize::<Mods>();
     register_resource_noserialize::<ParCommandBuffer<VehicleEnt>>();
     register_resource_noserialize::<ParCommandBuffer<TrainEnt>>();
     register_resource_noserialize::<ParCommandBuffer<HumanEnt>>();
     register_resource_noserialize::<ParCommandBuffer<WagonEnt>>();
     register_resource_noserialize::<ParCommandBuffer<FreightStationEnt>>();
     register_resource_noserialize::<ParCommandBuffer<CompanyEnt>>();
+    register_resource_noserialize::<ParCommandBuffer<TrainStationEnt>>();
     register_resource_noinit::<Market, Bincode>("market");
     register_resource_noinit::<EcoStats, Bincode>("ecostats");
+    register_resource_default::<PopulationStats, Bincode>("population_stats");
+    register_resource_default::<SatisfactionStats, Bincode>("satisfaction_stats");
+    register_resource_default::<LaborMarket, Bincode>("labor_market");
     register_resource_noinit::<EgregoriaOptions, Bincode>("egregoriaoptions");
 
     register_init(init_market);
@@ -62,6 +96,9 @@ pub fn init() {
     register_resource_default::<Map, Bincode>("map");
     register_resource_default::<TrainReservations, Bincode>("train_reservations");
     register_resource_default::<Government, Bincode>("government");
+    register_resource_default::<BudgetLedger, Bincode>("budget_ledger");
+    register_resource_default::<Maintenance, Bincode>("maintenance");
+    register_resource_default::<ExternalTrades, Bincode>("external_trades");
     register_resource_default::<ParkingManagement, Bincode>("pmanagement");
     register_resource_default::<BuildingInfos, Bincode>("binfos");
     register_resource::<GameTime, Bincode>("game_time", || {
@@ -71,6 +108,8 @@ pub fn init() {
     register_resource::<RandProvider, Bincode>("randprovider", || RandProvider::new(RNG_SEED));
     register_resource_default::<Dispatcher, Bincode>("dispatcher");
     register_resource_default::<Replay, Bincode>("replay");
+    register_resource_default::<BusLines, Bincode>("bus_lines");
+    register_resource_default::<ActuatedLights, Bincode>("actuated_lights");
 }
 
 pub struct InitFunc {
@@ -1,9 +1,11 @@
 #![allow(clippy::too_many_arguments)]
 #![allow(clippy::type_complexity)]
 
+use crate::economy::ExternalTrades;
 use crate::engine_interaction::WorldCommand;
 use crate::map::{BuildingKind, Map};
 use crate::map_dynamic::{Itinerary, ItineraryLeader};
+use crate::mods::{ModStamp, Mods};
 use crate::physics::CollisionWorld;
 use crate::physics::Speed;
 use crate::souls::add_souls_to_empty_buildings;
@@ -12,6 +14,7 @@ use crate::utils::resources::{Ref, RefMut, Resources};
 use common::saveload::Encoder;
 use derive_more::{From, TryInto};
 use geom::Vec3;
+use serde::de::Error;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::any::Any;
 use std::collections::BTreeMap;
@@ -39,6 +42,7 @@ pub mod engine_interaction;
 pub mod init;
 pub mod map;
 pub mod map_dynamic;
+pub mod mods;
 pub mod physics;
 pub mod souls;
 #[cfg(test)]
@@ -65,6 +69,7 @@ pub enum SoulID {
     Human(HumanID),
     GoodsCompany(CompanyID),
     FreightStation(FreightStationID),
+    TrainStation(TrainStationID),
 }
 
 impl From<SoulID> for AnyEntity {
@@ -73,6 +78,7 @@ impl From<SoulID> for AnyEntity {
             SoulID::Human(id) => AnyEntity::HumanID(id),
             SoulID::GoodsCompany(id) => AnyEntity::CompanyID(id),
             SoulID::FreightStation(id) => AnyEntity::FreightStationID(id),
+            SoulID::TrainStation(id) => AnyEntity::TrainStationID(id),
         }
     }
 }
@@ -85,6 +91,7 @@ impl TryFrom<AnyEntity> for SoulID {
             AnyEntity::HumanID(id) => Ok(SoulID::Human(id)),
             AnyEntity::CompanyID(id) => Ok(SoulID::GoodsCompany(id)),
             AnyEntity::FreightStationID(id) => Ok(SoulID::FreightStation(id)),
+            AnyEntity::TrainStationID(id) => Ok(SoulID::TrainStation(id)),
             _ => Err(()),
         }
     }
@@ -99,6 +106,8 @@ pub struct Egregoria {
 
 const RNG_SEED: u64 = 123;
 const VERSION: &str = include_str!("../../VERSION");
+/// Key of the enabled mods among the saved resources
+const MODS_SAVE_KEY: &str = "mods";
 
 #[derive(Debug, Copy, Clone, Serialize, Deserialize)]
 pub struct EgregoriaOptions {
@@ -355,6 +364,12 @@ impl Serialize for Egregoria {
             }
         }
 
+        let mods = self.resources.get::<Mods>().unwrap().stamps();
+        m.insert(
+            MODS_SAVE_KEY.to_string(),
+            common::saveload::Bincode::encode(&mods).unwrap(),
+        );
+
         log::info!("took {}s to serialize resources", t.elapsed().as_secs_f32());
 
         let v = EgregoriaSer {
@@ -421,6 +436,27 @@ impl<'de> Deserialize<'de> for Egregoria {
             }
         }
 
+        let saved_mods: Vec<ModStamp> = goriadeser
+            .res
+            .remove(MODS_SAVE_KEY)
+            .and_then(|v| common::saveload::Bincode::decode(&v).ok())
+            .unwrap_or_default();
+        let mods = goria.read::<Mods>().stamps();
+        if saved_mods != mods {
+            let list = |stamps: &[ModStamp]| {
+                stamps
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            };
+            return Err(D::Error::custom(format!(
+                "the save was made with the mods [{}] but the enabled mods are [{}]",
+                list(&saved_mods),
+                list(&mods)
+            )));
+        }
+
         goria.world = goriadeser.world;
 
         unsafe {
@@ -431,6 +467,10 @@ impl<'de> Deserialize<'de> for Egregoria {
             }
         }
 
+        let map = goria.map();
+        goria.write::<ExternalTrades>().add_missing(&map);
+        drop(map);
+
         log::info!(
             "took {}s to deserialize in total",
             t.elapsed().as_secs_f32()
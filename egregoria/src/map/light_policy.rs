@@ -1,42 +1,71 @@
-use crate::map::{Intersection, LaneID, Lanes, Roads, TrafficControl, TrafficLightSchedule};
+use crate::map::{
+    Intersection, LaneID, Lanes, RoadID, Roads, TrafficBehavior, TrafficControl,
+    TrafficLightSchedule,
+};
 use egui_inspect::{egui, egui::Ui, Inspect, InspectArgs};
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum LightPolicy {
     NoLights,
     StopSigns,
     Lights,
     #[default]
     Auto,
+    /// Hand-tuned timings, roads without a phase get stop signs
+    Custom(Vec<RoadLightPhase>),
+    /// Lights that give the green to the roads where vehicles are waiting,
+    /// driven by [`actuated_lights_system`](crate::map_dynamic::actuated_lights_system)
+    Actuated,
+}
+
+/// Light timings of one incoming road, in seconds
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize, Inspect)]
+pub struct RoadLightPhase {
+    pub road: RoadID,
+    /// Left-turn only green before the main green
+    pub protected_left: usize,
+    pub green: usize,
+    pub orange: usize,
+    pub red: usize,
+    pub offset: usize,
+}
+
+impl RoadLightPhase {
+    pub fn period(&self) -> usize {
+        self.protected_left + self.green + self.orange + self.red
+    }
+
+    fn control(&self) -> TrafficControl {
+        if self.period() == 0 {
+            return TrafficControl::Always;
+        }
+        TrafficControl::Light(
+            TrafficLightSchedule::from_basic(self.green, self.orange, self.red, self.offset)
+                .with_protected_left(self.protected_left),
+        )
+    }
 }
 
 impl LightPolicy {
-    pub fn apply(self, inter: &Intersection, lanes: &mut Lanes, roads: &Roads) {
-        let in_road_lanes: Vec<Vec<LaneID>> = inter
-            .roads
-            .iter()
-            .map(|&x| {
-                roads
-                    .get(x)
-                    .into_iter()
-                    .flat_map(|r| {
-                        r.incoming_lanes_to(inter.id)
-                            .iter()
-                            .filter(|(_, kind)| kind.needs_light())
-                            .map(|&(id, _)| id)
-                    })
-                    .collect::<Vec<_>>()
-            })
-            .filter(|v| !v.is_empty())
-            .collect();
+    pub fn apply(&self, inter: &Intersection, lanes: &mut Lanes, roads: &Roads) {
+        let in_road_lanes = Self::in_road_lanes(inter, roads);
 
-        for incoming_lanes in &in_road_lanes {
+        for (_, incoming_lanes) in &in_road_lanes {
             for &lane in incoming_lanes {
                 unwrap_cont!(lanes.get_mut(lane)).control = TrafficControl::Always;
             }
         }
 
+        if inter.turn_policy.roundabout {
+            for (_, incoming_lanes) in in_road_lanes {
+                for lane in incoming_lanes {
+                    unwrap_cont!(lanes.get_mut(lane)).control = TrafficControl::Yield;
+                }
+            }
+            return;
+        }
+
         match self {
             LightPolicy::NoLights => {}
             LightPolicy::StopSigns => {
@@ -56,23 +85,126 @@ impl LightPolicy {
                     Self::stop_signs(in_road_lanes, lanes);
                 }
             }
+            LightPolicy::Custom(ref phases) => {
+                Self::custom(phases, in_road_lanes, lanes);
+            }
+            LightPolicy::Actuated => {
+                for (_, incoming_lanes) in in_road_lanes {
+                    for lane in incoming_lanes {
+                        unwrap_cont!(lanes.get_mut(lane)).control =
+                            TrafficControl::Actuated(TrafficBehavior::RED);
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn is_actuated(&self) -> bool {
+        matches!(self, LightPolicy::Actuated)
+    }
+
+    /// The incoming lanes that get the green together, opposite roads share a phase
+    pub fn phase_groups(inter: &Intersection, roads: &Roads) -> Vec<Vec<LaneID>> {
+        let in_road_lanes = Self::in_road_lanes(inter, roads);
+        let n_cycles = in_road_lanes.len().div_ceil(2);
+
+        let mut groups = vec![vec![]; n_cycles];
+        for (i, (_, incoming_lanes)) in in_road_lanes.into_iter().enumerate() {
+            groups[i % n_cycles].extend(incoming_lanes);
         }
+        groups
+    }
+
+    /// The phases [`LightPolicy::Lights`] would use, as a starting point for a custom plan
+    pub fn default_phases(inter: &Intersection, roads: &Roads) -> Vec<RoadLightPhase> {
+        let in_roads: Vec<RoadID> = Self::in_road_lanes(inter, roads)
+            .into_iter()
+            .map(|(road, _)| road)
+            .collect();
+        Self::light_phases(&in_roads, inter)
+    }
+
+    /// Shifts the offsets of all phases so that the green of `road` starts at `green_start`
+    /// seconds, modulo the cycle. Returns false if the road has no phase.
+    pub fn synchronize(phases: &mut [RoadLightPhase], road: RoadID, green_start: usize) -> bool {
+        let Some(reference) = phases.iter().find(|p| p.road == road) else {
+            return false;
+        };
+        let period = reference.period();
+        if period == 0 {
+            return false;
+        }
+        // the green starts when (seconds + offset) % period == protected_left
+        let target = (reference.protected_left + period - green_start % period) % period;
+        let delta = (target + period - reference.offset % period) % period;
+
+        for p in phases {
+            let period = p.period();
+            if period != 0 {
+                p.offset = (p.offset + delta) % period;
+            }
+        }
+        true
+    }
+
+    fn in_road_lanes(inter: &Intersection, roads: &Roads) -> Vec<(RoadID, Vec<LaneID>)> {
+        inter
+            .roads
+            .iter()
+            .map(|&x| {
+                let lanes = roads
+                    .get(x)
+                    .into_iter()
+                    .flat_map(|r| {
+                        r.incoming_lanes_to(inter.id)
+                            .iter()
+                            .filter(|(_, kind)| kind.needs_light())
+                            .map(|&(id, _)| id)
+                    })
+                    .collect::<Vec<_>>();
+                (x, lanes)
+            })
+            .filter(|(_, v)| !v.is_empty())
+            .collect()
     }
 
     pub fn is_stop_signs(&self) -> bool {
         matches!(self, LightPolicy::StopSigns)
     }
 
-    fn stop_signs(in_road_lanes: Vec<Vec<LaneID>>, lanes: &mut Lanes) {
-        for incoming_lanes in in_road_lanes {
+    fn stop_signs(in_road_lanes: Vec<(RoadID, Vec<LaneID>)>, lanes: &mut Lanes) {
+        for (_, incoming_lanes) in in_road_lanes {
             for lane in incoming_lanes {
                 unwrap_cont!(lanes.get_mut(lane)).control = TrafficControl::StopSign;
             }
         }
     }
 
-    fn lights(in_road_lanes: Vec<Vec<LaneID>>, inter: &Intersection, lanes: &mut Lanes) {
-        let n_cycles = (in_road_lanes.len() + 1) / 2;
+    fn lights(in_road_lanes: Vec<(RoadID, Vec<LaneID>)>, inter: &Intersection, lanes: &mut Lanes) {
+        let in_roads: Vec<RoadID> = in_road_lanes.iter().map(|(road, _)| *road).collect();
+        Self::custom(&Self::light_phases(&in_roads, inter), in_road_lanes, lanes);
+    }
+
+    fn custom(
+        phases: &[RoadLightPhase],
+        in_road_lanes: Vec<(RoadID, Vec<LaneID>)>,
+        lanes: &mut Lanes,
+    ) {
+        for (road, incoming_lanes) in in_road_lanes {
+            let control = phases
+                .iter()
+                .find(|p| p.road == road)
+                .map(RoadLightPhase::control)
+                .unwrap_or(TrafficControl::StopSign);
+
+            for lane in incoming_lanes {
+                unwrap_cont!(lanes.get_mut(lane)).control = control;
+            }
+        }
+    }
+
+    fn light_phases(in_roads: &[RoadID], inter: &Intersection) -> Vec<RoadLightPhase> {
+        let n_cycles = in_roads.len().div_ceil(2);
         let cycle_size = 14;
         let orange_length = 4;
 
@@ -82,62 +214,198 @@ impl LightPolicy {
         let inter_offset: usize =
             (common::rand::rand(offset as f32) * total_length as f32) as usize;
 
-        for (i, incoming_lanes) in in_road_lanes.into_iter().enumerate() {
-            let light = TrafficControl::Light(TrafficLightSchedule::from_basic(
-                cycle_size - orange_length,
-                orange_length,
-                total_length - cycle_size,
-                cycle_size * (i % n_cycles) + inter_offset,
-            ));
-
-            for lane in incoming_lanes {
-                unwrap_cont!(lanes.get_mut(lane)).control = light;
-            }
-        }
-    }
-}
-
-impl Inspect<LightPolicy> for LightPolicy {
-    fn render(_: &LightPolicy, _: &'static str, _: &mut Ui, _: &InspectArgs) {
-        unimplemented!()
+        in_roads
+            .iter()
+            .enumerate()
+            .map(|(i, &road)| RoadLightPhase {
+                road,
+                protected_left: 0,
+                green: cycle_size - orange_length,
+                orange: orange_length,
+                red: total_length - cycle_size,
+                offset: cycle_size * (i % n_cycles) + inter_offset,
+            })
+            .collect()
     }
 
-    fn render_mut(
-        data: &mut LightPolicy,
+    /// Edits the policy of `inter`, switching to a custom plan starts from the default phases
+    pub fn render_editor(
+        &mut self,
+        inter: &Intersection,
+        roads: &Roads,
         label: &'static str,
         ui: &mut Ui,
-        _: &InspectArgs,
+        args: &InspectArgs,
     ) -> bool {
-        let p = data;
-        let mut id = match p {
+        let mut id = match self {
             LightPolicy::NoLights => 0,
             LightPolicy::StopSigns => 1,
             LightPolicy::Lights => 2,
             LightPolicy::Auto => 3,
+            LightPolicy::Custom(_) => 4,
+            LightPolicy::Actuated => 5,
         };
 
-        let tostr = |x: LightPolicy| match x {
-            LightPolicy::NoLights => "No lights",
-            LightPolicy::StopSigns => "Stop signs",
-            LightPolicy::Lights => "Lights",
-            LightPolicy::Auto => "Auto",
-        };
-
-        let get = |i| match i {
-            0 => LightPolicy::NoLights,
-            1 => LightPolicy::StopSigns,
-            2 => LightPolicy::Lights,
-            3 => LightPolicy::Auto,
+        let tostr = |i| match i {
+            0 => "No lights",
+            1 => "Stop signs",
+            2 => "Lights",
+            3 => "Auto",
+            4 => "Custom",
+            5 => "Actuated",
             _ => unreachable!(),
         };
 
-        let changed = egui::ComboBox::from_label(label)
-            .show_index(ui, &mut id, 4, |i| tostr(get(i)).to_string())
+        let mut changed = egui::ComboBox::from_label(label)
+            .show_index(ui, &mut id, 6, |i| tostr(i).to_string())
             .changed();
         if changed {
-            *p = get(id);
+            *self = match id {
+                0 => LightPolicy::NoLights,
+                1 => LightPolicy::StopSigns,
+                2 => LightPolicy::Lights,
+                3 => LightPolicy::Auto,
+                4 => LightPolicy::Custom(Self::default_phases(inter, roads)),
+                5 => LightPolicy::Actuated,
+                _ => unreachable!(),
+            };
+        }
+
+        if let LightPolicy::Custom(ref mut phases) = self {
+            for phase in phases.iter_mut() {
+                ui.collapsing(road_label(inter, roads, phase.road), |ui| {
+                    changed |= <RoadLightPhase as Inspect<RoadLightPhase>>::render_mut(
+                        phase, "", ui, args,
+                    );
+                });
+            }
         }
 
         changed
     }
 }
+
+/// Names an incoming road by the direction it leaves the intersection in
+fn road_label(inter: &Intersection, roads: &Roads, road: RoadID) -> String {
+    const DIRECTIONS: [&str; 8] = [
+        "East",
+        "North-east",
+        "North",
+        "North-west",
+        "West",
+        "South-west",
+        "South",
+        "South-east",
+    ];
+    let Some(r) = roads.get(road).filter(|r| r.other_end(inter.id).is_some()) else {
+        return "Disconnected road".to_string();
+    };
+    let dir = r.dir_from(inter.id);
+    let octant = (dir.y.atan2(dir.x) / std::f32::consts::FRAC_PI_4).round() as i32;
+    format!("{} road", DIRECTIONS[octant.rem_euclid(8) as usize])
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::map::{LanePatternBuilder, LightPolicy, ProjectFilter, TrafficBehavior};
+    use crate::tests::TestCtx;
+    use crate::WorldCommand;
+    use geom::vec3;
+
+    #[test]
+    fn test_green_wave_gives_consecutive_greens() {
+        let mut test = TestCtx::new();
+        let speed = 10.0;
+
+        {
+            let mut map = test.g.map_mut();
+            let pat = LanePatternBuilder::new().build();
+            let mut connect = |from, to| {
+                let a = map.project(from, 5.0, ProjectFilter::ALL);
+                let b = map.project(to, 5.0, ProjectFilter::ALL);
+                map.make_connection(a, b, None, &pat);
+            };
+            let xs = [0.0, 100.0, 300.0, 500.0, 600.0];
+            for w in xs.windows(2) {
+                connect(vec3(w[0], -500.0, 0.0), vec3(w[1], -500.0, 0.0));
+            }
+            for x in &xs[1..4] {
+                connect(vec3(*x, -500.0, 0.0), vec3(*x, -400.0, 0.0));
+                connect(vec3(*x, -500.0, 0.0), vec3(*x, -600.0, 0.0));
+            }
+        }
+
+        let path: Vec<_> = [100.0, 300.0, 500.0]
+            .iter()
+            .map(|&x| {
+                test.g
+                    .map()
+                    .intersections()
+                    .iter()
+                    .find(|(_, i)| i.pos.xy().distance(vec3(x, -500.0, 0.0).xy()) < 1.0)
+                    .unwrap()
+                    .0
+            })
+            .collect();
+
+        test.apply(&[WorldCommand::MapGreenWave {
+            path: path.clone(),
+            speed,
+        }]);
+
+        let map = test.g.map();
+        let mut arrival = 0.0;
+        for w in path.windows(2) {
+            let road = map
+                .find_road(w[0], w[1])
+                .or_else(|| map.find_road(w[1], w[0]))
+                .unwrap();
+            arrival += map.roads()[road].length() / speed;
+            let seconds = arrival.round() as u32;
+
+            for &(lane, kind) in map.roads()[road].incoming_lanes_to(w[1]) {
+                if !kind.needs_light() {
+                    continue;
+                }
+                let control = map.lanes()[lane].control;
+                assert_eq!(control.get_behavior(seconds), TrafficBehavior::GREEN);
+                assert_eq!(control.get_behavior(seconds - 1), TrafficBehavior::RED);
+            }
+        }
+    }
+
+    #[test]
+    fn test_empty_custom_plan_is_rejected() {
+        let mut test = TestCtx::new();
+        let inter = {
+            let mut map = test.g.map_mut();
+            let pat = LanePatternBuilder::new().build();
+            let center = map.project(vec3(0.0, -500.0, 0.0), 5.0, ProjectFilter::ALL);
+            for to in [
+                vec3(100.0, -500.0, 0.0),
+                vec3(-100.0, -500.0, 0.0),
+                vec3(0.0, -400.0, 0.0),
+            ] {
+                let c = map.project(center.pos, 5.0, ProjectFilter::ALL);
+                let b = map.project(to, 5.0, ProjectFilter::ALL);
+                map.make_connection(c, b, None, &pat);
+            }
+            map.intersections()
+                .iter()
+                .find(|(_, i)| i.roads.len() == 3)
+                .unwrap()
+                .0
+        };
+
+        test.apply(&[WorldCommand::MapUpdateIntersectionPolicy {
+            inter,
+            turn: Default::default(),
+            light: LightPolicy::Custom(vec![]),
+        }]);
+
+        assert_eq!(
+            test.g.map().intersections()[inter].light_policy,
+            LightPolicy::Auto
+        );
+    }
+}
@@ -1,8 +1,8 @@
 use crate::map::serializing::SerializedMap;
 use crate::map::{
     Building, BuildingGen, BuildingID, BuildingKind, Intersection, IntersectionID, Lane, LaneID,
-    LaneKind, LanePattern, Lot, LotID, LotKind, ParkingSpotID, ParkingSpots, ProjectFilter,
-    ProjectKind, Road, RoadID, RoadSegmentKind, SpatialMap, Terrain, Zone,
+    LaneKind, LanePattern, LightPolicy, Lot, LotID, LotKind, ParkingSpotID, ParkingSpots,
+    ProjectFilter, ProjectKind, Road, RoadID, RoadSegmentKind, SpatialMap, Terrain, Zone,
 };
 use geom::OBB;
 use geom::{Spline3, Vec2, Vec3};
@@ -68,12 +68,58 @@ impl Map {
 
         let inter = unwrap_ret!(self.intersections.get_mut(id));
         f(inter);
-        inter.update_traffic_control(&mut self.lanes, &self.roads);
-        inter.update_turns(&self.lanes, &self.roads);
+        // the interface changes when switching to a roundabout
+        self.invalidate(id);
 
         self.check_invariants()
     }
 
+    /// Sets the lights along a chain of intersections so that a platoon going at `speed` m/s
+    /// from the first one gets consecutive greens
+    pub fn set_green_wave(&mut self, path: &[IntersectionID], speed: f32) {
+        info!("set_green_wave {:?}", path);
+        if path.len() < 2 || speed <= 0.0 {
+            return;
+        }
+
+        let mut arrival = 0.0;
+        for (i, &id) in path.iter().enumerate() {
+            // the road we come from, or the one we leave by for the first intersection
+            // as it is in the same phase as the road coming in opposite to it
+            let (a, b) = if i == 0 {
+                (path[0], path[1])
+            } else {
+                (path[i - 1], id)
+            };
+            let Some(road) = self.find_road(a, b).or_else(|| self.find_road(b, a)) else {
+                log::warn!("green wave: no road between {:?} and {:?}", a, b);
+                return;
+            };
+            if i > 0 {
+                arrival += unwrap_ret!(self.roads.get(road)).length() / speed;
+            }
+
+            let inter = unwrap_ret!(self.intersections.get(id));
+            let mut phases = match inter.light_policy {
+                LightPolicy::Custom(ref phases) => phases.clone(),
+                _ => {
+                    let phases = LightPolicy::default_phases(inter, &self.roads);
+                    if phases.len() <= 2 {
+                        // no crossing traffic, no need for lights
+                        continue;
+                    }
+                    phases
+                }
+            };
+            if !LightPolicy::synchronize(&mut phases, road, arrival.round() as usize) {
+                continue;
+            }
+            self.update_intersection(id, move |inter| {
+                inter.light_policy = LightPolicy::Custom(phases.clone())
+            });
+        }
+    }
+
     pub fn remove_intersection(&mut self, src: IntersectionID) {
         info!("remove_intersection {:?}", src);
         self.dirt_id += Wrapping(1);
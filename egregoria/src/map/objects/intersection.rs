@@ -1,13 +1,13 @@
 use crate::map::{
-    Intersections, LaneID, LaneKind, Lanes, LightPolicy, Road, RoadID, Roads, SpatialMap,
-    TraverseDirection, Turn, TurnID, TurnPolicy,
+    Intersections, LaneID, LaneKind, LaneTurns, Lanes, LightPolicy, Road, RoadID, Roads,
+    SpatialMap, TraverseDirection, Turn, TurnID, TurnKind, TurnPolicy,
 };
 use geom::{pseudo_angle, Circle};
 use geom::{Vec2, Vec3};
 use ordered_float::OrderedFloat;
 use serde::{Deserialize, Serialize};
 use slotmapd::new_key_type;
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 
 new_key_type! {
     pub struct IntersectionID;
@@ -31,6 +31,8 @@ pub struct Intersection {
 
     pub turn_policy: TurnPolicy,
     pub light_policy: LightPolicy,
+    /// Restrictions on where vehicles of some incoming lanes may go
+    pub lane_turns: BTreeMap<LaneID, LaneTurns>,
 }
 
 impl Intersection {
@@ -42,6 +44,7 @@ impl Intersection {
             roads: Default::default(),
             turn_policy: Default::default(),
             light_policy: Default::default(),
+            lane_turns: Default::default(),
         });
         spatial.insert(id, pos.xy());
         id
@@ -77,6 +80,8 @@ impl Intersection {
     }
 
     pub fn update_turns(&mut self, lanes: &Lanes, roads: &Roads) {
+        self.lane_turns.retain(|&lane, _| lanes.contains_key(lane));
+
         self.turns = self
             .turn_policy
             .generate_turns(self, lanes, roads)
@@ -84,10 +89,20 @@ impl Intersection {
             .map(|(id, kind)| Turn::new(id, kind))
             .collect();
 
+        let ring = self
+            .turn_policy
+            .roundabout
+            .then(|| self.roundabout_radius(roads));
+
         self.turns = std::mem::take(&mut self.turns)
             .into_iter()
             .map(|mut x| {
-                x.make_points(lanes);
+                match ring {
+                    Some(radius) if x.kind == TurnKind::Driving => {
+                        x.make_ring_points(lanes, self.pos, radius)
+                    }
+                    _ => x.make_points(lanes),
+                }
                 x
             })
             .collect();
@@ -124,6 +139,14 @@ impl Intersection {
             r.set_interface(id, Self::empty_interface(r.width));
         }
 
+        if self.turn_policy.roundabout {
+            // leave room for the entries outside of the ring
+            let interface = self.roundabout_radius(roads) + LaneKind::Driving.width() * 0.5 + 2.0;
+            for &r in &self.roads {
+                roads[r].max_interface(id, interface);
+            }
+        }
+
         if self.roads.len() <= 1 {
             return;
         }
@@ -152,6 +175,18 @@ impl Intersection {
         }
     }
 
+    /// Distance from the center to the middle of the ring of a roundabout,
+    /// the island is as wide as the widest road
+    pub fn roundabout_radius(&self, roads: &Roads) -> f32 {
+        let widest = self
+            .roads
+            .iter()
+            .filter_map(|&r| roads.get(r))
+            .map(|r| r.width)
+            .fold(0.0, f32::max);
+        (widest * 0.5).max(4.0) + LaneKind::Driving.width() * 0.5
+    }
+
     pub fn empty_interface(width: f32) -> f32 {
         (width * 0.8).max(Self::MIN_INTERFACE)
     }
@@ -235,3 +270,144 @@ impl Intersection {
 }
 
 debug_inspect_impl!(IntersectionID);
+
+#[cfg(test)]
+mod tests {
+    use crate::map::{
+        LanePatternBuilder, LaneTurns, ProjectFilter, TrafficControl, TurnDirection, TurnKind,
+        TurnPolicy,
+    };
+    use crate::tests::TestCtx;
+    use crate::WorldCommand;
+    use geom::vec3;
+
+    #[test]
+    fn test_roundabout_turns_go_around_the_ring() {
+        let mut test = TestCtx::new();
+
+        let center = vec3(0.0, -500.0, 0.0);
+        {
+            let mut map = test.g.map_mut();
+            let pat = LanePatternBuilder::new().build();
+            for to in [
+                vec3(-200.0, -500.0, 0.0),
+                vec3(200.0, -500.0, 0.0),
+                vec3(0.0, -300.0, 0.0),
+                vec3(0.0, -700.0, 0.0),
+            ] {
+                let a = map.project(center, 5.0, ProjectFilter::ALL);
+                let b = map.project(to, 5.0, ProjectFilter::ALL);
+                map.make_connection(a, b, None, &pat);
+            }
+        }
+        let inter = test
+            .g
+            .map()
+            .intersections()
+            .iter()
+            .find(|(_, i)| i.pos.xy().distance(center.xy()) < 1.0)
+            .unwrap()
+            .0;
+
+        test.apply(&[WorldCommand::MapUpdateIntersectionPolicy {
+            inter,
+            turn: TurnPolicy {
+                roundabout: true,
+                ..Default::default()
+            },
+            light: Default::default(),
+        }]);
+
+        let map = test.g.map();
+        let i = &map.intersections()[inter];
+        let radius = i.roundabout_radius(map.roads());
+
+        let mut n_turns = 0;
+        for turn in i.turns().filter(|t| t.kind == TurnKind::Driving) {
+            n_turns += 1;
+            let pts = turn.points.as_slice();
+            for p in &pts[1..pts.len() - 1] {
+                assert!((p.xy().distance(center.xy()) - radius).abs() < 0.1);
+            }
+        }
+        // each of the 4 entries goes to the 3 other roads
+        assert_eq!(n_turns, 12);
+
+        for &r in &i.roads {
+            for &(lane, kind) in map.roads()[r].incoming_lanes_to(inter) {
+                if kind.needs_light() {
+                    assert!(matches!(map.lanes()[lane].control, TrafficControl::Yield));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_lane_turns_restrict_generated_turns() {
+        let mut test = TestCtx::new();
+
+        let center = vec3(0.0, -500.0, 0.0);
+        {
+            let mut map = test.g.map_mut();
+            let pat = LanePatternBuilder::new().build();
+            for to in [
+                vec3(-200.0, -500.0, 0.0),
+                vec3(200.0, -500.0, 0.0),
+                vec3(0.0, -300.0, 0.0),
+                vec3(0.0, -700.0, 0.0),
+            ] {
+                let a = map.project(center, 5.0, ProjectFilter::ALL);
+                let b = map.project(to, 5.0, ProjectFilter::ALL);
+                map.make_connection(a, b, None, &pat);
+            }
+        }
+        let (inter, lane) = {
+            let map = test.g.map();
+            let (id, i) = map
+                .intersections()
+                .iter()
+                .find(|(_, i)| i.pos.xy().distance(center.xy()) < 1.0)
+                .unwrap();
+            let lane = map.roads()[i.roads[0]]
+                .incoming_lanes_to(id)
+                .iter()
+                .find(|(_, kind)| kind.vehicles())
+                .unwrap()
+                .0;
+            (id, lane)
+        };
+
+        test.apply(&[WorldCommand::MapSetLaneTurns {
+            inter,
+            lane,
+            turns: LaneTurns {
+                left: true,
+                straight: false,
+                right: false,
+                back: false,
+            },
+        }]);
+
+        let map = test.g.map();
+        let i = &map.intersections()[inter];
+        let driving = || i.turns().filter(|t| t.kind == TurnKind::Driving);
+
+        let from_lane: Vec<_> = driving().filter(|t| t.id.src == lane).collect();
+        assert!(!from_lane.is_empty());
+        for turn in from_lane {
+            assert_eq!(turn.id.direction(map.lanes()), TurnDirection::Left);
+        }
+        // other lanes are untouched
+        assert!(driving()
+            .any(|t| t.id.src != lane && t.id.direction(map.lanes()) == TurnDirection::Straight));
+        drop(map);
+
+        // a lane must lead somewhere
+        test.apply(&[WorldCommand::MapSetLaneTurns {
+            inter,
+            lane,
+            turns: LaneTurns::NONE,
+        }]);
+        assert!(test.g.map().intersections()[inter].lane_turns[&lane].left);
+    }
+}
@@ -46,7 +46,8 @@ impl LaneKind {
     #[inline]
     pub fn width(self) -> f32 {
         match self {
-            LaneKind::Driving | LaneKind::Biking | LaneKind::Bus => 4.0,
+            LaneKind::Driving | LaneKind::Bus => 4.0,
+            LaneKind::Biking => 2.0,
             LaneKind::Parking => 2.5,
             LaneKind::Walking => 3.0,
             LaneKind::Rail => 5.3,
@@ -60,6 +61,10 @@ pub enum LaneDirection {
     Backward,
 }
 
+/// Condition of a lane that was neglected for a long time, it is driven at that fraction of
+/// the speed limit
+pub const MIN_CONDITION: f32 = 0.3;
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Lane {
     pub id: LaneID,
@@ -73,6 +78,11 @@ pub struct Lane {
 
     pub control: TrafficControl,
     pub speed_limit: f32,
+    /// Recent travel time of the vehicles going through the lane in seconds,
+    /// None when it flows freely
+    pub observed_travel_time: Option<f32>,
+    /// State of the pavement in [MIN_CONDITION; 1], worn lanes are driven slower
+    pub condition: f32,
 
     /// Always from src to dst
     pub points: PolyLine3,
@@ -83,6 +93,8 @@ pub struct Lane {
 pub struct LanePattern {
     pub lanes_forward: Vec<(LaneKind, f32)>,
     pub lanes_backward: Vec<(LaneKind, f32)>,
+    #[serde(default)]
+    pub class: RoadClass,
 }
 
 impl LanePattern {
@@ -103,6 +115,28 @@ impl LanePattern {
     }
 }
 
+/// The kinds of roads and their usual speed limit
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RoadClass {
+    #[default]
+    Residential,
+    Arterial,
+    Highway,
+}
+
+debug_inspect_impl!(RoadClass);
+
+impl RoadClass {
+    /// m/s
+    pub const fn speed_limit(self) -> f32 {
+        match self {
+            RoadClass::Residential => 9.0,
+            RoadClass::Arterial => 13.0,
+            RoadClass::Highway => 25.0,
+        }
+    }
+}
+
 #[derive(PartialEq, Copy, Clone, Inspect)]
 pub struct LanePatternBuilder {
     pub n_lanes: u32,
@@ -110,8 +144,10 @@ pub struct LanePatternBuilder {
     pub speed_limit: f32,
     pub sidewalks: bool,
     pub parking: bool,
+    pub bike_lanes: bool,
     pub one_way: bool,
     pub rail: bool,
+    pub class: RoadClass,
 }
 impl Eq for LanePatternBuilder {}
 
@@ -125,11 +161,13 @@ impl LanePatternBuilder {
     pub const fn new() -> Self {
         LanePatternBuilder {
             n_lanes: 1,
-            speed_limit: 9.0,
+            speed_limit: RoadClass::Residential.speed_limit(),
             sidewalks: true,
             parking: true,
+            bike_lanes: false,
             one_way: false,
             rail: false,
+            class: RoadClass::Residential,
         }
     }
 
@@ -148,11 +186,23 @@ impl LanePatternBuilder {
         self
     }
 
+    /// Sets the class and its speed limit
+    pub const fn class(mut self, class: RoadClass) -> Self {
+        self.class = class;
+        self.speed_limit = class.speed_limit();
+        self
+    }
+
     pub const fn parking(mut self, parking: bool) -> Self {
         self.parking = parking;
         self
     }
 
+    pub const fn bike_lanes(mut self, bike_lanes: bool) -> Self {
+        self.bike_lanes = bike_lanes;
+        self
+    }
+
     pub const fn one_way(mut self, one_way: bool) -> Self {
         self.one_way = one_way;
         self
@@ -177,6 +227,9 @@ impl LanePatternBuilder {
         if self.parking {
             w += LaneKind::Parking.width() * wayf;
         }
+        if self.bike_lanes {
+            w += LaneKind::Biking.width() * wayf;
+        }
         w += self.n_lanes as f32 * wayf * LaneKind::Driving.width();
         w + 0.5
     }
@@ -195,6 +248,13 @@ impl LanePatternBuilder {
 
         let mut forward: Vec<_> = (0..self.n_lanes).map(|_| LaneKind::Driving).collect();
 
+        if self.bike_lanes {
+            if !self.one_way {
+                backward.push(LaneKind::Biking);
+            }
+            forward.push(LaneKind::Biking);
+        }
+
         if self.parking {
             if !self.one_way {
                 backward.push(LaneKind::Parking);
@@ -222,6 +282,7 @@ impl LanePatternBuilder {
                 .map(|x| (x, self.speed_limit))
                 .collect(),
             lanes_forward: forward.into_iter().map(|x| (x, self.speed_limit)).collect(),
+            class: self.class,
         }
     }
 }
@@ -250,9 +311,27 @@ impl Lane {
             dist_from_bottom,
             control: TrafficControl::Always,
             speed_limit,
+            observed_travel_time: None,
+            condition: 1.0,
         })
     }
 
+    /// Speed limit lowered by the wear of the lane
+    pub fn max_speed(&self) -> f32 {
+        self.speed_limit * self.condition
+    }
+
+    /// Seconds to go through the lane at the speed limit
+    pub fn free_travel_time(&self) -> f32 {
+        self.points.length() / self.max_speed()
+    }
+
+    /// Seconds to go through the lane given the current traffic
+    pub fn travel_time(&self) -> f32 {
+        self.observed_travel_time
+            .unwrap_or_else(|| self.free_travel_time())
+    }
+
     pub fn get_inter_node_pos(&self, id: IntersectionID) -> Vec3 {
         match (id, self.points.as_slice()) {
             (x, [p, ..]) if x == self.src => *p,
@@ -1,6 +1,6 @@
 use crate::map::{
     Intersection, IntersectionID, Lane, LaneDirection, LaneID, LaneKind, LanePattern, Lanes,
-    ParkingSpots, Roads, SpatialMap, Terrain,
+    ParkingSpots, RoadClass, Roads, SpatialMap, Terrain,
 };
 use geom::Spline3;
 use geom::{BoldLine, PolyLine3};
@@ -40,6 +40,7 @@ pub struct Road {
     pub points: PolyLine3,
     pub interfaced_points: PolyLine3,
     pub width: f32,
+    pub class: RoadClass,
 
     src_interface: f32,
     dst_interface: f32,
@@ -87,6 +88,7 @@ impl Road {
             dst_interface: 9.0,
             segment,
             width,
+            class: lane_pattern.class,
             lanes_forward: vec![],
             lanes_backward: vec![],
             interfaced_points: PolyLine3::new(vec![points.first()]),
@@ -233,6 +235,7 @@ impl Road {
                     ))
                 })
                 .collect(),
+            class: self.class,
         }
     }
 
@@ -447,3 +450,5 @@ impl Road {
         None
     }
 }
+
+debug_inspect_impl!(RoadID);
@@ -1,9 +1,10 @@
-use crate::map::{IntersectionID, LaneID, Lanes};
+use crate::map::{IntersectionID, LaneID, Lanes, TurnDirection};
 use geom::PolyLine3;
-use geom::{Spline, Vec3};
+use geom::{vec2, Spline, Vec2, Vec3};
 use serde::{Deserialize, Serialize};
 use std::borrow::Borrow;
 use std::cmp::Ordering;
+use std::f32::consts::TAU;
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Hash)]
 pub struct TurnID {
@@ -22,6 +23,31 @@ impl TurnID {
             bidirectional,
         }
     }
+
+    /// Where the turn goes relative to the direction vehicles come from
+    pub fn direction(&self, lanes: &Lanes) -> TurnDirection {
+        let (Some(src), Some(dst)) = (lanes.get(self.src), lanes.get(self.dst)) else {
+            return TurnDirection::Straight;
+        };
+        let travel_in = -src.orientation_from(self.parent);
+        let travel_out = dst.orientation_from(self.parent);
+
+        let d = travel_in.dot(travel_out);
+        if d < -0.7 {
+            TurnDirection::Back
+        } else if d > 0.7 {
+            TurnDirection::Straight
+        } else if travel_in.cross(travel_out) > 0.0 {
+            TurnDirection::Left
+        } else {
+            TurnDirection::Right
+        }
+    }
+
+    /// Whether the turn goes to the left, crossing the incoming traffic
+    pub fn is_left(&self, lanes: &Lanes) -> bool {
+        self.direction(lanes) == TurnDirection::Left
+    }
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
@@ -75,6 +101,14 @@ const TURN_ANG_ADD: f32 = 0.29;
 const TURN_ANG_MUL: f32 = 0.36;
 const TURN_MUL: f32 = 0.46;
 const N_SPLINE: usize = 6;
+/// Distance between two points of a roundabout ring
+const RING_STEP: f32 = 2.0;
+
+/// Whether the traffic coming in `travel_dir` at `from_center` of a roundabout goes around it
+/// counter-clockwise, which depends on the side of the road vehicles drive on
+pub fn ring_ccw(travel_dir: Vec2, from_center: Vec2) -> bool {
+    travel_dir.cross(from_center) < 0.0
+}
 
 impl Turn {
     pub fn new(id: TurnID, kind: TurnKind) -> Self {
@@ -124,4 +158,39 @@ impl Turn {
                 .map(|x| x.z(pos_src.z)),
         );
     }
+
+    /// Makes the turn go around the ring of a roundabout
+    pub fn make_ring_points(&mut self, lanes: &Lanes, center: Vec3, radius: f32) {
+        let src_lane = unwrap_ret!(lanes.get(self.id.src));
+        let dst_lane = unwrap_ret!(lanes.get(self.id.dst));
+
+        let pos_src = src_lane.get_inter_node_pos(self.id.parent);
+        let pos_dst = dst_lane.get_inter_node_pos(self.id.parent);
+
+        let from_center = (pos_src - center).xy();
+        let to_center = (pos_dst - center).xy();
+        let ccw = ring_ccw(-src_lane.orientation_from(self.id.parent), from_center);
+        let sign = if ccw { 1.0 } else { -1.0 };
+
+        let ang_src = f32::atan2(from_center.y, from_center.x);
+        let ang_dst = f32::atan2(to_center.y, to_center.x);
+
+        let mut sweep = (sign * (ang_dst - ang_src)).rem_euclid(TAU);
+        if sweep < 0.3 {
+            // going back where we came from
+            sweep += TAU;
+        }
+        // part of the sweep used to merge in and out of the ring
+        let merge = (sweep * 0.25).min(0.4);
+        let ring = sweep - 2.0 * merge;
+        let n = ((ring * radius / RING_STEP).ceil() as usize).max(1);
+
+        self.points.clear_push(pos_src);
+        for i in 0..=n {
+            let ang = ang_src + sign * (merge + ring * i as f32 / n as f32);
+            self.points
+                .push((center.xy() + vec2(ang.cos(), ang.sin()) * radius).z(pos_src.z));
+        }
+        self.points.push(pos_dst);
+    }
 }
@@ -1,6 +1,7 @@
 use crate::map::{
-    LaneID, LaneKind, LanePatternBuilder, Map, Traversable, TraverseDirection, TraverseKind, TurnID,
+    Lane, LaneID, LaneKind, Map, RoadClass, Traversable, TraverseDirection, TraverseKind, TurnID,
 };
+use crate::transportation::{BIKE_SPEED, BIKE_TRAFFIC_PENALTY};
 use crate::utils::time::Tick;
 use common::hash_u64;
 use geom::{PolyLine3, Vec3};
@@ -25,6 +26,7 @@ pub enum PathKind {
     Pedestrian,
     Vehicle,
     Rail,
+    Bike,
 }
 
 impl Pathfinder for PathKind {
@@ -39,6 +41,7 @@ impl Pathfinder for PathKind {
             PathKind::Pedestrian => PedestrianPath.path(map, tick, start, end),
             PathKind::Vehicle => CarPath.path(map, tick, start, end),
             PathKind::Rail => RailPath.path(map, tick, start, end),
+            PathKind::Bike => BikePath.path(map, tick, start, end),
         }
     }
 
@@ -47,6 +50,7 @@ impl Pathfinder for PathKind {
             PathKind::Pedestrian => PedestrianPath.nearest_lane(map, pos),
             PathKind::Vehicle => CarPath.nearest_lane(map, pos),
             PathKind::Rail => RailPath.nearest_lane(map, pos),
+            PathKind::Bike => BikePath.nearest_lane(map, pos),
         }
     }
 
@@ -55,6 +59,7 @@ impl Pathfinder for PathKind {
             PathKind::Pedestrian => PedestrianPath.local_route(map, lane, start, end),
             PathKind::Vehicle => CarPath.local_route(map, lane, start, end),
             PathKind::Rail => RailPath.local_route(map, lane, start, end),
+            PathKind::Bike => BikePath.local_route(map, lane, start, end),
         }
     }
 }
@@ -169,6 +174,85 @@ impl Pathfinder for RailPath {
     }
 }
 
+/// A* on the lane graph, lanes for which `lane_cost` returns None are never taken
+fn lane_graph_path(
+    map: &Map,
+    tick: Tick,
+    start: Traversable,
+    end: LaneID,
+    lane_cost: impl Fn(&Lane) -> Option<f32>,
+) -> Option<Vec<Traversable>> {
+    let inters = &map.intersections;
+    let lanes = &map.lanes;
+
+    let start_lane = start.destination_lane();
+
+    let end_pos = inters.get(lanes.get(end)?.dst)?.pos;
+
+    let dummy = LaneID::null();
+
+    // costs are travel times, so the heuristic assumes the fastest roads for the paths going
+    // through highways to be found
+    const HEURISTIC_SPEED: f32 = RoadClass::Highway.speed_limit();
+
+    let heuristic = |&p: &LaneID| {
+        let pos = unwrap_ret!(
+            inters.get(unwrap_ret!(lanes.get(p), OrderedFloat(f32::INFINITY)).dst),
+            OrderedFloat(f32::INFINITY)
+        )
+        .pos;
+        OrderedFloat(pos.distance(end_pos) * 1.2 / HEURISTIC_SPEED) // Inexact but (much) faster
+    };
+
+    let lane_cost = &lane_cost;
+    let base_random = hash_u64((start_lane.data().as_ffi(), tick.0)) as u32;
+
+    let successors = move |&p: &LaneID| {
+        let l;
+        let p = if p == dummy {
+            l = lanes.get(start_lane);
+            start_lane
+        } else {
+            l = lanes.get(p);
+            p
+        };
+        l.and_then(move |x| inters.get(x.dst))
+            .into_iter()
+            .flat_map(move |inter| {
+                inter.turns_from(p).filter_map(move |(x, _)| {
+                    let l = lanes.get(x.dst)?;
+                    let cost = lane_cost(l)?
+                        + common::rand::randu(l.dist_from_bottom.to_bits() ^ base_random);
+
+                    Some((x.dst, OrderedFloat(cost)))
+                })
+            })
+    };
+
+    let (v, _) = pathfinding::directed::astar::astar(&dummy, successors, heuristic, |p| *p == end)?;
+
+    let mut path = Vec::with_capacity(v.len() * 2);
+    path.push(start);
+
+    let mut last_id = start_lane;
+
+    for lane in v.into_iter().skip(1) {
+        let inter_end = &inters.get(lanes.get(lane)?.src)?;
+        let id = TurnID::new(inter_end.id, last_id, lane, false);
+        path.push(Traversable::new(
+            TraverseKind::Turn(id),
+            TraverseDirection::Forward,
+        ));
+        path.push(Traversable::new(
+            TraverseKind::Lane(lane),
+            TraverseDirection::Forward,
+        ));
+
+        last_id = lane;
+    }
+    Some(path)
+}
+
 struct CarPath;
 
 impl Pathfinder for CarPath {
@@ -179,76 +263,9 @@ impl Pathfinder for CarPath {
         start: Traversable,
         end: LaneID,
     ) -> Option<Vec<Traversable>> {
-        let inters = &map.intersections;
-        let lanes = &map.lanes;
-
-        let start_lane = start.destination_lane();
-
-        let end_pos = inters.get(lanes.get(end)?.dst)?.pos;
-
-        let dummy = LaneID::null();
-
-        const HEURISTIC_SPEED: f32 = LanePatternBuilder::new().speed_limit;
-
-        let heuristic = |&p: &LaneID| {
-            let pos = unwrap_ret!(
-                inters.get(unwrap_ret!(lanes.get(p), OrderedFloat(f32::INFINITY)).dst),
-                OrderedFloat(f32::INFINITY)
-            )
-            .pos;
-            OrderedFloat(pos.distance(end_pos) * 1.2 / HEURISTIC_SPEED) // Inexact but (much) faster
-        };
-
-        let base_random = hash_u64((start_lane.data().as_ffi(), tick.0)) as u32;
-
-        let successors = move |&p: &LaneID| {
-            let l;
-            let p = if p == dummy {
-                l = lanes.get(start_lane);
-                start_lane
-            } else {
-                l = lanes.get(p);
-                p
-            };
-            l.and_then(move |x| inters.get(x.dst))
-                .into_iter()
-                .flat_map(move |inter| {
-                    inter.turns_from(p).map(move |(x, _)| {
-                        let mut cost = f32::INFINITY;
-
-                        if let Some(l) = lanes.get(x.dst) {
-                            cost = l.points.length() / l.speed_limit;
-                            cost += common::rand::randu(l.dist_from_bottom.to_bits() ^ base_random);
-                        }
-
-                        (x.dst, OrderedFloat(cost))
-                    })
-                })
-        };
-
-        let (v, _) =
-            pathfinding::directed::astar::astar(&dummy, successors, heuristic, |p| *p == end)?;
-
-        let mut path = Vec::with_capacity(v.len() * 2);
-        path.push(start);
-
-        let mut last_id = start_lane;
-
-        for lane in v.into_iter().skip(1) {
-            let inter_end = &inters.get(lanes.get(lane)?.src)?;
-            let id = TurnID::new(inter_end.id, last_id, lane, false);
-            path.push(Traversable::new(
-                TraverseKind::Turn(id),
-                TraverseDirection::Forward,
-            ));
-            path.push(Traversable::new(
-                TraverseKind::Lane(lane),
-                TraverseDirection::Forward,
-            ));
-
-            last_id = lane;
-        }
-        Some(path)
+        lane_graph_path(map, tick, start, end, |l| {
+            (l.kind != LaneKind::Biking).then(|| l.travel_time())
+        })
     }
 
     fn nearest_lane(&self, map: &Map, pos: Vec3) -> Option<LaneID> {
@@ -276,3 +293,100 @@ impl Pathfinder for CarPath {
         Some(PolyLine3::new(v))
     }
 }
+
+struct BikePath;
+
+impl Pathfinder for BikePath {
+    fn path(
+        &self,
+        map: &Map,
+        tick: Tick,
+        start: Traversable,
+        end: LaneID,
+    ) -> Option<Vec<Traversable>> {
+        lane_graph_path(map, tick, start, end, |l| {
+            let time = l.points.length() / l.max_speed().min(BIKE_SPEED);
+            match l.kind {
+                LaneKind::Biking => Some(time),
+                LaneKind::Driving => Some(time * BIKE_TRAFFIC_PENALTY),
+                _ => None,
+            }
+        })
+    }
+
+    fn nearest_lane(&self, map: &Map, pos: Vec3) -> Option<LaneID> {
+        map.nearest_lane(pos, LaneKind::Biking, Some(20.0))
+            .or_else(|| map.nearest_lane(pos, LaneKind::Driving, None))
+    }
+
+    fn local_route(&self, map: &Map, lane: LaneID, start: Vec3, end: Vec3) -> Option<PolyLine3> {
+        CarPath.local_route(map, lane, start, end)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::map::{
+        LaneKind, LanePatternBuilder, PathKind, Pathfinder, ProjectFilter, RoadClass, RoadID,
+        Traversable, TraverseDirection, TraverseKind,
+    };
+    use crate::tests::TestCtx;
+    use crate::utils::time::Tick;
+    use geom::{vec3, Vec3};
+
+    #[test]
+    fn test_car_path_prefers_faster_roads() {
+        let test = TestCtx::new();
+
+        let residential = LanePatternBuilder::new().build();
+        let highway = LanePatternBuilder::new()
+            .class(RoadClass::Highway)
+            .parking(false)
+            .sidewalks(false)
+            .build();
+
+        let mut map = test.g.map_mut();
+        let mut connect = |from: Vec3, to: Vec3, pat| {
+            let a = map.project(from, 5.0, ProjectFilter::ALL);
+            let b = map.project(to, 5.0, ProjectFilter::ALL);
+            map.make_connection(a, b, None, pat).unwrap().1
+        };
+
+        let a = vec3(0.0, -500.0, 0.0);
+        let b = vec3(400.0, -500.0, 0.0);
+        let c = vec3(200.0, -350.0, 0.0);
+
+        let entry = connect(vec3(-200.0, -500.0, 0.0), a, &residential);
+        let exit = connect(b, vec3(600.0, -500.0, 0.0), &residential);
+        // the direct way is shorter but the detour through the highway is faster
+        connect(a, b, &residential);
+        let hw1 = connect(a, c, &highway);
+        let hw2 = connect(c, b, &highway);
+        assert_eq!(map.roads()[hw1].class, RoadClass::Highway);
+        assert_eq!(map.roads()[entry].class, RoadClass::Residential);
+
+        let driving = |r: RoadID| {
+            map.roads()[r]
+                .lanes_iter()
+                .find(|&(l, kind)| {
+                    kind == LaneKind::Driving && map.lanes()[l].src == map.roads()[r].src
+                })
+                .unwrap()
+                .0
+        };
+
+        let start = Traversable::new(
+            TraverseKind::Lane(driving(entry)),
+            TraverseDirection::Forward,
+        );
+        let path = PathKind::Vehicle
+            .path(&map, Tick(0), start, driving(exit))
+            .unwrap();
+
+        let on_highway = |t: &Traversable| match t.kind {
+            TraverseKind::Lane(l) => [hw1, hw2].contains(&map.lanes()[l].parent),
+            TraverseKind::Turn(_) => false,
+        };
+        assert_eq!(path.iter().filter(|t| on_highway(t)).count(), 2);
+    }
+}
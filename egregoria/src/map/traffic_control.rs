@@ -1,11 +1,15 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Copy, Clone, Serialize, Deserialize)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum TrafficBehavior {
     RED,
     ORANGE,
     GREEN,
     STOP,
+    /// Only vehicles turning left may go
+    LEFT,
+    /// Go when the traffic that has priority is clear
+    YIELD,
 }
 
 impl TrafficBehavior {
@@ -21,6 +25,8 @@ pub struct TrafficLightSchedule {
     orange: usize,
     red: usize,
     offset: usize,
+    /// Left-turn only green at the start of the cycle
+    protected_left: usize,
 }
 
 impl TrafficLightSchedule {
@@ -31,8 +37,15 @@ impl TrafficLightSchedule {
             orange,
             red,
             offset,
+            protected_left: 0,
         }
     }
+
+    pub fn with_protected_left(mut self, protected_left: usize) -> Self {
+        self.period = self.period - self.protected_left + protected_left;
+        self.protected_left = protected_left;
+        self
+    }
 }
 
 #[derive(Copy, Clone, Debug, Serialize, Deserialize)]
@@ -40,6 +53,10 @@ pub enum TrafficControl {
     Always,
     Light(TrafficLightSchedule),
     StopSign,
+    /// Entry of a roundabout, the traffic on the ring has priority
+    Yield,
+    /// Light driven by the demand, the behavior is updated by the actuated lights system
+    Actuated(TrafficBehavior),
 }
 
 impl TrafficControl {
@@ -51,8 +68,12 @@ impl TrafficControl {
         matches!(self, TrafficControl::StopSign)
     }
 
+    pub fn is_yield(&self) -> bool {
+        matches!(self, TrafficControl::Yield)
+    }
+
     pub fn is_light(&self) -> bool {
-        matches!(self, TrafficControl::Light(_))
+        matches!(self, TrafficControl::Light(_) | TrafficControl::Actuated(_))
     }
 
     pub fn get_behavior(&self, seconds: u32) -> TrafficBehavior {
@@ -60,6 +81,10 @@ impl TrafficControl {
             TrafficControl::Always => TrafficBehavior::GREEN,
             TrafficControl::Light(schedule) => {
                 let remainder = (seconds as usize + schedule.offset) % schedule.period;
+                if remainder < schedule.protected_left {
+                    return TrafficBehavior::LEFT;
+                }
+                let remainder = remainder - schedule.protected_left;
                 if remainder < schedule.green {
                     TrafficBehavior::GREEN
                 } else if remainder < schedule.green + schedule.orange {
@@ -69,6 +94,27 @@ impl TrafficControl {
                 }
             }
             TrafficControl::StopSign => TrafficBehavior::STOP,
+            TrafficControl::Yield => TrafficBehavior::YIELD,
+            TrafficControl::Actuated(behavior) => *behavior,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{TrafficBehavior, TrafficControl, TrafficLightSchedule};
+
+    #[test]
+    fn protected_left_comes_before_green() {
+        let light = TrafficControl::Light(
+            TrafficLightSchedule::from_basic(10, 4, 20, 0).with_protected_left(6),
+        );
+
+        assert!(matches!(light.get_behavior(0), TrafficBehavior::LEFT));
+        assert!(matches!(light.get_behavior(5), TrafficBehavior::LEFT));
+        assert!(matches!(light.get_behavior(6), TrafficBehavior::GREEN));
+        assert!(matches!(light.get_behavior(16), TrafficBehavior::ORANGE));
+        assert!(matches!(light.get_behavior(20), TrafficBehavior::RED));
+        assert!(matches!(light.get_behavior(40), TrafficBehavior::LEFT));
+    }
+}
@@ -9,6 +9,61 @@ pub struct TurnPolicy {
     pub back_turns: bool,
     pub left_turns: bool,
     pub crosswalks: bool,
+    /// Vehicles go around a one-way ring and yield to the traffic on it when entering
+    #[serde(default)]
+    pub roundabout: bool,
+}
+
+/// Where a turn goes relative to the direction vehicles come from
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TurnDirection {
+    Left,
+    Straight,
+    Right,
+    /// U-turn
+    Back,
+}
+
+/// The directions vehicles on an incoming lane may take at an intersection,
+/// on top of what the [`TurnPolicy`] allows
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize, Inspect)]
+pub struct LaneTurns {
+    pub left: bool,
+    pub straight: bool,
+    pub right: bool,
+    pub back: bool,
+}
+
+impl LaneTurns {
+    pub const ALL: Self = Self {
+        left: true,
+        straight: true,
+        right: true,
+        back: true,
+    };
+
+    /// A lane nobody could leave, never stored on an intersection
+    pub const NONE: Self = Self {
+        left: false,
+        straight: false,
+        right: false,
+        back: false,
+    };
+
+    pub fn allows(self, dir: TurnDirection) -> bool {
+        match dir {
+            TurnDirection::Left => self.left,
+            TurnDirection::Straight => self.straight,
+            TurnDirection::Right => self.right,
+            TurnDirection::Back => self.back,
+        }
+    }
+}
+
+impl Default for LaneTurns {
+    fn default() -> Self {
+        Self::ALL
+    }
 }
 
 impl Default for TurnPolicy {
@@ -17,6 +72,7 @@ impl Default for TurnPolicy {
             back_turns: false,
             left_turns: true,
             crosswalks: true,
+            roundabout: false,
         }
     }
 }
@@ -89,6 +145,26 @@ impl TurnPolicy {
         roads: &Roads,
         turns: &mut Vec<(TurnID, TurnKind)>,
     ) {
+        if self.roundabout {
+            // every entry can go to every exit by going around the ring
+            for road1 in &inter.roads {
+                for road2 in &inter.roads {
+                    if road1 == road2 && !self.back_turns && inter.roads.len() > 1 {
+                        continue;
+                    }
+                    let r1 = unwrap_cont!(roads.get(*road1));
+                    let r2 = unwrap_cont!(roads.get(*road2));
+                    turns.extend(Self::zip_on_same_length(
+                        inter.id,
+                        &filter_vehicles(r1.incoming_lanes_to(inter.id)),
+                        &filter_vehicles(r2.outgoing_lanes_from(inter.id)),
+                        TurnKind::Driving,
+                    ));
+                }
+            }
+            return;
+        }
+
         match inter.roads.as_slice() {
             [road_id] => {
                 let road = unwrap_ret!(roads.get(*road_id));
@@ -308,6 +384,14 @@ impl TurnPolicy {
         let mut turns = vec![];
 
         self.generate_vehicle_turns(inter, lanes, roads, &mut turns);
+        turns.retain(|(id, _)| {
+            inter
+                .lane_turns
+                .get(&id.src)
+                .map(|allowed| allowed.allows(id.direction(lanes)))
+                .unwrap_or(true)
+        });
+
         self.generate_rail_turns(inter, lanes, roads, &mut turns);
 
         self.generate_walking_turns(inter, roads, &mut turns);
@@ -0,0 +1,228 @@
+use crate::map::{IntersectionID, LaneID, LightPolicy, Map, TrafficBehavior, TrafficControl};
+use crate::physics::{CollisionWorld, PhysicsGroup};
+use crate::utils::resources::Resources;
+use crate::utils::time::GameTime;
+use crate::World;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::num::Wrapping;
+
+/// A phase always stays green at least that many seconds
+const MIN_GREEN: u32 = 6;
+/// A phase with vehicles still coming stays green at most that many seconds when others wait
+const MAX_GREEN: u32 = 40;
+const ORANGE: u32 = 4;
+/// Vehicles closer than this to the stop line are detected
+const DETECTION_DIST: f32 = 25.0;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActuatedLight {
+    /// Index of the phase group that has the green (or the orange)
+    pub phase: usize,
+    pub phase_start: u32,
+    pub orange_until: Option<u32>,
+}
+
+/// State of the intersections using [`LightPolicy::Actuated`]
+#[derive(Default, Serialize, Deserialize)]
+pub struct ActuatedLights {
+    pub lights: BTreeMap<IntersectionID, ActuatedLight>,
+    /// Phase groups of the actuated intersections, computed again when the map changes
+    #[serde(skip)]
+    groups: BTreeMap<IntersectionID, Vec<Vec<LaneID>>>,
+    /// [`Map::dirt_id`] the groups were computed for
+    #[serde(skip)]
+    groups_dirt_id: Option<Wrapping<u32>>,
+}
+
+/// Whether a vehicle is waiting or coming to the stop line of the lane
+fn has_demand(map: &Map, cow: &CollisionWorld, lane: LaneID) -> bool {
+    let Some(lane) = map.lanes().get(lane) else { return false };
+    let stop = lane.control_point();
+    let Some(dir) = lane.points.last_dir() else { return false };
+    let dir = dir.xy();
+
+    cow.query_around(stop.xy(), DETECTION_DIST).any(|(h, pos)| {
+        let Some((_, obj)) = cow.get(h) else { return false };
+        obj.group == PhysicsGroup::Vehicles
+            && obj.dir.dot(dir) > 0.7
+            && (stop.xy() - pos).dot(dir) > 0.0
+    })
+}
+
+/// Gives the green to the phases where vehicles are detected, extending the current one while
+/// vehicles keep coming and skipping the empty ones.
+/// Only depends on the simulation state (no wall clock, no hashmap iteration order) so that
+/// every client computes the same lights.
+pub fn actuated_lights_system(_: &mut World, resources: &mut Resources) {
+    let mut lights = resources.get_mut::<ActuatedLights>().unwrap();
+    let mut map = resources.get_mut::<Map>().unwrap();
+    let cow = resources.get::<CollisionWorld>().unwrap();
+    let seconds = resources.get::<GameTime>().unwrap().seconds;
+
+    let lights = &mut *lights;
+
+    // a map change resets the lane controls, so they are all written again
+    let map_changed = lights.groups_dirt_id != Some(map.dirt_id);
+    if map_changed {
+        lights.groups = map
+            .intersections()
+            .iter()
+            .filter(|(_, inter)| inter.light_policy.is_actuated())
+            .map(|(id, inter)| (id, LightPolicy::phase_groups(inter, map.roads())))
+            .filter(|(_, groups)| !groups.is_empty())
+            .collect();
+        lights.groups_dirt_id = Some(map.dirt_id);
+        lights
+            .lights
+            .retain(|id, _| lights.groups.contains_key(id));
+    }
+
+    let mut controls = vec![];
+
+    for (&id, groups) in &lights.groups {
+        let light = lights.lights.entry(id).or_insert(ActuatedLight {
+            phase: 0,
+            phase_start: seconds,
+            orange_until: None,
+        });
+        light.phase %= groups.len();
+        let before = (light.phase, light.orange_until.is_some());
+
+        let demand: Vec<bool> = groups
+            .iter()
+            .map(|g| g.iter().any(|&l| has_demand(&map, &cow, l)))
+            .collect();
+        // next phase with waiting vehicles, in turn
+        let next = (1..groups.len())
+            .map(|i| (light.phase + i) % groups.len())
+            .find(|&i| demand[i]);
+
+        match light.orange_until {
+            Some(until) => {
+                if seconds >= until {
+                    light.phase = next.unwrap_or(light.phase);
+                    light.phase_start = seconds;
+                    light.orange_until = None;
+                }
+            }
+            None => {
+                let elapsed = seconds.saturating_sub(light.phase_start);
+                let extend = demand[light.phase] && elapsed < MAX_GREEN;
+                if elapsed >= MIN_GREEN && !extend && next.is_some() {
+                    light.orange_until = Some(seconds + ORANGE);
+                }
+            }
+        }
+
+        if !map_changed && before == (light.phase, light.orange_until.is_some()) {
+            continue;
+        }
+
+        for (i, group) in groups.iter().enumerate() {
+            let behavior = if i != light.phase {
+                TrafficBehavior::RED
+            } else if light.orange_until.is_some() {
+                TrafficBehavior::ORANGE
+            } else {
+                TrafficBehavior::GREEN
+            };
+            controls.extend(group.iter().map(|&l| (l, behavior)));
+        }
+    }
+
+    for (lane, behavior) in controls {
+        let Some(lane) = map.lanes.get_mut(lane) else { continue };
+        lane.control = TrafficControl::Actuated(behavior);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ActuatedLights;
+    use crate::map::{LanePatternBuilder, LightPolicy, ProjectFilter, TrafficBehavior};
+    use crate::physics::{CollisionWorld, PhysicsGroup, PhysicsObject};
+    use crate::tests::TestCtx;
+    use crate::utils::time::GameTime;
+    use crate::WorldCommand;
+    use geom::vec3;
+
+    #[test]
+    fn test_actuated_light_gives_green_to_waiting_vehicle() {
+        let mut test = TestCtx::new();
+
+        let center = vec3(0.0, 300.0, 0.0);
+        {
+            let mut map = test.g.map_mut();
+            let pat = LanePatternBuilder::new().build();
+            for to in [
+                vec3(-200.0, 300.0, 0.0),
+                vec3(200.0, 300.0, 0.0),
+                vec3(0.0, 500.0, 0.0),
+            ] {
+                let a = map.project(center, 5.0, ProjectFilter::ALL);
+                let b = map.project(to, 5.0, ProjectFilter::ALL);
+                map.make_connection(a, b, None, &pat);
+            }
+        }
+        let inter = test
+            .g
+            .map()
+            .intersections()
+            .iter()
+            .find(|(_, i)| i.pos.xy().distance(center.xy()) < 1.0)
+            .unwrap()
+            .0;
+        test.apply(&[WorldCommand::MapUpdateIntersectionPolicy {
+            inter,
+            turn: Default::default(),
+            light: LightPolicy::Actuated,
+        }]);
+        test.tick();
+        assert!(test.g.read::<ActuatedLights>().lights.contains_key(&inter));
+
+        let behavior = |test: &TestCtx, lane| {
+            let seconds = test.g.read::<GameTime>().seconds;
+            test.g.map().lanes()[lane].control.get_behavior(seconds)
+        };
+
+        let red_lane = {
+            let map = test.g.map();
+            let roads = &map.intersections()[inter].roads;
+            roads
+                .iter()
+                .flat_map(|&r| map.roads()[r].incoming_lanes_to(inter))
+                .map(|&(l, _)| l)
+                .find(|&l| behavior(&test, l) == TrafficBehavior::RED)
+                .unwrap()
+        };
+
+        // Nobody is waiting, the lights stay as they are
+        for _ in 0..1000 {
+            test.tick();
+            assert_eq!(behavior(&test, red_lane), TrafficBehavior::RED);
+        }
+
+        let (stop, dir) = {
+            let map = test.g.map();
+            let l = &map.lanes()[red_lane];
+            (l.control_point(), l.points.last_dir().unwrap())
+        };
+        test.g.write::<CollisionWorld>().insert(
+            (stop - dir * 5.0).xy(),
+            PhysicsObject {
+                dir: dir.xy(),
+                group: PhysicsGroup::Vehicles,
+                ..Default::default()
+            },
+        );
+
+        for _ in 0..2000 {
+            test.tick();
+            if behavior(&test, red_lane) == TrafficBehavior::GREEN {
+                return;
+            }
+        }
+        panic!("waiting vehicle did not get the green after 2000 ticks");
+    }
+}
@@ -21,6 +21,15 @@ impl BuildingInfos {
         self.assignment.insert(building, BuildingInfo::default());
     }
 
+    pub fn remove(&mut self, building: BuildingID) {
+        let Some(info) = self.assignment.remove(building) else {
+            return;
+        };
+        if let Some(owner) = info.owner {
+            self.owners.remove(&owner);
+        }
+    }
+
     pub fn get(&self, building: BuildingID) -> Option<&BuildingInfo> {
         self.assignment.get(building)
     }
@@ -56,6 +65,21 @@ impl BuildingInfos {
         b.inside.push(e);
     }
 
+    /// Removes a soul living in the building, another soul inside becomes the owner
+    /// if it owned it
+    pub fn leave(&mut self, building: BuildingID, e: SoulID) {
+        self.get_out(building, e);
+        if self.owners.get(&e) != Some(&building) {
+            return;
+        }
+        self.owners.remove(&e);
+        let b = unwrap_ret!(self.get_mut(building));
+        b.owner = b.inside.first().copied();
+        if let Some(next) = b.owner {
+            self.owners.insert(next, building);
+        }
+    }
+
     pub fn get_out(&mut self, building: BuildingID, e: SoulID) {
         let b = unwrap_ret!(self.get_mut(building));
         let inside = &mut b.inside;
@@ -0,0 +1,171 @@
+use crate::map::{LaneID, Map, PathKind, Traversable, TraverseKind};
+use crate::map_dynamic::ItineraryKind;
+use crate::transportation::VehicleState;
+use crate::utils::resources::Resources;
+use crate::utils::time::{Tick, TICKS_PER_SECOND};
+use crate::World;
+use common::hash_u64;
+use slotmapd::Key;
+use std::collections::BTreeMap;
+
+/// Weight of the newest observation in the travel times
+const SMOOTHING: f32 = 0.2;
+/// Slower vehicles are counted as crawling at that speed in m/s
+const MIN_SPEED: f32 = 0.5;
+/// Each vehicle looks at its route every that many seconds
+const REROUTE_PERIOD: u32 = 10;
+/// The rest of a route is reconsidered when it takes that many times longer than at the
+/// speed limits
+const DEGRADED_RATIO: f32 = 1.5;
+/// A new route must save that fraction of the remaining travel time to be taken
+const MIN_GAIN: f32 = 0.2;
+
+/// Updates the travel time of the lanes from the speed of the vehicles on them, and makes the
+/// cars stuck in traffic look for a faster way.
+/// Runs once per second, deterministic for the same reasons as [`super::actuated_lights_system`].
+pub fn congestion_system(world: &mut World, resources: &mut Resources) {
+    let tick = *resources.get::<Tick>().unwrap();
+    if !tick.0.is_multiple_of(TICKS_PER_SECOND) {
+        return;
+    }
+    let mut map = resources.get_mut::<Map>().unwrap();
+
+    // sum of the speeds and number of vehicles on each lane
+    let mut samples: BTreeMap<LaneID, (f32, u32)> = BTreeMap::new();
+    for v in world.vehicles.values() {
+        if !matches!(
+            v.vehicle.state,
+            VehicleState::Driving | VehicleState::Panicking(_)
+        ) {
+            continue;
+        }
+        let Some(&Traversable {
+            kind: TraverseKind::Lane(l),
+            ..
+        }) = v.it.get_travers()
+        else {
+            continue;
+        };
+        let sample = samples.entry(l).or_default();
+        sample.0 += v.speed.0;
+        sample.1 += 1;
+    }
+
+    for (id, lane) in map.lanes.iter_mut() {
+        let free = lane.free_travel_time();
+        let observed = match samples.get(&id) {
+            Some(&(sum, n)) => lane.points.length() / (sum / n as f32).max(MIN_SPEED),
+            None if lane.observed_travel_time.is_some() => free,
+            None => continue,
+        };
+        let old = lane.observed_travel_time.unwrap_or(free);
+        let t = old + (observed - old) * SMOOTHING;
+        lane.observed_travel_time = (t > free * 1.05).then_some(t);
+    }
+
+    let slot = (tick.0 / TICKS_PER_SECOND) % REROUTE_PERIOD;
+    for (id, v) in world.vehicles.iter_mut() {
+        if hash_u64(id.data().as_ffi()) % REROUTE_PERIOD as u64 != slot as u64 {
+            continue;
+        }
+        let ItineraryKind::Route(ref r, PathKind::Vehicle) = *v.it.kind() else {
+            continue;
+        };
+        let (remaining, free) = r.remaining_travel_time(&map);
+        if remaining < free * DEGRADED_RATIO {
+            continue;
+        }
+        v.it.reroute(tick, &map, remaining * (1.0 - MIN_GAIN));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::map::{LaneKind, LanePatternBuilder, PathKind, ProjectFilter, RoadID, TraverseKind};
+    use crate::map_dynamic::{Itinerary, ItineraryKind};
+    use crate::tests::TestCtx;
+    use crate::utils::time::Tick;
+    use geom::{vec3, Vec3};
+
+    #[test]
+    fn test_jammed_route_is_rerouted() {
+        let mut test = TestCtx::new();
+
+        let (direct, mut it) = {
+            let mut map = test.g.map_mut();
+            let pat = LanePatternBuilder::new().build();
+            let mut connect = |from: Vec3, to: Vec3| {
+                let a = map.project(from, 5.0, ProjectFilter::ALL);
+                let b = map.project(to, 5.0, ProjectFilter::ALL);
+                map.make_connection(a, b, None, &pat).unwrap().1
+            };
+
+            let a = vec3(0.0, -500.0, 0.0);
+            let b = vec3(400.0, -500.0, 0.0);
+            let entry = connect(vec3(-200.0, -500.0, 0.0), a);
+            let exit = connect(b, vec3(600.0, -500.0, 0.0));
+            let direct = connect(a, b);
+            connect(a, vec3(200.0, -350.0, 0.0));
+            connect(vec3(200.0, -350.0, 0.0), b);
+
+            let middle = |r: RoadID| {
+                let l = map.roads()[r]
+                    .lanes_iter()
+                    .find(|&(l, kind)| {
+                        kind == LaneKind::Driving && map.lanes()[l].src == map.roads()[r].src
+                    })
+                    .unwrap()
+                    .0;
+                let points = &map.lanes()[l].points;
+                points.point_along(points.length() * 0.5)
+            };
+            let it = Itinerary::route(
+                Tick(0),
+                middle(entry),
+                middle(exit),
+                &map,
+                PathKind::Vehicle,
+            )
+            .unwrap();
+            (direct, it)
+        };
+
+        let uses_direct = |it: &Itinerary, test: &TestCtx| {
+            let ItineraryKind::Route(ref r, _) = *it.kind() else {
+                panic!()
+            };
+            r.reversed_route.iter().any(|t| match t.kind {
+                TraverseKind::Lane(l) => test.g.map().lanes()[l].parent == direct,
+                TraverseKind::Turn(_) => false,
+            })
+        };
+        assert!(uses_direct(&it, &test));
+
+        let jammed = {
+            let mut map = test.g.map_mut();
+            let lanes: Vec<_> = map.roads()[direct].lanes_iter().map(|(l, _)| l).collect();
+            for &l in &lanes {
+                map.lanes.get_mut(l).unwrap().observed_travel_time = Some(1000.0);
+            }
+            lanes[0]
+        };
+
+        {
+            let map = test.g.map();
+            let ItineraryKind::Route(ref r, _) = *it.kind() else {
+                panic!()
+            };
+            let (remaining, free) = r.remaining_travel_time(&map);
+            assert!(remaining > free * super::DEGRADED_RATIO);
+            assert!(it.reroute(Tick(0), &map, remaining * (1.0 - super::MIN_GAIN)));
+        }
+        assert!(!uses_direct(&it, &test));
+
+        // without vehicles on it, the lane goes back to flowing freely
+        for _ in 0..200 {
+            test.tick();
+        }
+        let t = test.g.map().lanes()[jammed].observed_travel_time.unwrap();
+        assert!(t < 1000.0);
+    }
+}
@@ -1,4 +1,5 @@
 use crate::map::{LaneID, LaneKind, TraverseDirection};
+use crate::transportation::train::RailWagonKind;
 use crate::utils::resources::Resources;
 use crate::world::{TrainID, VehicleID};
 use crate::{Map, World};
@@ -27,6 +28,18 @@ pub struct Dispatcher {
 pub enum DispatchID {
     FreightTrain(TrainID),
     SmallTruck(VehicleID),
+    #[from(ignore)]
+    PassengerTrain(TrainID),
+}
+
+impl DispatchID {
+    /// The dispatch id of a train depends on the kind of wagons it pulls
+    pub fn train(id: TrainID, wagons_kind: RailWagonKind) -> Self {
+        match wagons_kind {
+            RailWagonKind::Passenger => DispatchID::PassengerTrain(id),
+            RailWagonKind::Freight | RailWagonKind::Locomotive => DispatchID::FreightTrain(id),
+        }
+    }
 }
 
 impl From<DispatchID> for DispatchKind {
@@ -34,6 +47,7 @@ impl From<DispatchID> for DispatchKind {
         match id {
             DispatchID::FreightTrain(_) => DispatchKind::FreightTrain,
             DispatchID::SmallTruck(_) => DispatchKind::SmallTruck,
+            DispatchID::PassengerTrain(_) => DispatchKind::PassengerTrain,
         }
     }
 }
@@ -60,12 +74,13 @@ struct DispatchPosition {
 pub enum DispatchKind {
     FreightTrain,
     SmallTruck,
+    PassengerTrain,
 }
 
 impl DispatchKind {
     pub fn lane_kind(self) -> LaneKind {
         match self {
-            DispatchKind::FreightTrain => LaneKind::Rail,
+            DispatchKind::FreightTrain | DispatchKind::PassengerTrain => LaneKind::Rail,
             DispatchKind::SmallTruck => LaneKind::Driving,
         }
     }
@@ -82,14 +97,14 @@ impl Dispatcher {
     /// Updates the dispatcher cache about the dispatachable entities to know where they are relative
     /// to the map, so that queries can be answered quickly
     pub fn update(&mut self, map: &Map, world: &World) {
-        let disp_trains = self
-            .dispatches
-            .entry(DispatchKind::FreightTrain)
-            .or_insert_with(|| DispatchOne::new(DispatchKind::FreightTrain.lane_kind()));
-
-        world.trains.iter().for_each(|(ent, train)| {
-            disp_trains.register(DispatchID::FreightTrain(ent), map, train.trans.position);
-        });
+        for (ent, train) in world.trains.iter() {
+            let id = DispatchID::train(ent, train.locomotive.wagons_kind);
+            let kind = DispatchKind::from(id);
+            self.dispatches
+                .entry(kind)
+                .or_insert_with(|| DispatchOne::new(kind.lane_kind()))
+                .register(id, map, train.trans.position);
+        }
 
         /*
         let disp_trucks = self
@@ -145,6 +160,11 @@ impl DispatchOne {
     }
 
     fn register(&mut self, id: DispatchID, map: &Map, pos: Vec3) {
+        // reserved passenger trains are only cached again once they are freed,
+        // as they stay in service between two stations
+        if matches!(id, DispatchID::PassengerTrain(_)) && self.reserved_by.contains(&id) {
+            return;
+        }
         let ent = self.positions.entry(id);
 
         let lanekind = self.lanekind;
@@ -204,8 +224,8 @@ impl DispatchOne {
     }
 
     pub fn unregister(&mut self, id: DispatchID) {
-        let Some(pos) = self.positions.remove(&id) else { return };
         self.reserved_by.remove(&id);
+        let Some(pos) = self.positions.remove(&id) else { return };
         self.lanes.get_mut(&pos.lane).unwrap().retain(|e| *e != id);
     }
 
@@ -52,6 +52,32 @@ pub struct Route {
 
 pub const OBJECTIVE_OK_DIST: f32 = 3.0;
 
+/// Seconds to go through the lanes of the path given the current traffic
+fn path_travel_time<'a>(map: &Map, path: impl IntoIterator<Item = &'a Traversable>) -> f32 {
+    path.into_iter()
+        .filter_map(|t| match t.kind {
+            TraverseKind::Lane(l) => map.lanes().get(l).map(|l| l.travel_time()),
+            TraverseKind::Turn(_) => None,
+        })
+        .sum()
+}
+
+impl Route {
+    /// Seconds to go through the lanes left after the current one given the current traffic,
+    /// and when they flow freely
+    pub fn remaining_travel_time(&self, map: &Map) -> (f32, f32) {
+        let free = self
+            .reversed_route
+            .iter()
+            .filter_map(|t| match t.kind {
+                TraverseKind::Lane(l) => map.lanes().get(l).map(|l| l.free_travel_time()),
+                TraverseKind::Turn(_) => None,
+            })
+            .sum();
+        (path_travel_time(map, &self.reversed_route), free)
+    }
+}
+
 impl Itinerary {
     pub const NONE: Self = Self {
         kind: ItineraryKind::None,
@@ -168,6 +194,23 @@ impl Itinerary {
         Some(it)
     }
 
+    /// Replaces the rest of the route after the current lane with a path taking less than
+    /// `max_time` seconds given the current traffic, if there is one
+    pub fn reroute(&mut self, tick: Tick, map: &Map, max_time: f32) -> bool {
+        let ItineraryKind::Route(ref mut r, pathkind) = self.kind else { return false };
+        let TraverseKind::Lane(cur) = r.cur.kind else { return false };
+        let Some(end_lane) = pathkind.nearest_lane(map, r.end_pos) else { return false };
+        if cur == end_lane {
+            return false;
+        }
+        let Some(path) = pathkind.path(map, tick, r.cur, end_lane) else { return false };
+        if path_travel_time(map, &path[1..]) >= max_time {
+            return false;
+        }
+        r.reversed_route = path.into_iter().skip(1).rev().collect();
+        true
+    }
+
     fn advance(&mut self, map: &Map, position: Vec3) -> Option<Vec3> {
         let v = self.reversed_local_path.pop();
 
@@ -304,6 +347,17 @@ impl Itinerary {
         }
     }
 
+    /// The traversable coming after the current one
+    pub fn peek_travers(&self) -> Option<&Traversable> {
+        match &self.kind {
+            ItineraryKind::None
+            | ItineraryKind::WaitUntil(_)
+            | ItineraryKind::Simple(_)
+            | ItineraryKind::WaitForReroute { .. } => None,
+            ItineraryKind::Route(Route { reversed_route, .. }, _) => reversed_route.last(),
+        }
+    }
+
     pub fn kind(&self) -> &ItineraryKind {
         &self.kind
     }
@@ -1,10 +1,14 @@
+mod actuated_lights;
 mod binfos;
+mod congestion;
 mod dispatch;
 mod itinerary;
 mod parking;
 mod router;
 
+pub use actuated_lights::*;
 pub use binfos::*;
+pub use congestion::*;
 pub use dispatch::*;
 pub use itinerary::*;
 pub use parking::*;
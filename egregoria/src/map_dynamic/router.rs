@@ -1,15 +1,29 @@
-use crate::map::{BuildingID, Map, PathKind};
+use crate::map::{BuildingID, LaneKind, LanePatternBuilder, Map, PathKind};
 use crate::map_dynamic::{Itinerary, ParkingManagement, ParkingReserveError, SpotReservation};
 use crate::physics::CollisionWorld;
-use crate::transportation::{put_pedestrian_in_coworld, unpark, Location, VehicleState};
+use crate::souls::train_station::{
+    best_rail_trip, passenger_train, passenger_train_mut, PassengerTrainState, RailTrip,
+};
+use crate::transportation::bus::{BusLineID, BusLines, BusRunState, BusStopID, BusTrip};
+use crate::transportation::{
+    put_pedestrian_in_coworld, spawn_bike, unpark, Location, VehicleKind, VehicleState, BIKE_SPEED,
+    BIKE_TRAFFIC_PENALTY, TIME_TO_PARK,
+};
 use crate::utils::resources::Resources;
-use crate::world::{HumanEnt, HumanID, VehicleEnt, VehicleID};
+use crate::world::{HumanEnt, HumanID, TrainStationEnt, TrainStationID, VehicleEnt, VehicleID};
 use crate::{ParCommandBuffer, World};
 use egui_inspect::Inspect;
 use geom::{Spline3, Transform, Vec3};
 use serde::{Deserialize, Serialize};
 use slotmapd::HopSlotMap;
 
+/// Time to find a parking spot and walk from it to the destination, a bike is left at the door.
+/// This is what makes short trips faster by bike than by car.
+const PARKING_WALK_TIME: f32 = 60.0;
+/// Roads aren't straight lines, the distance as the crow flies is lengthened by this much
+/// when estimating the time of a trip on the road
+const DETOUR_FACTOR: f32 = 1.2;
+
 #[derive(Inspect, Serialize, Deserialize)]
 pub struct Router {
     steps: Vec<RoutingStep>,
@@ -48,6 +62,17 @@ pub enum RoutingStep {
     GetOutVehicle(VehicleID),
     GetInBuilding(BuildingID),
     GetOutBuilding(BuildingID),
+    BoardBus(BusLineID, BusStopID),
+    AlightBus(BusStopID),
+    /// Take a train at the first station going to the second one
+    BoardTrain(TrainStationID, TrainStationID),
+    /// Get off the train once it has arrived at the station, or right away if it is out of service.
+    /// None when the train was already out of service, the human then gets off where it is.
+    AlightTrain(Option<TrainStationID>),
+    /// Take a bike out and ride it to the position
+    RideBike(Vec3),
+    /// Put the bike away once arrived
+    GetOffBike,
 }
 
 debug_inspect_impl!(RoutingStep);
@@ -56,6 +81,7 @@ debug_inspect_impl!(RoutingStep);
 pub fn routing_changed_system(world: &mut World, resources: &mut Resources) {
     let map: &Map = &resources.get().unwrap();
     let parking: &mut ParkingManagement = &mut resources.get_mut().unwrap();
+    let buslines: &BusLines = &resources.get().unwrap();
 
     world.humans.values_mut().for_each(|h| {
         let router = &mut h.router;
@@ -64,17 +90,45 @@ pub fn routing_changed_system(world: &mut World, resources: &mut Resources) {
             return;
         }
         let dest = unwrap_ret!(router.target_dest);
+        let start = match *loc {
+            Location::Outside => h.trans.position,
+            Location::Vehicle(id) => world
+                .vehicles
+                .get(id)
+                .map(|x| x.trans.position)
+                .unwrap_or(h.trans.position),
+            Location::Train(id) => world
+                .trains
+                .get(id)
+                .map(|x| x.trans.position)
+                .unwrap_or(h.trans.position),
+            Location::Building(id) => map
+                .buildings()
+                .get(id)
+                .map(|b| b.door_pos)
+                .unwrap_or(h.trans.position),
+        };
+        let trip = TripStart {
+            pos: start,
+            loc,
+            walking_speed: h.pedestrian.walking_speed,
+        };
+        let transit = Transit {
+            buslines,
+            stations: &world.train_stations,
+        };
 
         router.clear_steps(parking);
         match dest {
             Destination::Outside(pos) => {
-                router.steps = match router.steps_to(pos, parking, map, loc, &world.vehicles) {
-                    Ok(x) => x,
-                    Err(e) => {
-                        router.last_error = Some(e);
-                        return;
-                    }
-                };
+                router.steps =
+                    match router.steps_to(pos, &trip, parking, map, &transit, &world.vehicles) {
+                        Ok(x) => x,
+                        Err(e) => {
+                            router.last_error = Some(e);
+                            return;
+                        }
+                    };
             }
             Destination::Building(build) => {
                 if let Location::Building(cur_build) = loc {
@@ -92,13 +146,15 @@ pub fn routing_changed_system(world: &mut World, resources: &mut Resources) {
                     }
                 };
                 let door_pos = bobj.door_pos;
-                router.steps = match router.steps_to(door_pos, parking, map, loc, &world.vehicles) {
-                    Ok(x) => x,
-                    Err(e) => {
-                        router.last_error = Some(e);
-                        return;
-                    }
-                };
+                router.steps =
+                    match router.steps_to(door_pos, &trip, parking, map, &transit, &world.vehicles)
+                    {
+                        Ok(x) => x,
+                        Err(e) => {
+                            router.last_error = Some(e);
+                            return;
+                        }
+                    };
                 router.steps.push(RoutingStep::GetInBuilding(build));
             }
         }
@@ -114,6 +170,7 @@ pub fn routing_update_system(world: &mut World, resources: &mut Resources) {
     let map: &Map = &resources.get().unwrap();
     let cbuf_human: &ParCommandBuffer<HumanEnt> = &resources.get().unwrap();
     let cbuf_vehicle: &ParCommandBuffer<VehicleEnt> = &resources.get().unwrap();
+    let buslines: &mut BusLines = &mut resources.get_mut().unwrap();
 
     world.humans.iter_mut().for_each(|(body, h)| {
         if h.router.cur_step.is_none() && h.router.steps.is_empty() {
@@ -130,6 +187,11 @@ pub fn routing_update_system(world: &mut World, resources: &mut Resources) {
                 .get(id)
                 .map(|x| x.trans.position)
                 .unwrap_or_else(|| trans.position),
+            Location::Train(id) => world
+                .trains
+                .get(id)
+                .map(|x| x.trans.position)
+                .unwrap_or_else(|| trans.position),
             Location::Building(id) => map
                 .buildings()
                 .get(id)
@@ -159,6 +221,20 @@ pub fn routing_update_system(world: &mut World, resources: &mut Resources) {
                 RoutingStep::GetOutVehicle(_) => true,
                 RoutingStep::GetInBuilding(_) => true,
                 RoutingStep::GetOutBuilding(_) => true,
+                RoutingStep::BoardBus(_, _) => true,
+                RoutingStep::AlightBus(_) => true,
+                RoutingStep::BoardTrain(_, _) => true,
+                RoutingStep::AlightTrain(_) => true,
+                // the bike is spawned by a command, wait for it to exist
+                RoutingStep::RideBike(_) => match h.location {
+                    Location::Vehicle(bike) => world
+                        .vehicles
+                        .get(bike)
+                        .map(|x| x.it.has_ended(0.0))
+                        .unwrap_or(true),
+                    _ => false,
+                },
+                RoutingStep::GetOffBike => true,
             };
         }
         let mut next_step_ready = true;
@@ -181,6 +257,31 @@ pub fn routing_update_system(world: &mut World, resources: &mut Resources) {
                     .map(|b| b.door_pos.is_close(pos, 3.0))
                     .unwrap_or(true),
                 RoutingStep::GetOutBuilding(_) => true,
+                RoutingStep::BoardBus(line, stop) => {
+                    !buslines.lines().contains_key(line) || buslines.bus_at(line, stop).is_some()
+                }
+                RoutingStep::AlightBus(stop) => match h.location {
+                    Location::Vehicle(bus) => buslines
+                        .next_stop_of(bus)
+                        .map(|(_, next, state)| {
+                            next == stop && matches!(state, BusRunState::Dwelling)
+                        })
+                        .unwrap_or(true),
+                    _ => true,
+                },
+                RoutingStep::BoardTrain(from, to) => world
+                    .train_stations
+                    .get(from)
+                    .map(|st| st.s.boarding_train(to).is_some())
+                    .unwrap_or(true),
+                RoutingStep::AlightTrain(to) => match h.location {
+                    Location::Train(train) => passenger_train(&world.train_stations, train)
+                        .map(|t| Some(t.dest) != to || t.state == PassengerTrainState::Alighting)
+                        .unwrap_or(true),
+                    _ => true,
+                },
+                RoutingStep::RideBike(_) => true,
+                RoutingStep::GetOffBike => true,
             };
         }
 
@@ -249,11 +350,136 @@ pub fn routing_update_system(world: &mut World, resources: &mut Resources) {
                         .unwrap_or(pos);
                     walk_outside(body, wpos, cbuf_human, &mut h.location);
                 }
+                RoutingStep::BoardBus(line, stop) => {
+                    let bus = unwrap_or!(buslines.bus_at(line, stop), {
+                        h.router.reset_dest();
+                        return;
+                    });
+                    if let Some(run) = buslines.run_mut(bus) {
+                        run.passengers += 1;
+                    }
+                    h.location = Location::Vehicle(bus);
+                    walk_inside(body, h, cbuf_human);
+                }
+                RoutingStep::AlightBus(stop) => {
+                    if let Location::Vehicle(bus) = h.location {
+                        if let Some(run) = buslines.run_mut(bus) {
+                            run.passengers = run.passengers.saturating_sub(1);
+                        }
+                    }
+                    let wpos = buslines
+                        .stops()
+                        .get(stop)
+                        .map(|x| x.walk_pos)
+                        .unwrap_or(pos);
+                    walk_outside(body, wpos, cbuf_human, &mut h.location);
+                }
+                RoutingStep::BoardTrain(from, to) => {
+                    let train = unwrap_or!(
+                        world
+                            .train_stations
+                            .get(from)
+                            .and_then(|st| st.s.boarding_train(to)),
+                        {
+                            h.router.reset_dest();
+                            return;
+                        }
+                    );
+                    if let Some(t) = passenger_train_mut(&mut world.train_stations, train) {
+                        t.passengers += 1;
+                    }
+                    h.location = Location::Train(train);
+                    walk_inside(body, h, cbuf_human);
+                }
+                RoutingStep::AlightTrain(to) => {
+                    if let Location::Train(train) = h.location {
+                        if let Some(t) = passenger_train_mut(&mut world.train_stations, train) {
+                            t.passengers = t.passengers.saturating_sub(1);
+                        }
+                    }
+                    let wpos = to
+                        .and_then(|to| world.train_stations.get(to))
+                        .and_then(|st| map.buildings().get(st.s.building))
+                        .map(|b| b.door_pos)
+                        .unwrap_or(pos);
+                    walk_outside(body, wpos, cbuf_human, &mut h.location);
+                }
+                RoutingStep::RideBike(obj) => {
+                    walk_inside(body, h, cbuf_human);
+                    cbuf_human.exec_ent(body, move |goria| {
+                        let bike = spawn_bike(goria, pos, obj);
+                        let coll = bike.is_none().then(|| {
+                            put_pedestrian_in_coworld(&mut goria.write::<CollisionWorld>(), pos)
+                        });
+                        let h = unwrap_ret!(goria.world.humans.get_mut(body));
+                        match bike {
+                            Some(bike) => h.location = Location::Vehicle(bike),
+                            None => {
+                                h.collider = coll;
+                                h.router.reset_dest();
+                            }
+                        }
+                    });
+                }
+                RoutingStep::GetOffBike => {
+                    if let Location::Vehicle(bike) = h.location {
+                        let wpos = world
+                            .vehicles
+                            .get(bike)
+                            .map(|v| v.trans)
+                            .map(|vtrans| vtrans.position + vtrans.dir.cross(Vec3::Z) * 2.0)
+                            .unwrap_or(pos);
+                        cbuf_vehicle.kill(bike);
+                        walk_outside(body, wpos, cbuf_human, &mut h.location);
+                    }
+                }
             }
         }
     })
 }
 
+/// Estimated time to ride a bike from start to end, None if it's too far or there is no road
+fn bike_time(map: &Map, start: Vec3, end: Vec3) -> Option<f32> {
+    const MAX_BIKE_DIST: f32 = 3000.0;
+
+    let dist = start.distance(end);
+    if dist > MAX_BIKE_DIST {
+        return None;
+    }
+    let near_lane = |p, kind| map.nearest_lane(p, kind, Some(50.0)).is_some();
+    let near_bike_lane = |p| near_lane(p, LaneKind::Biking);
+    let near_road = |p| near_bike_lane(p) || near_lane(p, LaneKind::Driving);
+    if !near_road(start) || !near_road(end) {
+        return None;
+    }
+
+    let mut time = dist * DETOUR_FACTOR / BIKE_SPEED;
+    if !near_bike_lane(start) || !near_bike_lane(end) {
+        time *= BIKE_TRAFFIC_PENALTY;
+    }
+    Some(time)
+}
+
+/// Where and how a human starts a new route
+struct TripStart<'a> {
+    pos: Vec3,
+    loc: &'a Location,
+    walking_speed: f32,
+}
+
+/// The public transport a human can use
+struct Transit<'a> {
+    buslines: &'a BusLines,
+    stations: &'a HopSlotMap<TrainStationID, TrainStationEnt>,
+}
+
+#[derive(Copy, Clone)]
+enum TripMode {
+    Bike,
+    Bus(BusTrip),
+    Rail(RailTrip),
+}
+
 fn walk_inside(body: HumanID, h: &mut HumanEnt, cbuf: &ParCommandBuffer<HumanEnt>) {
     if let Some(coll) = h.collider.take() {
         cbuf.exec_ent(body, coll.destroy());
@@ -271,6 +497,43 @@ fn walk_outside(body: HumanID, pos: Vec3, cbuf: &ParCommandBuffer<HumanEnt>, loc
     });
 }
 
+/// Gets the humans riding a vehicle about to be removed out next to it,
+/// they then find another way to their destination
+pub(crate) fn drop_off_passengers(
+    world: &mut World,
+    cbuf: &ParCommandBuffer<HumanEnt>,
+    vehicle: VehicleID,
+) {
+    let Some(trans) = world.vehicles.get(vehicle).map(|v| v.trans) else {
+        return;
+    };
+    let pos = trans.position + trans.dir.cross(Vec3::Z) * 2.0;
+    for (body, h) in world.humans.iter_mut() {
+        if h.location != Location::Vehicle(vehicle) {
+            continue;
+        }
+        walk_outside(body, pos, cbuf, &mut h.location);
+        h.router.reset_dest();
+    }
+}
+
+/// Puts the humans inside a building about to be removed out at its door,
+/// they then find somewhere else to go
+pub(crate) fn evict_occupants(
+    world: &mut World,
+    cbuf: &ParCommandBuffer<HumanEnt>,
+    building: BuildingID,
+    door_pos: Vec3,
+) {
+    for (body, h) in world.humans.iter_mut() {
+        if h.location != Location::Building(building) {
+            continue;
+        }
+        walk_outside(body, door_pos, cbuf, &mut h.location);
+        h.router.reset_dest();
+    }
+}
+
 fn park(map: &Map, vehicle: &mut VehicleEnt, spot_resa: SpotReservation) {
     let trans = vehicle.trans;
     let spot = match spot_resa.get(&map.parking) {
@@ -317,6 +580,11 @@ impl Router {
         }
     }
 
+    /// Whether the human is currently riding a bike, which only exists while ridden
+    pub fn riding_bike(&self) -> bool {
+        matches!(self.cur_step, Some(RoutingStep::RideBike(_)))
+    }
+
     pub fn reset_dest(&mut self) {
         self.cur_dest = None;
     }
@@ -332,17 +600,133 @@ impl Router {
         false
     }
 
+    /// Returns the estimated time to reach obj by walking or using the personal car.
+    /// None if the human is using a work vehicle, as drivers don't leave it for public transport.
+    fn direct_time(
+        &self,
+        start: Vec3,
+        obj: Vec3,
+        walking_speed: f32,
+        cars: &HopSlotMap<VehicleID, VehicleEnt>,
+    ) -> Option<f32> {
+        if self.vehicle != self.personal_car {
+            return None;
+        }
+
+        let mut direct = start.distance(obj) / walking_speed;
+        if let Some(car_pos) = self
+            .vehicle
+            .and_then(|v| cars.get(v))
+            .map(|v| v.trans.position)
+        {
+            let car_time = start.distance(car_pos) / walking_speed
+                + car_pos.distance(obj) * DETOUR_FACTOR / LanePatternBuilder::new().speed_limit
+                + 2.0 * TIME_TO_PARK
+                + PARKING_WALK_TIME;
+            direct = direct.min(car_time);
+        }
+        Some(direct)
+    }
+
+    /// Returns the bike, bus or train trip to take if one is faster than walking or using the personal car
+    fn best_mode(
+        &self,
+        start: Vec3,
+        obj: Vec3,
+        walking_speed: f32,
+        map: &Map,
+        transit: &Transit<'_>,
+        cars: &HopSlotMap<VehicleID, VehicleEnt>,
+    ) -> Option<TripMode> {
+        let direct = self.direct_time(start, obj, walking_speed, cars)?;
+
+        let candidates = [
+            bike_time(map, start, obj).map(|t| (t, TripMode::Bike)),
+            transit
+                .buslines
+                .best_trip(start, obj, walking_speed)
+                .map(|t| (t.est_time, TripMode::Bus(t))),
+            best_rail_trip(transit.stations, map, start, obj, walking_speed)
+                .map(|t| (t.est_time, TripMode::Rail(t))),
+        ];
+
+        candidates
+            .into_iter()
+            .flatten()
+            .filter(|(t, _)| *t < direct)
+            .min_by(|a, b| a.0.total_cmp(&b.0))
+            .map(|(_, mode)| mode)
+    }
+
     fn steps_to(
         &mut self,
         obj: Vec3,
+        start: &TripStart<'_>,
         parking: &mut ParkingManagement,
         map: &Map,
-        loc: &Location,
+        transit: &Transit<'_>,
         cars: &HopSlotMap<VehicleID, VehicleEnt>,
     ) -> Result<Vec<RoutingStep>, RouterError> {
+        let buslines = transit.buslines;
         let mut steps = vec![];
-        if let Location::Building(cur_build) = loc {
-            steps.push(RoutingStep::GetOutBuilding(*cur_build));
+        let mut start_pos = start.pos;
+        let mut in_vehicle = false;
+        match *start.loc {
+            Location::Building(cur_build) => steps.push(RoutingStep::GetOutBuilding(cur_build)),
+            Location::Vehicle(v) => {
+                if let Some((_, stop, _)) = buslines.next_stop_of(v) {
+                    // Get off the bus at its next stop before going anywhere else
+                    steps.push(RoutingStep::AlightBus(stop));
+                    start_pos = buslines.stops()[stop].walk_pos;
+                } else if cars
+                    .get(v)
+                    .map(|x| matches!(x.vehicle.kind, VehicleKind::Bike))
+                    .unwrap_or(false)
+                {
+                    steps.push(RoutingStep::GetOffBike);
+                } else {
+                    in_vehicle = true;
+                }
+            }
+            Location::Train(train) => {
+                // Get off the train at its destination before going anywhere else,
+                // or right away if it is out of service
+                let to = passenger_train(transit.stations, train).map(|t| t.dest);
+                steps.push(RoutingStep::AlightTrain(to));
+                if let Some(door) = to
+                    .and_then(|to| transit.stations.get(to))
+                    .and_then(|st| map.buildings().get(st.s.building))
+                {
+                    start_pos = door.door_pos;
+                }
+            }
+            Location::Outside => {}
+        }
+
+        if !in_vehicle {
+            match self.best_mode(start_pos, obj, start.walking_speed, map, transit, cars) {
+                Some(TripMode::Bike) => {
+                    steps.push(RoutingStep::RideBike(obj));
+                    steps.push(RoutingStep::GetOffBike);
+                    steps.push(RoutingStep::WalkTo(obj));
+                    return Ok(steps);
+                }
+                Some(TripMode::Bus(trip)) => {
+                    steps.push(RoutingStep::WalkTo(trip.board_pos));
+                    steps.push(RoutingStep::BoardBus(trip.line, trip.board));
+                    steps.push(RoutingStep::AlightBus(trip.alight));
+                    steps.push(RoutingStep::WalkTo(obj));
+                    return Ok(steps);
+                }
+                Some(TripMode::Rail(trip)) => {
+                    steps.push(RoutingStep::WalkTo(trip.from_pos));
+                    steps.push(RoutingStep::BoardTrain(trip.from, trip.to));
+                    steps.push(RoutingStep::AlightTrain(Some(trip.to)));
+                    steps.push(RoutingStep::WalkTo(obj));
+                    return Ok(steps);
+                }
+                None => {}
+            }
         }
 
         if let Some(car) = self.vehicle {
@@ -357,7 +741,7 @@ impl Router {
                 }
             };
 
-            if !matches!(loc, Location::Vehicle(_)) {
+            if !in_vehicle {
                 if let Some(pos) = cars.get(car).map(|x| x.trans.position) {
                     steps.push(RoutingStep::WalkTo(pos));
                     steps.push(RoutingStep::GetInVehicle(car));
@@ -378,3 +762,73 @@ impl Router {
         Ok(steps)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::map::{LaneKind, LanePatternBuilder, ProjectFilter};
+    use crate::map_dynamic::Destination;
+    use crate::souls::human::{spawn_human, HumanDecisionKind};
+    use crate::tests::TestCtx;
+    use crate::transportation::{Location, VehicleKind};
+    use geom::{vec2, vec3};
+
+    /// Sends a human with a car on a trip along a road with bike lanes,
+    /// returns the kind of the first vehicle they take
+    fn first_vehicle_on_bike_road(length: f32, dest_x: f32) -> VehicleKind {
+        let mut test = TestCtx::new();
+
+        {
+            let mut map = test.g.map_mut();
+            let a = map.project(vec3(0.0, 0.0, 0.0), 0.0, ProjectFilter::ALL);
+            let b = map.project(vec3(length, 0.0, 0.0), 0.0, ProjectFilter::ALL);
+            let pat = LanePatternBuilder::new().bike_lanes(true).build();
+            map.make_connection(a, b, None, &pat);
+        }
+        let house = test.build_house_near(vec2(20.0, 20.0));
+        let human = spawn_human(&mut test.g, house).unwrap();
+        test.tick();
+
+        let dest = {
+            let map = test.g.map();
+            let walk = map
+                .nearest_lane(vec3(dest_x, 0.0, 0.0), LaneKind::Walking, None)
+                .unwrap();
+            map.lanes()[walk].points.project(vec3(dest_x, 0.0, 0.0))
+        };
+        test.g
+            .world_mut_unchecked()
+            .humans
+            .get_mut(human)
+            .unwrap()
+            .decision
+            .kind = HumanDecisionKind::GoTo(Destination::Outside(dest));
+
+        for _ in 0..2000 {
+            test.tick();
+            let Location::Vehicle(v) = test.g.world().humans[human].location else {
+                continue;
+            };
+            if let Some(v) = test.g.world().vehicles.get(v) {
+                return v.vehicle.kind;
+            }
+        }
+
+        panic!("human did not take a vehicle after 2000 ticks");
+    }
+
+    #[test]
+    fn test_short_trip_on_bike_lanes_uses_bike() {
+        assert!(matches!(
+            first_vehicle_on_bike_road(600.0, 550.0),
+            VehicleKind::Bike
+        ));
+    }
+
+    #[test]
+    fn test_long_trip_on_bike_lanes_uses_car() {
+        assert!(matches!(
+            first_vehicle_on_bike_road(2600.0, 2500.0),
+            VehicleKind::Car
+        ));
+    }
+}
@@ -0,0 +1,435 @@
+//! Mods add or override the data of the game, they are found in the `mods` directory.
+//!
+//! A mod is a directory with a `mod.json` manifest and optionally:
+//! - `items.json` and `companies.json`, in the same format as the files in `assets`.
+//!   A definition replaces the one with the same name, otherwise it is added.
+//! - `zones.json`, a map from company names to the zone description they should use.
+//! - `models/` and `sprites/`, used instead of the assets with the same name.
+//!
+//! Mods are applied by increasing load order, so the last one wins when several define the
+//! same thing. Those conflicts are reported but are not errors.
+
+use crate::economy::{DataError, ItemDefinition, COMPANIES_FILE, ITEMS_FILE};
+use crate::souls::goods_company::{GoodsCompanyDescriptionJSON, ZoneDescription};
+use common::saveload::Encoder;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+
+pub const MODS_DIR: &str = "mods";
+pub const MANIFEST_FILE: &str = "mod.json";
+const MOD_ITEMS_FILE: &str = "items.json";
+const MOD_COMPANIES_FILE: &str = "companies.json";
+const MOD_ZONES_FILE: &str = "zones.json";
+/// Directories of a mod that can hold assets, mirroring the ones in `assets`
+const ASSET_DIRS: [&str; 2] = ["models", "sprites"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModManifest {
+    pub name: String,
+    pub version: String,
+    #[serde(default)]
+    pub description: String,
+    /// Mods are applied by increasing load order then by name
+    #[serde(default)]
+    pub load_order: i32,
+    #[serde(default = "enabled_default")]
+    pub enabled: bool,
+}
+
+fn enabled_default() -> bool {
+    true
+}
+
+#[derive(Debug, Clone)]
+pub struct Mod {
+    pub manifest: ModManifest,
+    pub dir: PathBuf,
+}
+
+/// What a save remembers of the mods it was played with
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct ModStamp {
+    pub name: String,
+    pub version: String,
+}
+
+impl Display for ModStamp {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}@{}", self.name, self.version)
+    }
+}
+
+/// Something defined by several mods, only the definition of the winner is used
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModConflict {
+    /// What kind of definition it is, like "item" or "asset"
+    pub kind: &'static str,
+    pub name: String,
+    pub winner: String,
+    pub loser: String,
+}
+
+impl Display for ModConflict {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} is defined by both {} and {}, {} is used",
+            self.kind, self.name, self.loser, self.winner, self.winner
+        )
+    }
+}
+
+/// Item and company definitions once the mods are applied
+pub(crate) struct GameData {
+    pub items: Vec<ItemDefinition>,
+    pub companies: Vec<GoodsCompanyDescriptionJSON>,
+}
+
+/// The enabled mods, in load order
+#[derive(Default)]
+pub struct Mods {
+    /// The directory containing `assets` and `mods`
+    root: PathBuf,
+    pub mods: Vec<Mod>,
+    /// Where to find the assets overridden by the mods, by their path in `assets`
+    assets: BTreeMap<String, PathBuf>,
+    pub conflicts: Vec<ModConflict>,
+    pub errors: Vec<DataError>,
+}
+
+impl Mods {
+    /// Finds the enabled mods in the `mods` directory under `root`, a missing directory means
+    /// there are no mods
+    pub fn load(root: &Path) -> Self {
+        let mut m = Self {
+            root: root.to_path_buf(),
+            ..Default::default()
+        };
+
+        let dir = root.join(MODS_DIR);
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            return m;
+        };
+
+        for entry in entries.flatten() {
+            let dir = entry.path();
+            let manifest_path = dir.join(MANIFEST_FILE);
+            if !manifest_path.is_file() {
+                continue;
+            }
+            match read_json::<ModManifest>(&manifest_path) {
+                Ok(manifest) if manifest.enabled => m.mods.push(Mod { manifest, dir }),
+                Ok(_) => {}
+                Err(e) => m.errors.push(e),
+            }
+        }
+
+        m.mods.sort_by(|a, b| {
+            (a.manifest.load_order, &a.manifest.name, &a.dir).cmp(&(
+                b.manifest.load_order,
+                &b.manifest.name,
+                &b.dir,
+            ))
+        });
+        // the copies of a mod can have different load orders, the first one loaded is kept
+        let mut names = BTreeSet::new();
+        m.mods.retain(|md| {
+            let first = names.insert(md.manifest.name.clone());
+            if !first {
+                log::error!("mod {} is installed twice, skipping one", md.manifest.name);
+            }
+            first
+        });
+
+        let mut owners = BTreeMap::new();
+        for md in &m.mods {
+            for asset_dir in ASSET_DIRS {
+                let dir = md.dir.join(asset_dir);
+                if !dir.is_dir() {
+                    continue;
+                }
+                for path in common::saveload::walkdir(&dir) {
+                    let Ok(rel) = path.strip_prefix(&md.dir) else {
+                        continue;
+                    };
+                    let key = rel.to_string_lossy().replace('\\', "/");
+                    record_owner(
+                        &mut m.conflicts,
+                        &mut owners,
+                        "asset",
+                        &key,
+                        &md.manifest.name,
+                    );
+                    m.assets.insert(key, path);
+                }
+            }
+        }
+
+        for md in &m.mods {
+            log::info!("loaded mod {}", md.manifest.name);
+        }
+        m
+    }
+
+    pub fn stamps(&self) -> Vec<ModStamp> {
+        self.mods
+            .iter()
+            .map(|m| ModStamp {
+                name: m.manifest.name.clone(),
+                version: m.manifest.version.clone(),
+            })
+            .collect()
+    }
+
+    /// Path of an asset as referred to by the data files: models by their name in
+    /// `assets/models`, textures by their path. Mods take precedence over the base game.
+    pub fn asset_path(&self, asset: &str) -> PathBuf {
+        let key = if asset.ends_with(".glb") {
+            format!("models/{asset}")
+        } else {
+            asset.strip_prefix("assets/").unwrap_or(asset).to_string()
+        };
+        match self.assets.get(&key) {
+            Some(path) => path.clone(),
+            None => self.root.join("assets").join(key),
+        }
+    }
+
+    /// Reads the item and company definitions of the base game then applies the mods on top.
+    /// Files that can't be read are reported in `errors` and skipped.
+    pub(crate) fn load_data(&mut self) -> GameData {
+        let mut items: Vec<ItemDefinition> = self.read_base(ITEMS_FILE);
+        let mut companies: Vec<GoodsCompanyDescriptionJSON> = self.read_base(COMPANIES_FILE);
+
+        let mut item_owners = BTreeMap::new();
+        let mut company_owners = BTreeMap::new();
+        let mut zone_owners = BTreeMap::new();
+
+        for md in &self.mods {
+            let name = &md.manifest.name;
+
+            let mod_items: Vec<ItemDefinition> =
+                read_mod_file(&mut self.errors, md, MOD_ITEMS_FILE);
+            for item in mod_items {
+                record_owner(
+                    &mut self.conflicts,
+                    &mut item_owners,
+                    "item",
+                    &item.name,
+                    name,
+                );
+                replace_or_push(&mut items, item, |i| &i.name);
+            }
+
+            let mod_companies: Vec<GoodsCompanyDescriptionJSON> =
+                read_mod_file(&mut self.errors, md, MOD_COMPANIES_FILE);
+            for company in mod_companies {
+                record_owner(
+                    &mut self.conflicts,
+                    &mut company_owners,
+                    "company",
+                    &company.name,
+                    name,
+                );
+                replace_or_push(&mut companies, company, |c| &c.name);
+            }
+
+            let zones: BTreeMap<String, ZoneDescription> =
+                read_mod_file(&mut self.errors, md, MOD_ZONES_FILE);
+            for (company, zone) in zones {
+                let Some(c) = companies.iter_mut().find(|c| c.name == company) else {
+                    self.errors.push(DataError::Mod {
+                        name: name.clone(),
+                        error: format!("{MOD_ZONES_FILE} refers to the unknown company {company}"),
+                    });
+                    continue;
+                };
+                record_owner(
+                    &mut self.conflicts,
+                    &mut zone_owners,
+                    "zone",
+                    &company,
+                    name,
+                );
+                c.zone = Some(Box::new(zone));
+            }
+        }
+
+        for conflict in &self.conflicts {
+            log::warn!("mod conflict: {}", conflict);
+        }
+        for error in &self.errors {
+            log::error!("{}", error);
+        }
+
+        GameData { items, companies }
+    }
+
+    fn read_base<T: DeserializeOwned>(&mut self, file: &str) -> Vec<T> {
+        read_json(&self.root.join(file)).unwrap_or_else(|e| {
+            self.errors.push(e);
+            vec![]
+        })
+    }
+}
+
+/// Reads a data file of a mod, a mod doesn't have to provide all of them
+fn read_mod_file<T: DeserializeOwned + Default>(
+    errors: &mut Vec<DataError>,
+    md: &Mod,
+    file: &str,
+) -> T {
+    let path = md.dir.join(file);
+    if !path.is_file() {
+        return T::default();
+    }
+    read_json(&path).unwrap_or_else(|e| {
+        errors.push(e);
+        T::default()
+    })
+}
+
+fn read_json<T: DeserializeOwned>(path: &Path) -> Result<T, DataError> {
+    let parse_error = |error: String| DataError::Parse {
+        file: path.to_string_lossy().into_owned(),
+        error,
+    };
+    let source = common::saveload::load_string(path).map_err(|e| parse_error(e.to_string()))?;
+    common::saveload::JSON::decode(source.as_ref()).map_err(|e| parse_error(e.to_string()))
+}
+
+fn record_owner(
+    conflicts: &mut Vec<ModConflict>,
+    owners: &mut BTreeMap<String, String>,
+    kind: &'static str,
+    name: &str,
+    owner: &str,
+) {
+    if let Some(loser) = owners.insert(name.to_string(), owner.to_string()) {
+        conflicts.push(ModConflict {
+            kind,
+            name: name.to_string(),
+            winner: owner.to_string(),
+            loser,
+        });
+    }
+}
+
+fn replace_or_push<T>(v: &mut Vec<T>, x: T, name: impl Fn(&T) -> &str) {
+    match v.iter_mut().find(|y| name(y) == name(&x)) {
+        Some(y) => *y = x,
+        None => v.push(x),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Mod, ModManifest, Mods};
+    use crate::tests::TestCtx;
+    use crate::Egregoria;
+    use common::saveload::Encoder;
+    use std::path::Path;
+
+    #[test]
+    fn test_mods_override_and_conflict() {
+        let root = std::env::temp_dir().join(format!("egregoria_mods_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        let write = |path: &str, content: &str| {
+            let path = root.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        };
+
+        let base = Path::new("../assets");
+        write(
+            "assets/items.json",
+            &std::fs::read_to_string(base.join("items.json")).unwrap(),
+        );
+        write(
+            "assets/companies.json",
+            &std::fs::read_to_string(base.join("companies.json")).unwrap(),
+        );
+
+        write(
+            "mods/a/mod.json",
+            r#"{"name": "a", "version": "1.0", "load_order": 1}"#,
+        );
+        write(
+            "mods/a/items.json",
+            r#"[{"name": "bread", "label": "Baguette"}, {"name": "cheese", "label": "Cheese"}]"#,
+        );
+        write("mods/a/models/bakery.glb", "");
+        write(
+            "mods/b/mod.json",
+            r#"{"name": "b", "version": "2.0", "load_order": 0}"#,
+        );
+        write(
+            "mods/b/items.json",
+            r#"[{"name": "bread", "label": "Loaf"}]"#,
+        );
+        write(
+            "mods/b/zones.json",
+            r#"{"Bakery": {"floor": "assets/sprites/dirt.jpg", "filler": "salad.glb", "price_per_area": 5}}"#,
+        );
+        write(
+            "mods/c/mod.json",
+            r#"{"name": "c", "version": "1.0", "enabled": false}"#,
+        );
+        write(
+            "mods/a_copy/mod.json",
+            r#"{"name": "a", "version": "1.1", "load_order": 5}"#,
+        );
+
+        let mut mods = Mods::load(&root);
+        let data = mods.load_data();
+        let _ = std::fs::remove_dir_all(&root);
+
+        let names: Vec<_> = mods.stamps().into_iter().map(|s| s.to_string()).collect();
+        assert_eq!(names, vec!["b@2.0", "a@1.0"]);
+        assert!(mods.errors.is_empty());
+
+        let bread = data.items.iter().find(|i| i.name == "bread").unwrap();
+        assert_eq!(bread.label, "Baguette");
+        assert!(data.items.iter().any(|i| i.name == "cheese"));
+        assert_eq!(mods.conflicts.len(), 1);
+        assert_eq!(mods.conflicts[0].winner, "a");
+        assert_eq!(mods.conflicts[0].loser, "b");
+
+        let bakery = data.companies.iter().find(|c| c.name == "Bakery").unwrap();
+        assert_eq!(bakery.zone.as_ref().unwrap().price_per_area, 5);
+
+        assert!(mods
+            .asset_path("bakery.glb")
+            .starts_with(root.join("mods/a")));
+        assert_eq!(
+            mods.asset_path("assets/sprites/dirt.jpg"),
+            root.join("assets/sprites/dirt.jpg")
+        );
+    }
+
+    #[test]
+    fn test_save_refuses_other_mods() {
+        let test = TestCtx::new();
+        test.g.write::<Mods>().mods.push(Mod {
+            manifest: ModManifest {
+                name: "a".to_string(),
+                version: "1.0".to_string(),
+                description: String::new(),
+                load_order: 0,
+                enabled: true,
+            },
+            dir: "mods/a".into(),
+        });
+
+        let ser = common::saveload::Bincode::encode(&test.g).unwrap();
+        let deser = common::saveload::Bincode::decode::<Egregoria>(&ser);
+        assert!(deser.is_err());
+
+        test.g.write::<Mods>().mods.clear();
+        let ser = common::saveload::Bincode::encode(&test.g).unwrap();
+        assert!(common::saveload::Bincode::decode::<Egregoria>(&ser).is_ok());
+    }
+}
@@ -1,13 +1,12 @@
-use crate::economy::{find_trade_place, Bought, ItemID, ItemRegistry, Market};
+use crate::economy::{find_trade_place, ItemID, ItemRegistry, Market, Spending};
 use crate::map::BuildingID;
-use crate::map_dynamic::{BuildingInfos, Destination};
+use crate::map_dynamic::Destination;
+use crate::souls::desire::{Desire, DesireCtx};
 use crate::souls::human::HumanDecisionKind;
 use crate::transportation::Location;
 use crate::utils::time::{GameInstant, GameTime};
-use crate::world::{HumanEnt, HumanID};
-use crate::{Map, ParCommandBuffer, SoulID};
+use crate::SoulID;
 use egui_inspect::Inspect;
-use geom::Transform;
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
@@ -35,9 +34,22 @@ impl BuyFood {
         }
     }
 
-    pub fn score(&self, time: &GameTime, loc: &Location, bought: &Bought) -> f32 {
+    /// The item bought to eat
+    pub fn item(&self) -> ItemID {
+        self.bread
+    }
+
+    /// How many days past the first since the last meal, negative when fed
+    pub fn hunger(&self, time: &GameTime) -> f32 {
+        self.last_ate.elapsed(time) as f32 / GameTime::DAY as f32 - 1.0
+    }
+}
+
+impl Desire for BuyFood {
+    fn score(&self, ctx: &DesireCtx<'_>) -> f32 {
         if matches!(self.state, BuyFoodState::WaitingForTrade)
-            && bought
+            && ctx
+                .bought
                 .0
                 .get(&self.bread)
                 .map(Vec::is_empty)
@@ -46,49 +58,46 @@ impl BuyFood {
             return 0.0;
         }
         if let BuyFoodState::BoughtAt(id) = self.state {
-            if loc == &Location::Building(id) {
+            if ctx.loc == &Location::Building(id) {
                 return 1.0;
             }
         }
-        self.last_ate.elapsed(time) as f32 / GameTime::DAY as f32 - 1.0
+        if matches!(self.state, BuyFoodState::Empty)
+            && !ctx
+                .wallet
+                .can_afford(Spending::Food, ctx.market.price(self.bread))
+        {
+            return 0.0;
+        }
+        self.hunger(ctx.time)
     }
 
-    pub fn apply(
-        &mut self,
-        cbuf: &ParCommandBuffer<HumanEnt>,
-        binfos: &BuildingInfos,
-        map: &Map,
-        time: &GameTime,
-        id: HumanID,
-        trans: &Transform,
-        loc: &Location,
-        bought: &mut Bought,
-    ) -> HumanDecisionKind {
+    fn apply(&mut self, ctx: &mut DesireCtx<'_>) -> HumanDecisionKind {
         use HumanDecisionKind::*;
+        let id = ctx.me;
         match self.state {
             BuyFoodState::Empty => {
-                let pos = trans.position;
+                let pos = ctx.trans.position;
                 let bread = self.bread;
-                cbuf.exec_on(id, move |market: &mut Market| {
+                ctx.cbuf.exec_on(id, move |market: &mut Market| {
                     market.buy(SoulID::Human(id), pos.xy(), bread, 1)
                 });
                 self.state = BuyFoodState::WaitingForTrade;
                 Yield
             }
             BuyFoodState::WaitingForTrade => {
-                for trade in bought.0.entry(self.bread).or_default().drain(..) {
-                    if let Some(b) =
-                        find_trade_place(trade.seller, trans.position.xy(), binfos, map)
-                    {
+                let pos = ctx.trans.position.xy();
+                for trade in ctx.bought.0.entry(self.bread).or_default().drain(..) {
+                    if let Some(b) = find_trade_place(trade.seller, pos, ctx.binfos, ctx.map) {
                         self.state = BuyFoodState::BoughtAt(b);
                     }
                 }
                 Yield
             }
             BuyFoodState::BoughtAt(b) => {
-                if loc == &Location::Building(b) {
+                if ctx.loc == &Location::Building(b) {
                     self.state = BuyFoodState::Empty;
-                    self.last_ate = time.instant();
+                    self.last_ate = ctx.time.instant();
                     log::debug!("{:?} ate at {:?}", id, b);
                     Yield
                 } else {
@@ -98,3 +107,49 @@ impl BuyFood {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::BuyFoodState;
+    use crate::economy::Money;
+    use crate::souls::human::spawn_human;
+    use crate::tests::TestCtx;
+    use crate::utils::time::{GameInstant, GameTime};
+    use geom::{vec2, vec3};
+
+    #[test]
+    fn test_broke_human_does_not_buy_food() {
+        let mut test = TestCtx::new();
+
+        test.build_roads(&[vec3(0.0, -500.0, 0.0), vec3(100.0, -500.0, 0.0)]);
+        let house = test.build_house_near(vec2(50.0, -450.0));
+        let human = spawn_human(&mut test.g, house).unwrap();
+
+        {
+            let h = &mut test.g.world_mut_unchecked().humans[human];
+            h.desires.food.last_ate = GameInstant {
+                timestamp: -10.0 * GameTime::DAY as f64,
+            };
+            h.wallet.savings = Money::new_bucks(-100);
+        }
+
+        for _ in 0..300 {
+            test.tick();
+            let state = &test.g.world().humans[human].desires.food.state;
+            assert!(matches!(state, BuyFoodState::Empty), "{state:?}");
+        }
+
+        test.g.world_mut_unchecked().humans[human].wallet.savings = Money::new_bucks(100);
+
+        for _ in 0..300 {
+            test.tick();
+            if !matches!(
+                test.g.world().humans[human].desires.food.state,
+                BuyFoodState::Empty
+            ) {
+                return;
+            }
+        }
+        panic!("human with savings did not buy food");
+    }
+}
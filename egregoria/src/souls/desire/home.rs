@@ -1,24 +1,56 @@
+use crate::economy::{ItemID, ItemRegistry, Market, Spending};
 use crate::map::BuildingID;
 use crate::map_dynamic::Destination;
+use crate::souls::desire::{Desire, DesireCtx};
 use crate::souls::human::HumanDecisionKind;
+use crate::SoulID;
 use egui_inspect::Inspect;
 use serde::{Deserialize, Serialize};
 
 #[derive(Inspect, Clone, Serialize, Deserialize, Debug)]
 pub struct Home {
     house: BuildingID,
+    /// None when no item is named electricity, the house then buys none
+    electricity: Option<ItemID>,
+    /// Last day the electricity of the house was bought
+    electricity_day: i32,
 }
 
 impl Home {
-    pub fn new(house: BuildingID) -> Self {
-        Home { house }
+    pub fn new(house: BuildingID, registry: &ItemRegistry) -> Self {
+        Home {
+            house,
+            electricity: registry.try_id("electricity"),
+            electricity_day: -1,
+        }
     }
 
-    pub fn apply(&mut self) -> HumanDecisionKind {
-        HumanDecisionKind::GoTo(Destination::Building(self.house))
+    pub fn house(&self) -> BuildingID {
+        self.house
     }
+}
 
-    pub fn score(&self) -> f32 {
+impl Desire for Home {
+    fn score(&self, _ctx: &DesireCtx<'_>) -> f32 {
         0.2
     }
+
+    /// Goes home, buying the electricity for the day when the budget allows it
+    fn apply(&mut self, ctx: &mut DesireCtx<'_>) -> HumanDecisionKind {
+        let day = ctx.time.daytime.day;
+        if let Some(electricity) = self.electricity.filter(|&electricity| {
+            self.electricity_day < day
+                && ctx
+                    .wallet
+                    .can_afford(Spending::Goods, ctx.market.price(electricity))
+        }) {
+            self.electricity_day = day;
+            let me = ctx.me;
+            let pos = ctx.trans.position.xy();
+            ctx.cbuf.exec_on(me, move |market: &mut Market| {
+                market.buy(SoulID::Human(me), pos, electricity, 1)
+            });
+        }
+        HumanDecisionKind::GoTo(Destination::Building(self.house))
+    }
 }
@@ -0,0 +1,117 @@
+use crate::map::BuildingKind;
+use crate::souls::desire::{is_store, pick_building_near, Desire, DesireCtx, Outing};
+use crate::souls::human::HumanDecisionKind;
+use crate::utils::time::SECONDS_PER_HOUR;
+use egui_inspect::Inspect;
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+/// Hours of the evening when humans go out
+const LEISURE_HOURS: Range<i32> = 18..22;
+/// Hours when humans go out on the weekend
+const WEEKEND_LEISURE_HOURS: Range<i32> = 10..22;
+/// Seconds spent out
+const LEISURE_DURATION: f64 = SECONDS_PER_HOUR as f64;
+/// Humans go out to the venues closer than this to them
+const LEISURE_RADIUS: f32 = 1000.0;
+
+/// Going out once a day in the evening or during the weekend, to one of the venues nearby
+#[derive(Inspect, Clone, Serialize, Deserialize, Debug, Default)]
+pub struct Leisure {
+    outing: Outing,
+    /// Last day the human went out
+    last_day: i32,
+}
+
+impl Desire for Leisure {
+    fn score(&self, ctx: &DesireCtx<'_>) -> f32 {
+        if self.outing.staying(ctx, LEISURE_DURATION) {
+            return 0.4;
+        }
+        let daytime = &ctx.time.daytime;
+        let hours = if daytime.weekday().is_weekend() {
+            WEEKEND_LEISURE_HOURS
+        } else {
+            LEISURE_HOURS
+        };
+        if self.last_day >= daytime.day || !hours.contains(&daytime.hour) {
+            return 0.0;
+        }
+        0.3
+    }
+
+    fn apply(&mut self, ctx: &mut DesireCtx<'_>) -> HumanDecisionKind {
+        let day = ctx.time.daytime.day;
+        let decision = self.outing.apply(ctx, |ctx| {
+            pick_building_near(ctx, LEISURE_RADIUS, day as f32, |_, kind| {
+                is_venue(ctx, kind)
+            })
+        });
+        if decision.is_none() || self.outing.arrived() {
+            self.last_day = day;
+        }
+        decision.unwrap_or(HumanDecisionKind::Yield)
+    }
+}
+
+/// Whether humans can go out to the building. The map has no parks nor leisure venues
+/// yet, so stores stand in for them.
+fn is_venue(ctx: &DesireCtx<'_>, kind: BuildingKind) -> bool {
+    is_store(ctx, kind)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::map_dynamic::Destination;
+    use crate::souls::human::{spawn_resident, HumanDecisionKind};
+    use crate::tests::TestCtx;
+    use crate::utils::time::{GameTime, SECONDS_PER_HOUR};
+    use crate::{BuildingKind, WorldCommand};
+    use geom::vec2;
+
+    #[test]
+    fn test_humans_go_out_in_the_evening() {
+        let mut test = TestCtx::new();
+
+        let house = test.build_street_with_house();
+        let bakery = test.company_kind("Bakery");
+        test.apply(&[TestCtx::build_special(
+            BuildingKind::GoodsCompany(bakery),
+            vec2(50.0, -550.0),
+        )]);
+        // a child, so they don't get a job at the store
+        let human = spawn_resident(&mut test.g, house, 10).unwrap();
+        for _ in 0..200 {
+            test.tick();
+        }
+        let store = test
+            .g
+            .world()
+            .companies
+            .values()
+            .next()
+            .unwrap()
+            .comp
+            .building;
+        assert_eq!(
+            test.g.world().humans[human].desires.leisure.outing.dest(),
+            None
+        );
+
+        let t = test.g.read::<GameTime>().timestamp;
+        test.apply(&[WorldCommand::SetGameTime(GameTime::new(
+            0.0,
+            t + 8.5 * SECONDS_PER_HOUR as f64,
+        ))]);
+        for _ in 0..200 {
+            test.tick();
+        }
+
+        let h = &test.g.world().humans[human];
+        assert_eq!(h.desires.leisure.outing.dest(), Some(store));
+        assert!(matches!(
+            h.decision.kind,
+            HumanDecisionKind::GoTo(Destination::Building(b)) if b == store
+        ));
+    }
+}
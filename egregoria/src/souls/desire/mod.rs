@@ -1,7 +1,93 @@
+use crate::economy::{Bought, ItemRegistry, Market, Wallet};
+use crate::map::BuildingID;
+use crate::map_dynamic::{BuildingInfos, Router};
+use crate::souls::goods_company::GoodsCompanyRegistry;
+use crate::souls::human::HumanDecisionKind;
+use crate::transportation::Location;
+use crate::utils::time::GameTime;
+use crate::world::{HumanEnt, HumanID};
+use crate::{Map, ParCommandBuffer};
+use egui_inspect::Inspect;
+use geom::Transform;
+use serde::{Deserialize, Serialize};
+
 mod buyfood;
 mod home;
+mod leisure;
+mod outing;
+mod shopping;
+mod visit;
 mod work;
 
 pub use buyfood::*;
 pub use home::*;
+pub use leisure::*;
+pub use outing::*;
+pub use shopping::*;
+pub use visit::*;
 pub use work::*;
+
+/// Everything a human looks at to decide what to do next
+pub struct DesireCtx<'a> {
+    pub cbuf: &'a ParCommandBuffer<HumanEnt>,
+    pub time: &'a GameTime,
+    pub binfos: &'a BuildingInfos,
+    pub map: &'a Map,
+    pub market: &'a Market,
+    pub companies: &'a GoodsCompanyRegistry,
+    pub me: HumanID,
+    pub home: BuildingID,
+    pub trans: &'a Transform,
+    pub loc: &'a Location,
+    pub router: &'a Router,
+    pub wallet: &'a Wallet,
+    pub bought: &'a mut Bought,
+}
+
+/// A need of a human. Each time a human is done with what they were doing,
+/// the desire with the highest score decides what they do next.
+pub trait Desire {
+    fn score(&self, ctx: &DesireCtx<'_>) -> f32;
+
+    fn apply(&mut self, ctx: &mut DesireCtx<'_>) -> HumanDecisionKind;
+}
+
+/// The desires of a human, in the order they are weighed when scores are equal.
+/// A new desire only needs a field here, its initial state in [`Desires::new`]
+/// and its place in [`Desires::iter_mut`].
+#[derive(Inspect, Serialize, Deserialize)]
+pub struct Desires {
+    pub home: Home,
+    /// Given by the labor market
+    pub work: Option<Work>,
+    pub food: BuyFood,
+    pub leisure: Leisure,
+    pub shopping: Shopping,
+    pub visit: Visit,
+}
+
+impl Desires {
+    pub fn new(house: BuildingID, time: &GameTime, registry: &ItemRegistry) -> Self {
+        Self {
+            home: Home::new(house, registry),
+            work: None,
+            food: BuyFood::new(time.instant(), registry),
+            leisure: Leisure::default(),
+            shopping: Shopping::new(time.instant(), registry),
+            visit: Visit::new(house, time),
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut dyn Desire> {
+        [
+            Some(&mut self.home as &mut dyn Desire),
+            self.work.as_mut().map(|w| w as &mut dyn Desire),
+            Some(&mut self.food),
+            Some(&mut self.leisure),
+            Some(&mut self.shopping),
+            Some(&mut self.visit),
+        ]
+        .into_iter()
+        .flatten()
+    }
+}
@@ -0,0 +1,97 @@
+use crate::map::{BuildingID, BuildingKind, ProjectFilter, ProjectKind};
+use crate::map_dynamic::Destination;
+use crate::souls::desire::DesireCtx;
+use crate::souls::goods_company::CompanyKind;
+use crate::souls::human::HumanDecisionKind;
+use crate::transportation::Location;
+use crate::utils::time::GameInstant;
+use egui_inspect::Inspect;
+use serde::{Deserialize, Serialize};
+
+/// A trip to a building where the human stays for a while before doing something else
+#[derive(Inspect, Clone, Serialize, Deserialize, Debug, Default)]
+pub struct Outing {
+    dest: Option<BuildingID>,
+    arrived: Option<GameInstant>,
+}
+
+impl Outing {
+    pub fn dest(&self) -> Option<BuildingID> {
+        self.dest
+    }
+
+    /// Whether the human is at the destination since less than `duration` seconds
+    pub fn staying(&self, ctx: &DesireCtx<'_>, duration: f64) -> bool {
+        self.arrived.is_some_and(|t| t.elapsed(ctx.time) < duration)
+            && self.dest.map(Location::Building).as_ref() == Some(ctx.loc)
+    }
+
+    /// Whether the human has arrived at the destination, on the current outing or the last one
+    pub fn arrived(&self) -> bool {
+        self.arrived.is_some()
+    }
+
+    /// Heads to the destination, or to a new one picked by `pick` once the last one was reached.
+    /// Returns `None` when there is nowhere to go.
+    pub fn apply(
+        &mut self,
+        ctx: &DesireCtx<'_>,
+        pick: impl FnOnce(&DesireCtx<'_>) -> Option<BuildingID>,
+    ) -> Option<HumanDecisionKind> {
+        if let Some(dest) = self.dest.filter(|&d| ctx.map.buildings().contains_key(d)) {
+            if ctx.loc == &Location::Building(dest) {
+                if self.arrived.is_none() {
+                    self.arrived = Some(ctx.time.instant());
+                }
+                return Some(HumanDecisionKind::Yield);
+            }
+            if self.arrived.is_none() {
+                return Some(HumanDecisionKind::GoTo(Destination::Building(dest)));
+            }
+        }
+
+        self.arrived = None;
+        self.dest = pick(ctx);
+        self.dest
+            .map(|dest| HumanDecisionKind::GoTo(Destination::Building(dest)))
+    }
+}
+
+/// Picks one of the buildings within `radius` of the human accepted by `filter`,
+/// `seed` changing which one
+pub fn pick_building_near(
+    ctx: &DesireCtx<'_>,
+    radius: f32,
+    seed: f32,
+    filter: impl Fn(BuildingID, BuildingKind) -> bool,
+) -> Option<BuildingID> {
+    let pos = ctx.trans.position.xy();
+    let candidates: Vec<BuildingID> = ctx
+        .map
+        .spatial_map()
+        .query_around(pos, radius, ProjectFilter::BUILDING)
+        .filter_map(|kind| match kind {
+            ProjectKind::Building(id) => Some(id),
+            _ => None,
+        })
+        .filter(|&id| ctx.loc != &Location::Building(id))
+        .filter(|&id| {
+            ctx.map
+                .buildings()
+                .get(id)
+                .is_some_and(|b| filter(id, b.kind))
+        })
+        .collect();
+    if candidates.is_empty() {
+        return None;
+    }
+    let i = common::rand::rand3(pos.x, pos.y, seed) * candidates.len() as f32;
+    candidates.get(i as usize).copied()
+}
+
+/// Whether the building is a store, where buyers come to get their goods
+pub fn is_store(ctx: &DesireCtx<'_>, kind: BuildingKind) -> bool {
+    kind.as_goods_company()
+        .and_then(|id| ctx.companies.descriptions.get(id))
+        .is_some_and(|d| matches!(d.kind, CompanyKind::Store))
+}
@@ -0,0 +1,147 @@
+use crate::economy::{find_trade_place, ItemID, ItemRegistry, Market, Spending};
+use crate::map::BuildingID;
+use crate::map_dynamic::Destination;
+use crate::souls::desire::{is_store, Desire, DesireCtx};
+use crate::souls::human::HumanDecisionKind;
+use crate::transportation::Location;
+use crate::utils::time::{GameInstant, GameTime};
+use crate::SoulID;
+use egui_inspect::Inspect;
+use serde::{Deserialize, Serialize};
+
+/// Goods humans shop for, besides food
+const SHOPPING_ITEMS: [&str; 2] = ["furniture", "clothes"];
+/// Days between two purchases
+const SHOPPING_INTERVAL: f64 = 3.0 * GameTime::DAY as f64;
+/// Humans give up on the goods no one sold them after this long
+const ORDER_TIMEOUT: f64 = GameTime::DAY as f64;
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub enum ShoppingState {
+    Empty,
+    /// The item ordered and when
+    WaitingForTrade(ItemID, GameInstant),
+    BoughtAt(BuildingID),
+}
+
+debug_inspect_impl!(ShoppingState);
+
+/// Buying goods other than food every few days,
+/// picking them up at the store when they come from one
+#[derive(Inspect, Clone, Serialize, Deserialize, Debug)]
+pub struct Shopping {
+    items: Vec<ItemID>,
+    last_bought: GameInstant,
+    state: ShoppingState,
+}
+
+impl Shopping {
+    pub fn new(start: GameInstant, registry: &ItemRegistry) -> Self {
+        Self {
+            items: SHOPPING_ITEMS
+                .iter()
+                .filter_map(|name| registry.try_id(name))
+                .collect(),
+            last_bought: start,
+            state: ShoppingState::Empty,
+        }
+    }
+
+    /// The item to buy next
+    fn next_item(&self, ctx: &DesireCtx<'_>) -> Option<ItemID> {
+        let pos = ctx.trans.position;
+        let r = common::rand::rand3(pos.x, pos.y, self.last_bought.timestamp as f32);
+        self.items
+            .get((r * self.items.len() as f32) as usize)
+            .copied()
+    }
+
+    fn done(&mut self, ctx: &DesireCtx<'_>) {
+        self.state = ShoppingState::Empty;
+        self.last_bought = ctx.time.instant();
+    }
+}
+
+impl Desire for Shopping {
+    fn score(&self, ctx: &DesireCtx<'_>) -> f32 {
+        match self.state {
+            ShoppingState::Empty => {
+                let Some(item) = self.next_item(ctx) else {
+                    return 0.0;
+                };
+                if self.last_bought.elapsed(ctx.time) < SHOPPING_INTERVAL
+                    || !ctx
+                        .wallet
+                        .can_afford(Spending::Goods, ctx.market.price(item))
+                {
+                    return 0.0;
+                }
+                0.3
+            }
+            ShoppingState::WaitingForTrade(item, ordered) => {
+                if ctx.bought.0.get(&item).is_none_or(Vec::is_empty)
+                    && ordered.elapsed(ctx.time) < ORDER_TIMEOUT
+                {
+                    return 0.0;
+                }
+                0.3
+            }
+            ShoppingState::BoughtAt(b) => {
+                if ctx.loc == &Location::Building(b) {
+                    return 1.0;
+                }
+                0.3
+            }
+        }
+    }
+
+    fn apply(&mut self, ctx: &mut DesireCtx<'_>) -> HumanDecisionKind {
+        use HumanDecisionKind::*;
+        match self.state {
+            ShoppingState::Empty => {
+                let Some(item) = self.next_item(ctx) else {
+                    return Yield;
+                };
+                let me = ctx.me;
+                let pos = ctx.trans.position.xy();
+                ctx.cbuf.exec_on(me, move |market: &mut Market| {
+                    market.buy(SoulID::Human(me), pos, item, 1)
+                });
+                self.state = ShoppingState::WaitingForTrade(item, ctx.time.instant());
+                Yield
+            }
+            ShoppingState::WaitingForTrade(item, ordered) => {
+                let pos = ctx.trans.position.xy();
+                let trades: Vec<_> = ctx.bought.0.entry(item).or_default().drain(..).collect();
+                if trades.is_empty() && ordered.elapsed(ctx.time) >= ORDER_TIMEOUT {
+                    let me = ctx.me;
+                    ctx.cbuf.exec_on(me, move |market: &mut Market| {
+                        market.cancel_buy(SoulID::Human(me), item)
+                    });
+                    self.done(ctx);
+                }
+                for trade in trades {
+                    let place = find_trade_place(trade.seller, pos, ctx.binfos, ctx.map);
+                    let place = place.filter(|&b| {
+                        let kind = ctx.map.buildings().get(b).map(|b| b.kind);
+                        kind.is_some_and(|kind| is_store(ctx, kind))
+                    });
+                    match place {
+                        Some(b) => self.state = ShoppingState::BoughtAt(b),
+                        // the other sellers deliver the goods
+                        None => self.done(ctx),
+                    }
+                }
+                Yield
+            }
+            ShoppingState::BoughtAt(b) => {
+                if ctx.loc == &Location::Building(b) || !ctx.map.buildings().contains_key(b) {
+                    self.done(ctx);
+                    Yield
+                } else {
+                    GoTo(Destination::Building(b))
+                }
+            }
+        }
+    }
+}
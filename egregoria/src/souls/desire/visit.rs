@@ -0,0 +1,62 @@
+use crate::map::{BuildingID, BuildingKind};
+use crate::souls::desire::{pick_building_near, Desire, DesireCtx, Outing};
+use crate::souls::human::HumanDecisionKind;
+use crate::utils::time::{GameTime, SECONDS_PER_HOUR};
+use egui_inspect::Inspect;
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+/// Hours of the day when humans visit each other
+const VISIT_HOURS: Range<i32> = 10..20;
+/// Days between two visits
+const VISIT_INTERVAL: i32 = 2;
+/// Seconds spent at the other house
+const VISIT_DURATION: f64 = 2.0 * SECONDS_PER_HOUR as f64;
+/// Humans visit the houses closer than this to them
+const VISIT_RADIUS: f32 = 500.0;
+
+/// Visiting the people of another house nearby every few days
+#[derive(Inspect, Clone, Serialize, Deserialize, Debug)]
+pub struct Visit {
+    house: BuildingID,
+    outing: Outing,
+    /// Last day the human visited someone
+    last_day: i32,
+}
+
+impl Visit {
+    pub fn new(house: BuildingID, time: &GameTime) -> Self {
+        Self {
+            house,
+            outing: Outing::default(),
+            last_day: time.daytime.day,
+        }
+    }
+}
+
+impl Desire for Visit {
+    fn score(&self, ctx: &DesireCtx<'_>) -> f32 {
+        if self.outing.staying(ctx, VISIT_DURATION) {
+            return 0.4;
+        }
+        let daytime = &ctx.time.daytime;
+        if daytime.day - self.last_day < VISIT_INTERVAL || !VISIT_HOURS.contains(&daytime.hour) {
+            return 0.0;
+        }
+        0.25
+    }
+
+    fn apply(&mut self, ctx: &mut DesireCtx<'_>) -> HumanDecisionKind {
+        let day = ctx.time.daytime.day;
+        let house = self.house;
+        let decision = self.outing.apply(ctx, |ctx| {
+            pick_building_near(ctx, VISIT_RADIUS, day as f32, |id, kind| {
+                id != house && matches!(kind, BuildingKind::House)
+            })
+        });
+        if decision.is_none() || self.outing.arrived() {
+            self.last_day = day;
+        }
+        decision.unwrap_or(HumanDecisionKind::Yield)
+    }
+}
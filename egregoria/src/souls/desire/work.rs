@@ -1,8 +1,11 @@
 use crate::map::BuildingID;
-use crate::map_dynamic::{Destination, Router};
+use crate::map_dynamic::Destination;
+use crate::souls::desire::{Desire, DesireCtx};
 use crate::souls::human::HumanDecisionKind;
 use crate::transportation::Location;
-use crate::utils::time::{GameTime, RecTimeInterval, SECONDS_PER_HOUR};
+use crate::utils::time::{
+    GameInstant, GameTime, RecTimeInterval, Weekday, Weekdays, SECONDS_PER_HOUR,
+};
 use crate::world::VehicleID;
 use egui_inspect::Inspect;
 use serde::{Deserialize, Serialize};
@@ -21,25 +24,93 @@ debug_inspect_impl!(WorkKind);
 pub struct Work {
     workplace: BuildingID,
     work_inter: RecTimeInterval,
+    /// Index of the shift in the schedule of the workplace
+    shift: usize,
+    /// Days of the week the shift starts on
+    days: Weekdays,
     pub kind: WorkKind,
     on_mission: bool,
+    /// When the human last left home for work
+    commute_start: Option<GameInstant>,
+    /// Duration in seconds of the last trip to work
+    last_commute: Option<f64>,
 }
 
 impl Work {
-    pub fn new(workplace: BuildingID, kind: WorkKind, offset: f32) -> Self {
+    /// Works the given shift, starting and ending up to an hour later depending on `offset`
+    pub fn new(
+        workplace: BuildingID,
+        kind: WorkKind,
+        shift_inter: RecTimeInterval,
+        shift: usize,
+        days: Weekdays,
+        offset: f32,
+    ) -> Self {
+        let offset = (offset * SECONDS_PER_HOUR as f32) as i32;
         Work {
             workplace,
             work_inter: RecTimeInterval::new(
-                (8, (offset * SECONDS_PER_HOUR as f32) as i32),
-                (18, (offset * SECONDS_PER_HOUR as f32) as i32),
+                (shift_inter.start_hour, shift_inter.start_second + offset),
+                (shift_inter.end_hour, shift_inter.end_second + offset),
             ),
+            shift,
+            days,
             kind,
             on_mission: false,
+            commute_start: None,
+            last_commute: None,
         }
     }
 
-    pub fn apply(&mut self, loc: &Location, router: &Router) -> HumanDecisionKind {
+    pub fn workplace(&self) -> BuildingID {
+        self.workplace
+    }
+
+    pub fn shift(&self) -> usize {
+        self.shift
+    }
+
+    /// Whether the human is expected at work at the given time
+    pub fn on_shift(&self, time: &GameTime) -> bool {
+        let t = time.daytime;
+        self.work_inter.dist_until(t) == 0
+            && self
+                .days
+                .contains(Weekday::of(self.work_inter.start_day(t)))
+    }
+
+    pub fn last_commute(&self) -> Option<f64> {
+        self.last_commute
+    }
+
+    /// Measures the trip to work once the human gets there
+    pub fn update_commute(&mut self, loc: &Location, time: &GameTime) {
+        if loc != &Location::Building(self.workplace) {
+            return;
+        }
+        if let Some(start) = self.commute_start.take() {
+            self.last_commute = Some(start.elapsed(time));
+        }
+    }
+}
+
+impl Desire for Work {
+    fn score(&self, ctx: &DesireCtx<'_>) -> f32 {
+        if self.on_mission || self.on_shift(ctx.time) {
+            0.5
+        } else {
+            0.0
+        }
+    }
+
+    fn apply(&mut self, ctx: &mut DesireCtx<'_>) -> HumanDecisionKind {
         use HumanDecisionKind::*;
+        let loc = ctx.loc;
+        let router = ctx.router;
+        // only the trips from home count as commutes, not the deliveries
+        if loc == &Location::Building(ctx.home) {
+            self.commute_start = Some(ctx.time.instant());
+        }
         match self.kind {
             WorkKind::Worker => GoTo(Destination::Building(self.workplace)),
             WorkKind::Driver {
@@ -65,12 +136,4 @@ impl Work {
             }
         }
     }
-
-    pub fn score(&self, time: &GameTime) -> f32 {
-        if self.on_mission || self.work_inter.dist_until(time.daytime) == 0 {
-            0.5
-        } else {
-            0.0
-        }
-    }
 }
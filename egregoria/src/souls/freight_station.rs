@@ -1,4 +1,5 @@
-use crate::map::{BuildingID, BuildingKind, Map, PathKind};
+use crate::economy::{ExternalTradeKind, ExternalTrades};
+use crate::map::{BuildingID, Map, PathKind};
 use crate::map_dynamic::{
     BuildingInfos, DispatchID, DispatchKind, DispatchQueryTarget, Dispatcher, Itinerary,
 };
@@ -72,6 +73,7 @@ pub fn freight_station_system(world: &mut World, resources: &mut Resources) {
         .unwrap();
     let mut dispatch = resources.get_mut::<Dispatcher>().unwrap();
     let map = resources.get::<Map>().unwrap();
+    let external = resources.get::<ExternalTrades>().unwrap();
     let time = resources.get::<GameTime>().unwrap();
     let tick = *resources.get::<Tick>().unwrap();
 
@@ -103,8 +105,12 @@ pub fn freight_station_system(world: &mut World, resources: &mut Resources) {
                 }
                 FreightTrainState::Loading => {
                     if itin.has_ended(time.timestamp) {
-                        let ext = map.bkinds.get(&BuildingKind::ExternalTrading).unwrap()[0];
-                        let bpos = map.buildings[ext].obb.center().z(0.0);
+                        let nearest = external.nearest(ExternalTradeKind::Rail, pos.position.xy());
+                        let Some(ext) = nearest else {
+                            *itin = Itinerary::wait_until(time.timestamp + 10.0);
+                            continue;
+                        };
+                        let bpos = ext.pos.z(0.0);
 
                         *itin = if let Some(r) =
                             Itinerary::route(tick, train.trans.position, bpos, &map, PathKind::Rail)
@@ -1,11 +1,16 @@
 use super::desire::Work;
-use crate::economy::{find_trade_place, ItemID, ItemRegistry, Market};
+use crate::economy::{find_trade_place, ItemID, ItemRegistry, Market, Money, Tax, TaxRates};
+use crate::engine_interaction::remove_building;
 use crate::map::{Building, BuildingGen, BuildingID, Map, Zone, MAX_ZONE_AREA};
-use crate::map_dynamic::BuildingInfos;
+use crate::map_dynamic::{drop_off_passengers, BuildingInfos};
 use crate::souls::desire::WorkKind;
+use crate::souls::labor::Skill;
+use crate::souls::satisfaction::SatisfactionStats;
 use crate::utils::resources::Resources;
-use crate::utils::time::GameTime;
-use crate::world::{CompanyEnt, HumanEnt, HumanID, VehicleID};
+use crate::utils::time::{
+    GameInstant, GameTime, RecTimeInterval, Weekday, Weekdays, SECONDS_PER_DAY,
+};
+use crate::world::{CompanyEnt, CompanyID, HumanEnt, HumanID, VehicleEnt, VehicleID};
 use crate::World;
 use crate::{Egregoria, ParCommandBuffer, SoulID};
 use common::saveload::Encoder;
@@ -40,15 +45,21 @@ pub struct GoodsCompanyDescription {
     pub name: String,
     pub bgen: BuildingGen,
     pub kind: CompanyKind,
-    pub recipe: Recipe,
+    /// What the company can make, the first one is active when it is built
+    pub recipes: Vec<Recipe>,
+    /// Capacity of a warehouse for each item it stores
+    pub storage: Vec<(ItemID, i32)>,
     pub n_workers: i32,
     pub size: f32,
     pub asset_location: String,
     pub price: i64,
     pub zone: Option<Box<ZoneDescription>>,
+    pub schedule: WorkSchedule,
+    /// Required from the workers, setting their wage
+    pub skill: Skill,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ZoneDescription {
     pub floor: String,
     pub filler: String,
@@ -64,27 +75,43 @@ pub struct GoodsCompanyRegistry {
     pub descriptions: SlotMap<GoodsCompanyID, GoodsCompanyDescription>,
 }
 
-#[derive(Serialize, Deserialize)]
-struct RecipeDescription {
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct RecipeDescription {
     pub consumption: Vec<(String, i32)>,
     pub production: Vec<(String, i32)>,
     pub complexity: i32,
     pub storage_multiplier: i32,
 }
 
-#[derive(Serialize, Deserialize)]
-struct BuildingGenDescription {
+/// Shifts as written in companies.json, the days default to the whole week
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct WorkScheduleDescription {
+    /// Start and end hour of each shift
+    pub shifts: Vec<(i32, i32)>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub days: Option<Vec<Weekday>>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct BuildingGenDescription {
     pub kind: String,
     pub vertical_factor: Option<f32>,
     pub door_pos: Option<Vec2>,
 }
 
-#[derive(Serialize, Deserialize)]
-struct GoodsCompanyDescriptionJSON {
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct GoodsCompanyDescriptionJSON {
     pub name: String,
     pub bgen: BuildingGenDescription,
     pub kind: String,
-    pub recipe: RecipeDescription,
+    /// A company with a single recipe can use this instead of `recipes`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recipe: Option<RecipeDescription>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub recipes: Vec<RecipeDescription>,
+    /// Capacity of a warehouse for each item it stores
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub storage: Vec<(String, i32)>,
     pub n_workers: i32,
     pub n_trucks: Option<u32>,
     pub size: f32,
@@ -92,6 +119,69 @@ struct GoodsCompanyDescriptionJSON {
     pub price: i64,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub zone: Option<Box<ZoneDescription>>,
+    /// Office hours on workdays when missing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schedule: Option<WorkScheduleDescription>,
+    /// Basic when missing
+    #[serde(default)]
+    pub skill: Skill,
+}
+
+impl RecipeDescription {
+    /// None if one of the items isn't in the registry
+    fn resolve(self, registry: &ItemRegistry) -> Option<Recipe> {
+        let resolve_items = |items: Vec<(String, i32)>| {
+            items
+                .into_iter()
+                .map(|(item, qty)| Some((registry.try_id(&item)?, qty)))
+                .collect::<Option<Vec<_>>>()
+        };
+        Some(Recipe {
+            consumption: resolve_items(self.consumption)?,
+            production: resolve_items(self.production)?,
+            complexity: self.complexity,
+            storage_multiplier: self.storage_multiplier,
+        })
+    }
+}
+
+impl WorkScheduleDescription {
+    /// None if there is no shift or no day, or if an hour isn't one of the day
+    pub(crate) fn resolve(&self) -> Option<WorkSchedule> {
+        let hours = 0..24;
+        let shifts: Vec<RecTimeInterval> = self
+            .shifts
+            .iter()
+            .map(|&(start, end)| {
+                (hours.contains(&start) && hours.contains(&end) && start != end)
+                    .then(|| RecTimeInterval::new((start, 0), (end, 0)))
+            })
+            .collect::<Option<_>>()?;
+        let days = match self.days {
+            Some(ref days) => days.iter().copied().collect(),
+            None => Weekdays::ALL,
+        };
+        if shifts.is_empty() || days.is_empty() {
+            return None;
+        }
+        Some(WorkSchedule { shifts, days })
+    }
+}
+
+impl BuildingGenDescription {
+    /// None if the kind is unknown or if it is missing its parameters
+    pub(crate) fn resolve(&self) -> Option<BuildingGen> {
+        Some(match self.kind.as_ref() {
+            "farm" => BuildingGen::Farm,
+            "centered_door" => BuildingGen::CenteredDoor {
+                vertical_factor: self.vertical_factor?,
+            },
+            "no_walkway" => BuildingGen::NoWalkway {
+                door_pos: self.door_pos?,
+            },
+            _ => return None,
+        })
+    }
 }
 
 impl GoodsCompanyRegistry {
@@ -104,62 +194,83 @@ impl GoodsCompanyRegistry {
                     return;
                 }
             };
+        self.add_descriptions(descriptions, registry);
+    }
 
+    pub(crate) fn add_descriptions(
+        &mut self,
+        descriptions: Vec<GoodsCompanyDescriptionJSON>,
+        registry: &ItemRegistry,
+    ) {
         for descr in descriptions {
             let kind = match descr.kind.as_ref() {
                 "store" => CompanyKind::Store,
                 "network" => CompanyKind::Network,
-                "factory" => CompanyKind::Factory {
-                    n_trucks: descr
-                        .n_trucks
-                        .expect("expecting n_trucks when using kind factory"),
-                },
+                "factory" | "warehouse" => {
+                    let Some(n_trucks) = descr.n_trucks else {
+                        log::error!(
+                            "goods company {} is a {} without n_trucks",
+                            descr.name,
+                            descr.kind
+                        );
+                        continue;
+                    };
+                    if descr.kind == "factory" {
+                        CompanyKind::Factory { n_trucks }
+                    } else {
+                        CompanyKind::Warehouse { n_trucks }
+                    }
+                }
                 _ => {
                     log::error!("unknown goods company kind: {}", descr.kind);
                     continue;
                 }
             };
-            let recipe = Recipe {
-                consumption: descr
-                    .recipe
-                    .consumption
-                    .into_iter()
-                    .map(|(item, qty)| {
-                        let item_id = registry.id(&item);
-                        (item_id, qty)
-                    })
-                    .collect(),
-                production: descr
-                    .recipe
-                    .production
-                    .into_iter()
-                    .map(|(item, qty)| {
-                        let item_id = registry.id(&item);
-                        (item_id, qty)
-                    })
-                    .collect(),
-                complexity: descr.recipe.complexity,
-                storage_multiplier: descr.recipe.storage_multiplier,
+            let recipes: Option<Vec<Recipe>> = descr
+                .recipe
+                .into_iter()
+                .chain(descr.recipes)
+                .map(|recipe| recipe.resolve(registry))
+                .collect();
+            let Some(recipes) = recipes else {
+                log::error!("goods company {} uses an unknown item", descr.name);
+                continue;
             };
-
-            let bgen = match descr.bgen.kind.as_ref() {
-                "farm" => BuildingGen::Farm,
-                "centered_door" => BuildingGen::CenteredDoor {
-                    vertical_factor: descr
-                        .bgen
-                        .vertical_factor
-                        .expect("expecting vertical factor when using centered_door"),
-                },
-                "no_walkway" => BuildingGen::NoWalkway {
-                    door_pos: descr
-                        .bgen
-                        .door_pos
-                        .expect("expecting door_pos when using no_walkway"),
-                },
-                _ => {
-                    log::error!("unknown building gen kind: {}", descr.bgen.kind);
+            let storage: Option<Vec<(ItemID, i32)>> = descr
+                .storage
+                .iter()
+                .map(|(item, capacity)| Some((registry.try_id(item)?, *capacity)))
+                .collect();
+            let Some(storage) = storage else {
+                log::error!("goods company {} stores an unknown item", descr.name);
+                continue;
+            };
+            if let CompanyKind::Warehouse { .. } = kind {
+                if storage.is_empty() {
+                    log::error!("warehouse {} stores nothing", descr.name);
                     continue;
                 }
+            } else if recipes.is_empty() {
+                log::error!("goods company {} has no recipe", descr.name);
+                continue;
+            }
+
+            let schedule = match descr.schedule {
+                Some(ref schedule) => schedule.resolve(),
+                None => Some(WorkSchedule::default()),
+            };
+            let Some(schedule) = schedule else {
+                log::error!("goods company {} has an invalid schedule", descr.name);
+                continue;
+            };
+
+            let Some(bgen) = descr.bgen.resolve() else {
+                log::error!(
+                    "invalid building gen {} for goods company {}",
+                    descr.bgen.kind,
+                    descr.name
+                );
+                continue;
             };
             #[allow(unused_variables)]
             let id = self
@@ -169,12 +280,15 @@ impl GoodsCompanyRegistry {
                     name: descr.name,
                     bgen,
                     kind,
-                    recipe,
+                    recipes,
+                    storage,
                     n_workers: descr.n_workers,
                     size: descr.size,
                     asset_location: descr.asset_location,
                     price: descr.price,
                     zone: descr.zone,
+                    schedule,
+                    skill: descr.skill,
                 });
 
             #[cfg(not(test))]
@@ -205,6 +319,34 @@ impl Recipe {
             })
     }
 
+    /// Moves the orders of a company from this recipe to the new one.
+    /// The stock of every item is kept so it can still be sold or used later.
+    pub fn switch_to(&self, new: &Recipe, soul: SoulID, near: Vec2, market: &mut Market) {
+        for &(kind, _) in &self.consumption {
+            if !new.consumption.iter().any(|&(k, _)| k == kind) {
+                market.cancel_buy(soul, kind);
+            }
+        }
+        new.init(soul, near, market);
+    }
+
+    /// Margin of one production at the market prices
+    pub fn margin(&self, market: &Market) -> Money {
+        let value = |items: &[(ItemID, i32)]| -> Money {
+            items
+                .iter()
+                .map(|&(kind, qty)| market.price(kind) * qty as i64)
+                .sum()
+        };
+        value(&self.production) - value(&self.consumption)
+    }
+
+    pub fn has_inputs(&self, soul: SoulID, market: &Market) -> bool {
+        self.consumption
+            .iter()
+            .all(|&(kind, qty)| market.capital(soul, kind) >= qty)
+    }
+
     pub fn act(&self, soul: SoulID, near: Vec2, market: &mut Market) {
         for &(kind, qty) in &self.consumption {
             market.produce(soul, kind, -qty);
@@ -225,26 +367,173 @@ pub enum CompanyKind {
     Factory { n_trucks: u32 },
     // Buyers get their goods instantly delivered, useful for things like electricity/water/..
     Network,
+    // Buys goods when there is a surplus and sells them back later, delivering them like a factory
+    Warehouse { n_trucks: u32 },
+}
+
+impl CompanyKind {
+    pub fn n_trucks(&self) -> u32 {
+        match *self {
+            CompanyKind::Factory { n_trucks } | CompanyKind::Warehouse { n_trucks } => n_trucks,
+            CompanyKind::Store | CompanyKind::Network => 0,
+        }
+    }
 }
 
 debug_inspect_impl!(CompanyKind);
 
+/// When the workers of a company work, each of them works one of the shifts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkSchedule {
+    pub shifts: Vec<RecTimeInterval>,
+    /// Days of the week the shifts start on
+    pub days: Weekdays,
+}
+
+debug_inspect_impl!(WorkSchedule);
+
+impl Default for WorkSchedule {
+    /// From 8 to 18 on workdays
+    fn default() -> Self {
+        Self {
+            shifts: vec![RecTimeInterval::new((8, 0), (18, 0))],
+            days: Weekdays::WORKDAYS,
+        }
+    }
+}
+
+/// Cash a company starts with
+pub const COMPANY_STARTING_MONEY: Money = Money::new_bucks(1000);
+
+/// A company staying in debt for that many seconds goes bankrupt
+const BANKRUPTCY_DELAY: f64 = SECONDS_PER_DAY as f64;
+
+/// Totals of what went in and out of a company since it was founded
+#[derive(Debug, Default, Copy, Clone, Serialize, Deserialize, Inspect)]
+pub struct ProfitAndLoss {
+    /// From the goods it sold
+    pub revenue: Money,
+    /// For the goods it bought
+    pub purchases: Money,
+    pub wages: Money,
+    pub taxes: Money,
+    /// Operating profit the corporate tax was already paid on
+    pub taxed_profit: Money,
+}
+
+impl ProfitAndLoss {
+    /// Profit before taxes
+    pub fn operating_profit(&self) -> Money {
+        self.revenue - self.purchases - self.wages
+    }
+
+    pub fn profit(&self) -> Money {
+        self.operating_profit() - self.taxes
+    }
+}
+
 #[derive(Clone, Serialize, Deserialize, Inspect)]
 pub struct GoodsCompany {
     pub kind: CompanyKind,
-    pub recipe: Recipe,
+    /// Empty for warehouses
+    pub recipes: Vec<Recipe>,
+    /// Index of the recipe being made in `recipes`
+    pub active_recipe: usize,
+    /// Whether the company picks its recipe from the market prices and its stock
+    pub auto_recipe: bool,
+    /// Capacity for each item stored by a warehouse
+    pub storage: Vec<(ItemID, i32)>,
     pub building: BuildingID,
     pub max_workers: i32,
+    pub schedule: WorkSchedule,
+    /// Required from the workers, setting their wage
+    pub skill: Skill,
     /// In [0; 1] range, to show how much has been made until new product
     pub progress: f32,
     pub driver: Option<HumanID>,
     pub trucks: Vec<VehicleID>,
+    pub money: Money,
+    pub pnl: ProfitAndLoss,
+    /// When the company went in debt, if it still is
+    pub insolvent_since: Option<GameInstant>,
 }
 
 impl GoodsCompany {
+    /// None for warehouses, they have no recipe
+    pub fn recipe(&self) -> Option<&Recipe> {
+        self.recipes.get(self.active_recipe)
+    }
+
+    /// Recipe the company should make: the one with the best margin among those it has the
+    /// inputs for, or among all of them when it has none. The active one wins ties.
+    pub fn best_recipe(&self, soul: SoulID, market: &Market) -> usize {
+        let score = |i: usize| {
+            let recipe = &self.recipes[i];
+            (
+                recipe.has_inputs(soul, market),
+                recipe.margin(market),
+                i == self.active_recipe,
+            )
+        };
+        (0..self.recipes.len())
+            .max_by_key(|&i| score(i))
+            .unwrap_or(self.active_recipe)
+    }
+
+    /// Makes the given recipe the active one, the progress on the previous one is lost
+    pub fn set_recipe(&mut self, i: usize, soul: SoulID, near: Vec2, market: &mut Market) {
+        if i == self.active_recipe {
+            return;
+        }
+        let (Some(old), Some(new)) = (self.recipe(), self.recipes.get(i)) else {
+            return;
+        };
+        old.switch_to(new, soul, near, market);
+        self.active_recipe = i;
+        self.progress = 0.0;
+    }
+
+    /// Sets how much of the item a warehouse can store, the stock above it is sold first
+    pub fn set_capacity(&mut self, kind: ItemID, capacity: i32) {
+        let capacity = capacity.max(0);
+        match self.storage.iter_mut().find(|(k, _)| *k == kind) {
+            Some((_, c)) => *c = capacity,
+            None => self.storage.push((kind, capacity)),
+        }
+    }
+
     pub fn productivity(&self, workers: usize, zone: Option<&Zone>) -> f32 {
         workers as f32 / self.max_workers as f32 * zone.map_or(1.0, |z| z.area / MAX_ZONE_AREA)
     }
+
+    pub fn pay_wages(&mut self, wages: Money) {
+        self.money -= wages;
+        self.pnl.wages += wages;
+    }
+
+    pub fn pay(&mut self, cost: Money) {
+        self.money -= cost;
+        self.pnl.purchases += cost;
+    }
+
+    pub fn earn(&mut self, revenue: Money) {
+        self.money += revenue;
+        self.pnl.revenue += revenue;
+    }
+
+    /// Pays the corporate tax on the profit made since it was last paid, losses are carried
+    /// forward until the profit gets back above what was already taxed. Returns the tax paid.
+    pub fn pay_corporate_tax(&mut self, rates: &TaxRates) -> Money {
+        let profit = self.pnl.operating_profit();
+        if profit <= self.pnl.taxed_profit {
+            return Money::ZERO;
+        }
+        let tax = rates.levy(Tax::Corporate, profit - self.pnl.taxed_profit);
+        self.pnl.taxed_profit = profit;
+        self.pnl.taxes += tax;
+        self.money -= tax;
+        tax
+    }
 }
 
 pub fn company_soul(goria: &mut Egregoria, company: GoodsCompany) -> Option<SoulID> {
@@ -267,14 +556,14 @@ pub fn company_soul(goria: &mut Egregoria, company: GoodsCompany) -> Option<Soul
 
     let soul = SoulID::GoodsCompany(id);
 
-    let job_opening = goria.read::<ItemRegistry>().id("job-opening");
-
     {
         let m = &mut *goria.write::<Market>();
-        m.produce(soul, job_opening, company.max_workers);
-        m.sell_all(soul, door_pos.xy(), job_opening, 0);
-
-        company.recipe.init(soul, door_pos.xy(), m);
+        if let Some(recipe) = company.recipes.get(company.active_recipe) {
+            recipe.init(soul, door_pos.xy(), m);
+        }
+        for &(kind, _) in &company.storage {
+            m.register(soul, kind);
+        }
     }
 
     goria
@@ -286,30 +575,80 @@ pub fn company_soul(goria: &mut Egregoria, company: GoodsCompany) -> Option<Soul
 
 #[profiling::function]
 pub fn company_system(world: &mut World, res: &mut Resources) {
-    let delta = res.get::<GameTime>().unwrap().realdelta;
+    let time = res.get::<GameTime>().unwrap();
+    let delta = time.realdelta;
     let cbuf: &ParCommandBuffer<CompanyEnt> = &res.get().unwrap();
     let cbuf_human: &ParCommandBuffer<HumanEnt> = &res.get().unwrap();
     let binfos: &BuildingInfos = &res.get().unwrap();
     let market: &Market = &res.get().unwrap();
     let map: &Map = &res.get().unwrap();
+    let satisfaction: &SatisfactionStats = &res.get().unwrap();
 
     world.companies.iter_mut().for_each(|(me, c)| {
-        let n_workers = c.workers.0.len();
         let soul = SoulID::GoodsCompany(me);
         let b: &Building = unwrap_or!(map.buildings.get(c.comp.building), {
             cbuf.kill(me);
             return;
         });
 
-        if c.comp.recipe.should_produce(soul, market) {
+        // the jobs of the workers who died or left the city open again
+        c.workers.0.retain(|&w| world.humans.contains_key(w));
+        if c.comp.driver.is_some_and(|d| !world.humans.contains_key(d)) {
+            c.comp.driver = None;
+        }
+        let n_workers = c.workers.0.len();
+
+        let warehouse = matches!(c.comp.kind, CompanyKind::Warehouse { .. });
+        // the city pays the workers of the warehouses, so they can't go bankrupt
+        if c.comp.money < Money::ZERO && !warehouse {
+            let since = *c.comp.insolvent_since.get_or_insert(time.instant());
+            if since.elapsed(&time) > BANKRUPTCY_DELAY {
+                cbuf.exec_ent(me, move |goria| bankrupt(goria, me));
+                return;
+            }
+        } else {
+            c.comp.insolvent_since = None;
+        }
+
+        if warehouse {
+            if warehouse_orders_outdated(&c.comp.storage, soul, market) {
+                let storage = c.comp.storage.clone();
+                let bpos = b.door_pos.xy();
+                cbuf.exec_on(me, move |market| {
+                    warehouse_orders(&storage, soul, bpos, market);
+                });
+            }
+        } else if let Some(complexity) = c
+            .comp
+            .recipe()
+            .filter(|r| r.should_produce(soul, market))
+            .map(|r| r.complexity)
+        {
             c.comp.progress += c.comp.productivity(n_workers, b.zone.as_ref())
-                / c.comp.recipe.complexity as f32
+                * satisfaction.productivity(b.id)
+                / complexity as f32
                 * delta;
+        } else if c.comp.auto_recipe {
+            // the company can't make its recipe anyway, so the progress left on it is lost
+            let best = c.comp.best_recipe(soul, market);
+            if best != c.comp.active_recipe {
+                let bpos = b.door_pos.xy();
+                cbuf.exec_ent(me, move |goria| {
+                    let (world, res) = goria.world_res();
+                    let Some(c) = world.companies.get_mut(me) else {
+                        return;
+                    };
+                    c.comp
+                        .set_recipe(best, soul, bpos, &mut res.get_mut::<Market>().unwrap());
+                });
+            }
         }
 
         if c.comp.progress >= 1.0 {
             c.comp.progress -= 1.0;
-            let recipe = c.comp.recipe.clone();
+            let Some(recipe) = c.comp.recipe().cloned() else {
+                return;
+            };
             let bpos = b.door_pos;
 
             cbuf.exec_on(me, move |market| {
@@ -339,7 +678,7 @@ pub fn company_system(world: &mut World, res: &mut Resources) {
 
         if let Some(trade) = c.sold.0.drain(..1.min(c.sold.0.len())).next() {
             if let Some(driver) = c.comp.driver {
-                if let Some(ref mut w) = world.humans.get(driver).and_then(|h| h.work) {
+                if let Some(ref mut w) = world.humans.get(driver).and_then(|h| h.desires.work) {
                     if matches!(
                         w.kind,
                         WorkKind::Driver {
@@ -352,7 +691,7 @@ pub fn company_system(world: &mut World, res: &mut Resources) {
                         {
                             cbuf.exec_ent(me, move |goria| {
                                 if let Some(ref mut w) =
-                                    goria.world.humans.get(driver).and_then(|h| h.work)
+                                    goria.world.humans.get(driver).and_then(|h| h.desires.work)
                                 {
                                     if let WorkKind::Driver {
                                         ref mut deliver_order,
@@ -371,15 +710,26 @@ pub fn company_system(world: &mut World, res: &mut Resources) {
             }
         }
 
+        // workers on each shift, new workers going to the one with the fewest
+        let mut staffed = vec![0; c.comp.schedule.shifts.len()];
+        for &worker in c.workers.0.iter() {
+            let work = world
+                .humans
+                .get(worker)
+                .and_then(|w| w.desires.work.as_ref());
+            if let Some(n) = work.and_then(|w| staffed.get_mut(w.shift())) {
+                *n += 1;
+            }
+        }
+
         for &worker in c.workers.0.iter() {
             let Some(w) = world.humans.get(worker) else { continue; };
 
-            if w.work.is_none() {
+            if w.desires.work.is_none() {
                 let mut kind = WorkKind::Worker;
 
                 if let Some(truck) = c.comp.trucks.get(0) {
-                    if matches!(c.comp.kind, CompanyKind::Factory { .. }) && c.comp.driver.is_none()
-                    {
+                    if c.comp.kind.n_trucks() > 0 && c.comp.driver.is_none() {
                         kind = WorkKind::Driver {
                             deliver_order: None,
                             truck: *truck,
@@ -390,13 +740,341 @@ pub fn company_system(world: &mut World, res: &mut Resources) {
                 }
 
                 let offset = common::rand::randu(common::hash_u64(worker) as u32);
+                let Some(shift) = (0..staffed.len()).min_by_key(|&i| staffed[i]) else {
+                    continue;
+                };
+                staffed[shift] += 1;
 
                 let b = c.comp.building;
+                let inter = c.comp.schedule.shifts[shift];
+                let days = c.comp.schedule.days;
                 cbuf_human.exec_ent(worker, move |goria| {
-                    let Some(w) = goria.world.humans.get_mut(worker) else { return };
-                    w.work = Some(Work::new(b, kind, offset));
+                    let Some(w) = goria.world.humans.get_mut(worker) else {
+                        return;
+                    };
+                    w.desires.work = Some(Work::new(b, kind, inter, shift, days, offset));
                 });
             }
         }
     });
 }
+
+/// What a warehouse has on the market for one of the items it stores
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum WarehouseOrder {
+    Buy,
+    Sell(u32),
+    Nothing,
+}
+
+/// A warehouse buys the items it stores while their price is below the middle of its range,
+/// which means the supply exceeds the demand, and sells them back once the price is above.
+/// It only trades with other souls, buying outside would cost more than it could sell for.
+fn wanted_warehouse_order(
+    kind: ItemID,
+    capacity: i32,
+    soul: SoulID,
+    market: &Market,
+) -> Option<WarehouseOrder> {
+    let m = market.inner().get(&kind)?;
+    let surplus = m.price < (m.export_price() + m.import_price()) / 2;
+    let stock = market.capital(soul, kind);
+
+    let to_sell = if surplus { stock - capacity } else { stock };
+    Some(if to_sell > 0 {
+        WarehouseOrder::Sell(to_sell as u32)
+    } else if surplus && stock < capacity {
+        WarehouseOrder::Buy
+    } else {
+        WarehouseOrder::Nothing
+    })
+}
+
+/// None when the orders on the market are not ones the warehouse would place
+fn placed_warehouse_order(kind: ItemID, soul: SoulID, market: &Market) -> Option<WarehouseOrder> {
+    let m = market.inner().get(&kind)?;
+    match (m.sell_order(soul), m.buy_order(soul)) {
+        (Some(o), None) => Some(WarehouseOrder::Sell(o.qty)),
+        (None, Some(o)) if o.qty == 1 => Some(WarehouseOrder::Buy),
+        (None, None) => Some(WarehouseOrder::Nothing),
+        _ => None,
+    }
+}
+
+/// Whether the stock or the prices changed since the orders of the warehouse were placed
+fn warehouse_orders_outdated(storage: &[(ItemID, i32)], soul: SoulID, market: &Market) -> bool {
+    storage.iter().any(|&(kind, capacity)| {
+        wanted_warehouse_order(kind, capacity, soul, market)
+            .is_some_and(|o| Some(o) != placed_warehouse_order(kind, soul, market))
+    })
+}
+
+/// Places the orders the warehouse wants, leaving those already placed alone
+fn warehouse_orders(storage: &[(ItemID, i32)], soul: SoulID, near: Vec2, market: &mut Market) {
+    for &(kind, capacity) in storage {
+        let Some(wanted) = wanted_warehouse_order(kind, capacity, soul, market) else {
+            continue;
+        };
+        if Some(wanted) == placed_warehouse_order(kind, soul, market) {
+            continue;
+        }
+        match wanted {
+            WarehouseOrder::Sell(qty) => {
+                market.cancel_buy(soul, kind);
+                market.sell(soul, near, kind, qty, qty);
+            }
+            WarehouseOrder::Buy => {
+                market.cancel_sell(soul, kind);
+                market.buy(soul, near, kind, 1);
+            }
+            WarehouseOrder::Nothing => {
+                market.cancel_buy(soul, kind);
+                market.cancel_sell(soul, kind);
+            }
+        }
+    }
+}
+
+/// Lays off the workers of a company that stayed in debt for too long and frees its building
+/// and its trucks, the company is then removed along with its market orders.
+fn bankrupt(goria: &mut Egregoria, id: CompanyID) {
+    let Some(c) = goria.world.companies.get(id) else {
+        return;
+    };
+    let building = c.comp.building;
+    let workers = c.workers.0.clone();
+    let trucks = c.comp.trucks.clone();
+    log::info!("{:?} went bankrupt", id);
+
+    for worker in workers {
+        let Some(h) = goria.world.humans.get_mut(worker) else {
+            continue;
+        };
+        h.desires.work = None;
+    }
+
+    // drivers out on a delivery get off where they are
+    let (world, res) = goria.world_res();
+    let cbuf = res.get::<ParCommandBuffer<HumanEnt>>().unwrap();
+    for &truck in &trucks {
+        drop_off_passengers(world, &cbuf, truck);
+    }
+    drop(cbuf);
+    goria
+        .write::<ParCommandBuffer<VehicleEnt>>()
+        .kill_all(&trucks);
+
+    goria.write::<ParCommandBuffer<CompanyEnt>>().kill(id);
+    remove_building(goria, building);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        warehouse_orders, warehouse_orders_outdated, CompanyKind, GoodsCompanyRegistry, Recipe,
+        BANKRUPTCY_DELAY,
+    };
+    use crate::economy::{ItemRegistry, Market, Money};
+    use crate::map::BuildingID;
+    use crate::map_dynamic::BuildingInfos;
+    use crate::souls::human::spawn_resident;
+    use crate::tests::TestCtx;
+    use crate::transportation::Location;
+    use crate::utils::time::{GameInstant, GameTime};
+    use crate::world::CompanyID;
+    use crate::{BuildingKind, SoulID, WorldCommand};
+    use geom::vec2;
+
+    #[test]
+    fn test_company_in_debt_goes_bankrupt() {
+        let mut test = TestCtx::new();
+
+        let (house, driver) = test.build_house_with_resident();
+        let visitor = spawn_resident(&mut test.g, house, 30).unwrap();
+        let factory = test.company_kind("Cereal Factory");
+        test.apply(&[TestCtx::build_special(
+            BuildingKind::GoodsCompany(factory),
+            vec2(50.0, -520.0),
+        )]);
+        test.tick();
+
+        let (id, building, truck) = {
+            let map = test.g.map();
+            test.g
+                .world()
+                .companies
+                .iter()
+                .find(|(_, c)| {
+                    map.buildings()[c.comp.building].kind == BuildingKind::GoodsCompany(factory)
+                })
+                .map(|(id, c)| (id, c.comp.building, c.comp.trucks[0]))
+                .unwrap()
+        };
+        // out on a delivery
+        test.g.world_mut_unchecked().humans[driver].location = Location::Vehicle(truck);
+        test.g.world_mut_unchecked().humans[visitor].location = Location::Building(building);
+
+        // going in debt only starts the clock
+        let now = test.g.read::<GameTime>().timestamp;
+        test.g.world_mut_unchecked().companies[id].comp.money = Money::new_bucks(-1);
+        test.tick();
+        assert!(test.g.world().companies[id].comp.insolvent_since.is_some());
+        assert!(test.g.map().buildings().contains_key(building));
+
+        let since = GameInstant {
+            timestamp: now - BANKRUPTCY_DELAY - 1.0,
+        };
+        {
+            let c = &mut test.g.world_mut_unchecked().companies[id].comp;
+            c.insolvent_since = Some(since);
+        }
+        test.tick();
+        test.tick();
+
+        assert!(!test.g.map().buildings().contains_key(building));
+        assert!(!test.g.world().companies.contains_key(id));
+        assert!(!test.g.world().vehicles.contains_key(truck));
+        assert_eq!(test.g.world().humans[driver].location, Location::Outside);
+        assert!(test
+            .g
+            .world()
+            .humans
+            .values()
+            .all(|h| h.location != Location::Building(building)));
+        assert!(test.g.read::<BuildingInfos>().get(building).is_none());
+    }
+
+    /// Builds a bakery and gives it a second recipe making flour from cereal
+    fn bakery_with_flour_recipe(test: &mut TestCtx) -> (CompanyID, BuildingID) {
+        let bakery = test.company_kind("Bakery");
+        let (cereal, flour) = {
+            let items = test.g.read::<ItemRegistry>();
+            (items.id("cereal"), items.id("flour"))
+        };
+
+        test.apply(&[TestCtx::build_special(
+            BuildingKind::GoodsCompany(bakery),
+            vec2(50.0, -500.0),
+        )]);
+        test.tick();
+
+        let (id, building) = test
+            .g
+            .world()
+            .companies
+            .iter()
+            .map(|(id, c)| (id, c.comp.building))
+            .next()
+            .unwrap();
+        test.g.world_mut_unchecked().companies[id]
+            .comp
+            .recipes
+            .push(Recipe {
+                consumption: vec![(cereal, 1)],
+                production: vec![(flour, 1)],
+                complexity: 100,
+                storage_multiplier: 5,
+            });
+        (id, building)
+    }
+
+    #[test]
+    fn test_set_company_recipe() {
+        let mut test = TestCtx::new();
+
+        let (cereal, flour, bread) = {
+            let items = test.g.read::<ItemRegistry>();
+            (items.id("cereal"), items.id("flour"), items.id("bread"))
+        };
+        let (id, building) = bakery_with_flour_recipe(&mut test);
+        let soul = SoulID::GoodsCompany(id);
+
+        test.g.write::<Market>().produce(soul, bread, 3);
+
+        test.apply(&[WorldCommand::SetCompanyRecipe {
+            building,
+            recipe: Some(1),
+        }]);
+
+        let comp = &test.g.world().companies[id].comp;
+        assert_eq!(comp.active_recipe, 1);
+        assert!(!comp.auto_recipe);
+
+        let market = test.g.read::<Market>();
+        assert!(market.inner()[&flour].buy_order(soul).is_none());
+        assert!(market.inner()[&cereal].buy_order(soul).is_some());
+        assert_eq!(market.capital(soul, bread), 3);
+        drop(market);
+
+        test.apply(&[WorldCommand::SetCompanyRecipe {
+            building,
+            recipe: None,
+        }]);
+        assert!(test.g.world().companies[id].comp.auto_recipe);
+    }
+
+    #[test]
+    fn test_company_switches_to_the_recipe_it_has_inputs_for() {
+        let mut test = TestCtx::new();
+
+        let cereal = test.g.read::<ItemRegistry>().id("cereal");
+        let (id, _) = bakery_with_flour_recipe(&mut test);
+        {
+            let c = &mut test.g.world_mut_unchecked().companies[id].comp;
+            assert!(c.auto_recipe);
+            assert_eq!(c.active_recipe, 0);
+            // left over from the last production
+            c.progress = 0.3;
+        }
+        // no flour to bake bread, but cereal to make flour
+        test.g
+            .write::<Market>()
+            .produce(SoulID::GoodsCompany(id), cereal, 5);
+
+        test.tick();
+        test.tick();
+
+        let c = &test.g.world().companies[id].comp;
+        assert_eq!(c.active_recipe, 1);
+        assert!(c.progress < 0.3);
+    }
+
+    #[test]
+    fn test_warehouse_buys_surplus_and_sells_it_back() {
+        let test = TestCtx::new();
+
+        let registry = test.g.read::<GoodsCompanyRegistry>();
+        let warehouse = registry
+            .descriptions
+            .values()
+            .find(|d| matches!(d.kind, CompanyKind::Warehouse { .. }))
+            .unwrap();
+        assert!(!warehouse.storage.is_empty());
+        drop(registry);
+
+        let cereal = test.g.read::<ItemRegistry>().id("cereal");
+        let soul = SoulID::GoodsCompany(CompanyID::default());
+        let storage = [(cereal, 2)];
+        let pos = vec2(0.0, 0.0);
+        let mut market = test.g.write::<Market>();
+
+        let export = market.inner()[&cereal].export_price();
+        market.m(cereal).price = export;
+        warehouse_orders(&storage, soul, pos, &mut market);
+        assert_eq!(market.inner()[&cereal].buy_order(soul).unwrap().qty, 1);
+        assert!(market.inner()[&cereal].sell_order(soul).is_none());
+        assert!(!warehouse_orders_outdated(&storage, soul, &market));
+
+        // full, it waits for the price to go up
+        market.produce(soul, cereal, 2);
+        warehouse_orders(&storage, soul, pos, &mut market);
+        assert!(market.inner()[&cereal].buy_order(soul).is_none());
+        assert!(market.inner()[&cereal].sell_order(soul).is_none());
+
+        let import = market.inner()[&cereal].import_price();
+        market.m(cereal).price = import;
+        warehouse_orders(&storage, soul, pos, &mut market);
+        assert!(market.inner()[&cereal].buy_order(soul).is_none());
+        assert_eq!(market.inner()[&cereal].sell_order(soul).unwrap().qty, 2);
+    }
+}
@@ -1,8 +1,12 @@
-use crate::economy::{Bought, ItemRegistry, Market};
+use crate::economy::{Bought, ItemRegistry, Market, Money, Wallet, STARTING_SAVINGS};
 use crate::map::BuildingID;
 use crate::map_dynamic::{BuildingInfos, Destination, Itinerary, Router};
 use crate::physics::Speed;
-use crate::souls::desire::{BuyFood, Home, Work};
+use crate::souls::desire::{Desire, DesireCtx, Desires};
+use crate::souls::goods_company::GoodsCompanyRegistry;
+use crate::souls::labor::Skill;
+use crate::souls::population::{Age, ADULT_AGE, IMMIGRANT_AGE_SPREAD};
+use crate::souls::satisfaction::Satisfaction;
 use crate::transportation::{
     random_pedestrian_shirt_color, spawn_parked_vehicle, Location, Pedestrian, VehicleKind,
 };
@@ -80,112 +84,82 @@ impl HumanDecisionKind {
     }
 }
 
-#[derive(Debug)]
-enum NextDesire<'a> {
-    None,
-    Home(&'a mut Home),
-    Work(&'a mut Work),
-    Food(&'a mut BuyFood),
-}
-
 #[profiling::function]
 pub fn update_decision_system(world: &mut World, resources: &mut Resources) {
-    let ra = &*resources.get().unwrap();
-    let rb = &*resources.get().unwrap();
-    let rc = &*resources.get().unwrap();
-    let rd = &*resources.get().unwrap();
-    let re = &*resources.get().unwrap();
+    let cbuf: &ParCommandBuffer<HumanEnt> = &resources.get().unwrap();
+    let cbuf_freight: &ParCommandBuffer<FreightStationEnt> = &resources.get().unwrap();
+    let time: &GameTime = &resources.get().unwrap();
+    let binfos: &BuildingInfos = &resources.get().unwrap();
+    let map: &Map = &resources.get().unwrap();
+    let market: &Market = &resources.get().unwrap();
+    let companies: &GoodsCompanyRegistry = &resources.get().unwrap();
 
     world.humans.iter_mut().for_each(|(ent, h)| {
-        update_decision(
-            ra,
-            rb,
-            rc,
-            rd,
-            re,
-            ent,
-            &h.trans,
-            &h.location,
-            &mut h.router,
-            &mut h.bought,
-            &mut h.decision,
-            Some(&mut h.food),
-            Some(&mut h.home),
-            h.work.as_mut(),
-        )
-    });
-}
-
-#[allow(clippy::too_many_arguments)]
-pub fn update_decision(
-    cbuf: &ParCommandBuffer<HumanEnt>,
-    cbuf_freight: &ParCommandBuffer<FreightStationEnt>,
-    time: &GameTime,
-    binfos: &BuildingInfos,
-    map: &Map,
-    me: HumanID,
-    trans: &Transform,
-    loc: &Location,
-    router: &mut Router,
-    bought: &mut Bought,
-    decision: &mut HumanDecision,
-    food: Option<&mut BuyFood>,
-    home: Option<&mut Home>,
-    work: Option<&mut Work>,
-) {
-    if decision.wait != 0 {
-        decision.wait -= 1;
-        return;
-    }
-    let pos = trans.position;
-    decision.wait = (30.0 + common::rand::rand2(pos.x, pos.y) * 50.0) as u8;
-    if !decision.kind.update(router, binfos, map, cbuf_freight) {
-        return;
-    }
-
-    let mut decision_id = NextDesire::None;
-    let mut max_score = f32::NEG_INFINITY;
-
-    if let Some(home) = home {
-        let score = home.score();
-
-        if score > max_score {
-            max_score = score;
-            decision_id = NextDesire::Home(home);
+        let decision = &mut h.decision;
+        if decision.wait != 0 {
+            decision.wait -= 1;
+            return;
         }
-    }
-
-    if let Some(work) = work {
-        let score = work.score(time);
-
-        if score > max_score {
-            max_score = score;
-            decision_id = NextDesire::Work(work);
+        let pos = h.trans.position;
+        decision.wait = (30.0 + common::rand::rand2(pos.x, pos.y) * 50.0) as u8;
+        if !decision
+            .kind
+            .update(&mut h.router, binfos, map, cbuf_freight)
+        {
+            return;
         }
-    }
 
-    if let Some(food) = food {
-        let score = food.score(time, loc, bought);
+        let mut ctx = DesireCtx {
+            cbuf,
+            time,
+            binfos,
+            map,
+            market,
+            companies,
+            me: ent,
+            home: h.desires.home.house(),
+            trans: &h.trans,
+            loc: &h.location,
+            router: &h.router,
+            wallet: &h.wallet,
+            bought: &mut h.bought,
+        };
+        if let Some(kind) = decide(&mut ctx, h.desires.iter_mut()) {
+            decision.kind = kind;
+        }
+    });
+}
 
-        #[allow(unused_assignments)]
+/// Applies the desire with the highest score, if any
+pub fn decide<'a>(
+    ctx: &mut DesireCtx<'_>,
+    desires: impl Iterator<Item = &'a mut dyn Desire>,
+) -> Option<HumanDecisionKind> {
+    let mut best: Option<&mut dyn Desire> = None;
+    let mut max_score = f32::NEG_INFINITY;
+    for desire in desires {
+        let score = desire.score(ctx);
         if score > max_score {
             max_score = score;
-            decision_id = NextDesire::Food(food);
+            best = Some(desire);
         }
     }
-
-    match decision_id {
-        NextDesire::Home(home) => decision.kind = home.apply(),
-        NextDesire::Work(work) => decision.kind = work.apply(loc, router),
-        NextDesire::Food(food) => {
-            decision.kind = food.apply(cbuf, binfos, map, time, me, trans, loc, bought)
-        }
-        NextDesire::None => {}
-    }
+    best.map(|desire| desire.apply(ctx))
 }
 
+/// Spawns an adult moving into the house
 #[profiling::function]
 pub fn spawn_human(goria: &mut Egregoria, house: BuildingID) -> Option<HumanID> {
+    let years = {
+        let mut rng = goria.write::<RandProvider>();
+        ADULT_AGE + (rng.next_f32() * IMMIGRANT_AGE_SPREAD as f32) as i32
+    };
+    spawn_resident(goria, house, years)
+}
+
+/// Spawns a human of the given age living in the house.
+/// Adults come with a car and look for a job, children don't.
+pub fn spawn_resident(goria: &mut Egregoria, house: BuildingID, years: i32) -> Option<HumanID> {
     let map = goria.map();
     let housepos = map.buildings().get(house)?.door_pos;
     drop(map);
@@ -195,13 +169,20 @@ pub fn spawn_human(goria: &mut Egregoria, house: BuildingID) -> Option<HumanID>
     let hpos = goria.map().buildings().get(house)?.door_pos;
     let p = Pedestrian::new(&mut goria.write::<RandProvider>());
 
-    let registry = goria.read::<ItemRegistry>();
-    let time = goria.read::<GameTime>().instant();
-
-    let food = BuyFood::new(time, &registry);
-    drop(registry);
-
-    let car = spawn_parked_vehicle(goria, VehicleKind::Car, housepos);
+    let age = Age::new(goria.read::<GameTime>().daytime.day - years);
+    let skill = Skill::random(goria.write::<RandProvider>().next_f32());
+    let desires = Desires::new(
+        house,
+        &goria.read::<GameTime>(),
+        &goria.read::<ItemRegistry>(),
+    );
+
+    let adult = years >= ADULT_AGE;
+    let car = if adult {
+        spawn_parked_vehicle(goria, VehicleKind::Car, housepos)
+    } else {
+        None
+    };
 
     let id = goria.world.insert(HumanEnt {
         trans: Transform::new(hpos),
@@ -209,22 +190,24 @@ pub fn spawn_human(goria: &mut Egregoria, house: BuildingID) -> Option<HumanID>
         pedestrian: p,
         it: Itinerary::NONE,
         speed: Speed::default(),
+        age,
+        skill,
+        satisfaction: Satisfaction::default(),
         decision: HumanDecision::default(),
-        home: Home::new(house),
-        food,
+        desires,
         bought: Bought::default(),
+        wallet: Wallet::new(if adult { STARTING_SAVINGS } else { Money::ZERO }),
         router: Router::new(car),
         collider: None,
-        work: None,
     });
 
     let soul = SoulID::Human(id);
-    let mut m = goria.write::<Market>();
-    let registry = goria.read::<ItemRegistry>();
-    m.buy(soul, housepos.xy(), registry.id("job-opening"), 1);
 
-    goria.write::<BuildingInfos>().get_in(house, soul);
-    goria.write::<BuildingInfos>().set_owner(house, soul);
+    let mut binfos = goria.write::<BuildingInfos>();
+    binfos.get_in(house, soul);
+    if binfos.owner(house).is_none() {
+        binfos.set_owner(house, soul);
+    }
 
     Some(id)
 }
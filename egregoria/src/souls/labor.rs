@@ -0,0 +1,292 @@
+//! Matching the humans looking for a job with the jobs the companies offer.
+//!
+//! Each job requires a skill level and pays according to it. The unemployed adults take the
+//! opening paying the most for its commute among those they are skilled enough for, and workers
+//! switch to another job when one pays much more for its commute than theirs.
+
+use crate::economy::{Money, HISTORY_SIZE, LEVEL_FREQS, WORKER_CONSUMPTION_PER_SECOND};
+use crate::map::{BuildingID, Map};
+use crate::souls::desire::WorkKind;
+use crate::souls::population::PopulationHistoryLevel;
+use crate::utils::resources::Resources;
+use crate::utils::time::{GameTime, Tick};
+use crate::world::{CompanyID, HumanID};
+use crate::World;
+use geom::Vec2;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Ticks between two matchings of the job seekers with the openings
+const MATCHING_FREQ: u32 = 50;
+/// Workers look for a better job once every that many matchings
+const SWITCH_FREQ: u32 = 10;
+/// A job this far from home is worth half as much as the same job next door
+const COMMUTE_HALVING_DISTANCE: f32 = 1000.0;
+/// A worker switches to a job worth that much more than theirs
+const SWITCH_GAIN: f32 = 1.5;
+
+/// Education and experience of a human, and what a job requires from its workers
+#[derive(
+    Debug, Default, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Skill {
+    #[default]
+    Basic,
+    Trained,
+    Expert,
+}
+
+debug_inspect_impl!(Skill);
+
+impl Skill {
+    /// Skill of a new human, `r` being uniform in [0; 1)
+    pub fn random(r: f32) -> Skill {
+        match r {
+            r if r < 0.6 => Skill::Basic,
+            r if r < 0.9 => Skill::Trained,
+            _ => Skill::Expert,
+        }
+    }
+
+    /// Paid each second to the workers of a job requiring this skill, before the income tax
+    pub fn wage(self) -> Money {
+        match self {
+            Skill::Basic => WORKER_CONSUMPTION_PER_SECOND,
+            Skill::Trained => WORKER_CONSUMPTION_PER_SECOND * 3 / 2,
+            Skill::Expert => WORKER_CONSUMPTION_PER_SECOND * 2,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Skill::Basic => "Basic",
+            Skill::Trained => "Trained",
+            Skill::Expert => "Expert",
+        }
+    }
+}
+
+/// How much a job is worth to a human living `distance` away from it
+fn job_value(wage: Money, distance: f32) -> f32 {
+    wage.inner() as f32 / (1.0 + distance / COMMUTE_HALVING_DISTANCE)
+}
+
+/// The jobs of a company
+struct Jobs {
+    company: CompanyID,
+    pos: Vec2,
+    skill: Skill,
+    /// Jobs nobody took yet
+    open: i32,
+}
+
+/// The job worth the most to a human of that skill living at `home`, if one is open
+fn best_job(jobs: &[Jobs], skill: Skill, home: Vec2) -> Option<(usize, f32)> {
+    jobs.iter()
+        .enumerate()
+        .filter(|(_, j)| j.open > 0 && j.skill <= skill)
+        .map(|(i, j)| (i, job_value(j.skill.wage(), home.distance(j.pos))))
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+}
+
+/// Who is looking for a job and which jobs are left
+#[derive(Default, Serialize, Deserialize)]
+pub struct LaborMarket {
+    /// Unemployed adults after the last matching
+    unemployed: u32,
+    employed: u32,
+    /// Jobs left open after the last matching
+    vacancies: u32,
+    /// Unemployed adults at the end of each bin
+    history: [PopulationHistoryLevel; LEVEL_FREQS.len()],
+    cursors: [usize; LEVEL_FREQS.len()],
+    /// Since the start of the game
+    hires: u32,
+    switches: u32,
+}
+
+impl LaborMarket {
+    pub fn unemployed(&self) -> u32 {
+        self.unemployed
+    }
+
+    pub fn employed(&self) -> u32 {
+        self.employed
+    }
+
+    pub fn vacancies(&self) -> u32 {
+        self.vacancies
+    }
+
+    /// Share of the adults without a job
+    pub fn unemployment_rate(&self) -> f32 {
+        let adults = self.unemployed + self.employed;
+        if adults == 0 {
+            return 0.0;
+        }
+        self.unemployed as f32 / adults as f32
+    }
+
+    pub fn hires(&self) -> u32 {
+        self.hires
+    }
+
+    pub fn switches(&self) -> u32 {
+        self.switches
+    }
+
+    pub fn cursors(&self) -> &[usize] {
+        &self.cursors
+    }
+
+    pub fn history(&self, level: usize) -> Option<&PopulationHistoryLevel> {
+        self.history.get(level)
+    }
+
+    fn advance(&mut self, tick: u32) {
+        for (c, freq) in self.cursors.iter_mut().zip(&LEVEL_FREQS) {
+            if tick.is_multiple_of(*freq) {
+                *c = (*c + 1) % HISTORY_SIZE;
+            }
+        }
+        for (level, cursor) in self.history.iter_mut().zip(&self.cursors) {
+            level.past_ring[*cursor] = self.unemployed;
+        }
+    }
+}
+
+/// Hires the unemployed adults and lets the workers switch to better jobs. The company then
+/// gives its new workers their shift in `company_system`.
+#[profiling::function]
+pub fn labor_market_system(world: &mut World, res: &mut Resources) {
+    let tick = res.get::<Tick>().unwrap().0;
+    let mut labor = res.get_mut::<LaborMarket>().unwrap();
+    labor.advance(tick);
+    if !tick.is_multiple_of(MATCHING_FREQ) {
+        return;
+    }
+    let map = res.get::<Map>().unwrap();
+    let day = res.get::<GameTime>().unwrap().daytime.day;
+    let switching = (tick / MATCHING_FREQ).is_multiple_of(SWITCH_FREQ);
+
+    let mut jobs: Vec<Jobs> = vec![];
+    let mut workplaces: BTreeMap<BuildingID, usize> = BTreeMap::new();
+    for (id, c) in world.companies.iter() {
+        let Some(b) = map.buildings().get(c.comp.building) else {
+            continue;
+        };
+        workplaces.insert(c.comp.building, jobs.len());
+        jobs.push(Jobs {
+            company: id,
+            pos: b.door_pos.xy(),
+            skill: c.comp.skill,
+            open: c.comp.max_workers - c.workers.0.len() as i32,
+        });
+    }
+
+    let mut unemployed = 0;
+    let mut employed = 0;
+    // who goes to work where, leaving which company
+    let mut moves: Vec<(HumanID, Option<CompanyID>, CompanyID)> = vec![];
+    for (id, h) in world.humans.iter() {
+        if !h.age.is_adult(day) {
+            continue;
+        }
+        let Some(home) = map
+            .buildings()
+            .get(h.desires.home.house())
+            .map(|b| b.door_pos.xy())
+        else {
+            continue;
+        };
+        let Some(ref work) = h.desires.work else {
+            unemployed += 1;
+            if let Some((best, _)) = best_job(&jobs, h.skill, home) {
+                jobs[best].open -= 1;
+                moves.push((id, None, jobs[best].company));
+            }
+            continue;
+        };
+        employed += 1;
+        if !switching || matches!(work.kind, WorkKind::Driver { .. }) {
+            continue;
+        }
+        let Some(&current) = workplaces.get(&work.workplace()) else {
+            continue;
+        };
+        let Some((best, value)) = best_job(&jobs, h.skill, home) else {
+            continue;
+        };
+        let current_value = job_value(jobs[current].skill.wage(), home.distance(jobs[current].pos));
+        if value > current_value * SWITCH_GAIN {
+            jobs[best].open -= 1;
+            jobs[current].open += 1;
+            moves.push((id, Some(jobs[current].company), jobs[best].company));
+        }
+    }
+    drop(map);
+
+    let n_hired = moves.iter().filter(|(_, from, _)| from.is_none()).count() as u32;
+    labor.hires += n_hired;
+    labor.switches += moves.len() as u32 - n_hired;
+    labor.unemployed = unemployed - n_hired;
+    labor.employed = employed + n_hired;
+    labor.vacancies = jobs.iter().map(|j| j.open.max(0) as u32).sum();
+
+    for (human, from, to) in moves {
+        if let Some(c) = from.and_then(|from| world.companies.get_mut(from)) {
+            c.workers.0.retain(|&w| w != human);
+        }
+        if let Some(h) = world.humans.get_mut(human) {
+            h.desires.work = None;
+        }
+        if let Some(c) = world.companies.get_mut(to) {
+            c.workers.0.push(human);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LaborMarket, Skill};
+    use crate::souls::goods_company::GoodsCompanyRegistry;
+    use crate::souls::human::spawn_human;
+    use crate::tests::TestCtx;
+    use crate::BuildingKind;
+    use geom::{vec2, vec3};
+
+    #[test]
+    fn test_job_seekers_take_the_closest_job_they_can_do() {
+        let mut test = TestCtx::new();
+
+        test.build_roads(&[vec3(0.0, 0.0, 0.0), vec3(3000.0, 0.0, 0.0)]);
+        let house = test.build_house_near(vec2(50.0, 50.0));
+        let near = test.company_kind("Bakery");
+        let far = test.company_kind("Supermarket");
+        let expert = test.company_kind("High tech facility");
+        assert_eq!(
+            test.g.read::<GoodsCompanyRegistry>().descriptions[expert].skill,
+            Skill::Expert
+        );
+
+        let build = |id, x| TestCtx::build_special(BuildingKind::GoodsCompany(id), vec2(x, -50.0));
+        test.apply(&[build(expert, 50.0), build(near, 300.0), build(far, 2500.0)]);
+        let human = spawn_human(&mut test.g, house).unwrap();
+        test.g.world.humans.get_mut(human).unwrap().skill = Skill::Basic;
+
+        for _ in 0..100 {
+            test.tick();
+        }
+
+        let work = test.g.world().humans[human].desires.work.unwrap();
+        let workplace = work.workplace();
+        assert_eq!(
+            test.g.map().buildings()[workplace].kind,
+            BuildingKind::GoodsCompany(near)
+        );
+        let labor = test.g.read::<LaborMarket>();
+        assert_eq!(labor.unemployed(), 0);
+        assert_eq!(labor.hires(), 1);
+    }
+}
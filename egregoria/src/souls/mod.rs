@@ -1,8 +1,10 @@
 use crate::map::{BuildingID, BuildingKind};
 use crate::map_dynamic::BuildingInfos;
 use crate::souls::freight_station::freight_station_soul;
-use crate::souls::goods_company::{company_soul, CompanyKind, GoodsCompany, GoodsCompanyRegistry};
-use crate::souls::human::spawn_human;
+use crate::souls::goods_company::{
+    company_soul, GoodsCompany, GoodsCompanyRegistry, COMPANY_STARTING_MONEY,
+};
+use crate::souls::train_station::train_station_soul;
 use crate::transportation::{spawn_parked_vehicle, VehicleKind};
 use crate::Egregoria;
 use geom::Vec3;
@@ -14,8 +16,12 @@ pub mod desire;
 pub mod freight_station;
 pub mod goods_company;
 pub mod human;
+pub mod labor;
+pub mod population;
+pub mod satisfaction;
+pub mod train_station;
 
-/// Adds souls to empty buildings
+/// Adds souls to empty buildings, houses are filled by immigration instead
 #[profiling::function]
 pub(crate) fn add_souls_to_empty_buildings(goria: &mut Egregoria) {
     let map = goria.map();
@@ -38,21 +44,20 @@ pub(crate) fn add_souls_to_empty_buildings(goria: &mut Egregoria) {
     let mut n_souls_added = 0;
 
     for &(build_id, _) in empty_buildings
-        .get(&BuildingKind::House)
+        .get(&BuildingKind::RailFreightStation)
         .unwrap_or(&vec![])
         .iter()
-        .take(50)
     {
-        spawn_human(goria, build_id);
+        freight_station_soul(goria, build_id);
         n_souls_added += 1;
     }
 
     for &(build_id, _) in empty_buildings
-        .get(&BuildingKind::RailFreightStation)
+        .get(&BuildingKind::TrainStation)
         .unwrap_or(&vec![])
         .iter()
     {
-        freight_station_soul(goria, build_id);
+        train_station_soul(goria, build_id);
         n_souls_added += 1;
     }
 
@@ -67,7 +72,8 @@ pub(crate) fn add_souls_to_empty_buildings(goria: &mut Egregoria) {
         let ckind = des.kind;
         let mk_trucks = |goria: &mut Egregoria| {
             let mut trucks = vec![];
-            if let CompanyKind::Factory { n_trucks } = ckind {
+            let n_trucks = ckind.n_trucks();
+            if n_trucks > 0 {
                 for _ in 0..n_trucks {
                     trucks.extend(spawn_parked_vehicle(goria, VehicleKind::Truck, pos))
                 }
@@ -81,14 +87,22 @@ pub(crate) fn add_souls_to_empty_buildings(goria: &mut Egregoria) {
         let comp = GoodsCompany {
             kind: des.kind,
             building: build_id,
-            recipe: des.recipe.clone(),
+            recipes: des.recipes.clone(),
+            active_recipe: 0,
+            auto_recipe: true,
+            storage: des.storage.clone(),
             max_workers: des.n_workers,
+            schedule: des.schedule.clone(),
+            skill: des.skill,
             progress: 0.0,
             driver: None,
             trucks: {
                 drop(registry);
                 unwrap_or!(mk_trucks(goria), continue)
             },
+            money: COMPANY_STARTING_MONEY,
+            pnl: Default::default(),
+            insolvent_since: None,
         };
 
         company_soul(goria, comp);
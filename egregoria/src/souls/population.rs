@@ -0,0 +1,409 @@
+//! Births, deaths and migrations of the humans living in the city.
+//!
+//! The humans living in the same house form a household. Households move into the vacant houses
+//! while the city has jobs for them and is pleasant to live in, and the ones without any job
+//! leave when jobs are lacking, as do the unhappy ones.
+
+use crate::economy::{HISTORY_SIZE, LEVEL_FREQS};
+use crate::map::{BuildingID, BuildingKind};
+use crate::map_dynamic::BuildingInfos;
+use crate::souls::human::{spawn_human, spawn_resident};
+use crate::souls::satisfaction::{SatisfactionStats, NEUTRAL_SATISFACTION};
+use crate::utils::rand_provider::RandProvider;
+use crate::utils::time::{GameTime, Tick, HOURS_PER_DAY};
+use crate::world::{HumanEnt, HumanID};
+use crate::{Egregoria, ParCommandBuffer};
+use egui_inspect::Inspect;
+use serde::{Deserialize, Serialize};
+use serde_big_array::BigArray;
+use std::collections::BTreeMap;
+
+/// Age at which humans start working and can have children
+pub const ADULT_AGE: i32 = 18;
+/// Age after which humans no longer have children
+const FERTILE_AGE_END: i32 = 45;
+/// Age from which the chance to die rises
+const OLD_AGE: i32 = 60;
+/// No human lives longer
+const MAX_AGE: i32 = 100;
+/// Immigrants are adults up to this many years older than `ADULT_AGE`
+pub const IMMIGRANT_AGE_SPREAD: i32 = 30;
+
+pub const MAX_HOUSEHOLD_SIZE: usize = 4;
+/// Chance each day for a household with two fertile adults to have a child
+const BIRTH_CHANCE: f32 = 0.1;
+/// Households moving in each hour even when the jobs are all taken
+const BASE_IMMIGRANTS: i64 = 2;
+const MAX_IMMIGRANTS_PER_HOUR: usize = 10;
+/// Chance each hour for a household without any job to leave when jobs are lacking,
+/// or for an unhappy household to leave
+const EMIGRATION_CHANCE: f32 = 0.1;
+/// Households below this satisfaction leave even when they have jobs
+const UNHAPPY: f32 = 0.25;
+/// Immigrants coming in each hour for every point of satisfaction of the city above neutral
+const SATISFACTION_PULL: f32 = 10.0;
+
+/// Age of a human, one game day being one year of their life
+#[derive(Inspect, Copy, Clone, Debug, Serialize, Deserialize)]
+pub struct Age {
+    /// Day of birth
+    pub born: i32,
+}
+
+impl Age {
+    pub fn new(born: i32) -> Self {
+        Self { born }
+    }
+
+    pub fn years(&self, day: i32) -> i32 {
+        day - self.born
+    }
+
+    pub fn is_adult(&self, day: i32) -> bool {
+        self.years(day) >= ADULT_AGE
+    }
+}
+
+/// Chance to die each day
+fn death_chance(years: i32) -> f32 {
+    if years >= MAX_AGE {
+        return 1.0;
+    }
+    let old = (years - OLD_AGE).max(0) as f32 / (MAX_AGE - OLD_AGE) as f32;
+    0.002 + 0.3 * old * old
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum PopulationEvent {
+    Birth,
+    Death,
+    Immigration,
+    Emigration,
+}
+
+impl PopulationEvent {
+    pub const ALL: [PopulationEvent; 4] = [
+        PopulationEvent::Birth,
+        PopulationEvent::Death,
+        PopulationEvent::Immigration,
+        PopulationEvent::Emigration,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PopulationEvent::Birth => "Births",
+            PopulationEvent::Death => "Deaths",
+            PopulationEvent::Immigration => "Immigrants",
+            PopulationEvent::Emigration => "Emigrants",
+        }
+    }
+}
+
+/// Number of humans at one frequency level
+#[derive(Serialize, Deserialize)]
+pub struct PopulationHistoryLevel {
+    #[serde(with = "BigArray")]
+    pub past_ring: [u32; HISTORY_SIZE],
+}
+
+impl Default for PopulationHistoryLevel {
+    fn default() -> Self {
+        Self {
+            past_ring: [0; HISTORY_SIZE],
+        }
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct PopulationHistory {
+    levels: [PopulationHistoryLevel; LEVEL_FREQS.len()],
+    /// Since the start of the game
+    total: u32,
+}
+
+/// How many humans live in the city and what made it change
+#[derive(Default, Serialize, Deserialize)]
+pub struct PopulationStats {
+    /// Population at the end of each bin
+    population: PopulationHistory,
+    events: BTreeMap<PopulationEvent, PopulationHistory>,
+    cursors: [usize; LEVEL_FREQS.len()],
+    /// Last hour the demographics were updated
+    last_hour: Option<i32>,
+}
+
+impl PopulationStats {
+    pub fn cursors(&self) -> &[usize] {
+        &self.cursors
+    }
+
+    pub fn population(&self, level: usize) -> Option<&PopulationHistoryLevel> {
+        self.population.levels.get(level)
+    }
+
+    pub fn iter_histories(
+        &self,
+        level: usize,
+    ) -> impl Iterator<Item = (PopulationEvent, &PopulationHistoryLevel)> {
+        self.events
+            .iter()
+            .filter_map(move |(event, history)| Some((*event, history.levels.get(level)?)))
+    }
+
+    pub fn total(&self, event: PopulationEvent) -> u32 {
+        self.events.get(&event).map_or(0, |h| h.total)
+    }
+
+    pub fn record(&mut self, event: PopulationEvent, n: u32) {
+        if n == 0 {
+            return;
+        }
+        let h = self.events.entry(event).or_default();
+        h.total += n;
+        for (level, cursor) in h.levels.iter_mut().zip(&self.cursors) {
+            level.past_ring[*cursor] += n;
+        }
+    }
+
+    pub fn advance(&mut self, tick: u32, population: u32) {
+        for (c_i, (c, freq)) in self.cursors.iter_mut().zip(&LEVEL_FREQS).enumerate() {
+            if tick.is_multiple_of(*freq) {
+                *c = (*c + 1) % HISTORY_SIZE;
+                self.events.values_mut().for_each(|h| {
+                    h.levels[c_i].past_ring[*c] = 0;
+                });
+            }
+        }
+        for (level, cursor) in self.population.levels.iter_mut().zip(&self.cursors) {
+            level.past_ring[*cursor] = population;
+        }
+    }
+}
+
+/// Updates the demographics once per hour: births and deaths once per day,
+/// and migrations every hour.
+#[profiling::function]
+pub(crate) fn population_system(goria: &mut Egregoria) {
+    let tick = goria.read::<Tick>().0;
+    let daytime = goria.read::<GameTime>().daytime;
+    let hour = daytime.day * HOURS_PER_DAY + daytime.hour;
+
+    let mut stats = goria.write::<PopulationStats>();
+    stats.advance(tick, goria.world.humans.len() as u32);
+    let last_hour = stats.last_hour.replace(hour);
+    drop(stats);
+
+    match last_hour {
+        Some(last) if last < hour => {
+            if last / HOURS_PER_DAY < daytime.day {
+                daily_update(goria, daytime.day);
+            }
+            migration_update(goria, daytime.day);
+        }
+        _ => {}
+    }
+}
+
+fn daily_update(goria: &mut Egregoria, day: i32) {
+    let mut rng = goria.write::<RandProvider>();
+
+    let mut dead = vec![];
+    // members and fertile adults of each household
+    let mut households: BTreeMap<BuildingID, (usize, usize)> = BTreeMap::new();
+    for (id, h) in goria.world.humans.iter() {
+        let years = h.age.years(day);
+        if rng.next_f32() < death_chance(years) {
+            dead.push(id);
+            continue;
+        }
+        let household = households.entry(h.desires.home.house()).or_default();
+        household.0 += 1;
+        if (ADULT_AGE..=FERTILE_AGE_END).contains(&years) {
+            household.1 += 1;
+        }
+    }
+
+    let births: Vec<BuildingID> = households
+        .into_iter()
+        .filter(|&(_, (members, fertile))| {
+            fertile >= 2 && members < MAX_HOUSEHOLD_SIZE && rng.next_f32() < BIRTH_CHANCE
+        })
+        .map(|(house, _)| house)
+        .collect();
+    drop(rng);
+
+    goria.write::<ParCommandBuffer<HumanEnt>>().kill_all(&dead);
+
+    let mut n_births = 0;
+    for house in births {
+        if spawn_resident(goria, house, 0).is_some() {
+            n_births += 1;
+        }
+    }
+
+    let mut stats = goria.write::<PopulationStats>();
+    stats.record(PopulationEvent::Death, dead.len() as u32);
+    stats.record(PopulationEvent::Birth, n_births);
+}
+
+/// Households move into the vacant houses as long as there are jobs for them, more of them when
+/// the city is satisfying. The ones without jobs leave when there are more unemployed than jobs,
+/// and the unhappy ones leave anyway.
+fn migration_update(goria: &mut Egregoria, day: i32) {
+    let map = goria.map();
+    let binfos = goria.read::<BuildingInfos>();
+    let vacant: Vec<BuildingID> = map
+        .buildings()
+        .iter()
+        .filter(|(_, b)| matches!(b.kind, BuildingKind::House))
+        .filter(|(id, _)| {
+            binfos
+                .get(*id)
+                .is_some_and(|i| i.owner.is_none() && i.inside.is_empty())
+        })
+        .map(|(id, _)| id)
+        .collect();
+    drop(binfos);
+    drop(map);
+
+    let jobs: i64 = goria
+        .world
+        .companies
+        .values()
+        .map(|c| (c.comp.max_workers as i64 - c.workers.0.len() as i64).max(0))
+        .sum();
+
+    let mut unemployed = 0;
+    // members of each household, its unemployed adults and whether one of them works
+    let mut households: BTreeMap<BuildingID, (Vec<HumanID>, i64, bool)> = BTreeMap::new();
+    for (id, h) in goria.world.humans.iter() {
+        let household = households.entry(h.desires.home.house()).or_default();
+        household.0.push(id);
+        household.2 |= h.desires.work.is_some();
+        if h.age.is_adult(day) && h.desires.work.is_none() {
+            household.1 += 1;
+            unemployed += 1;
+        }
+    }
+    let satisfaction = goria.read::<SatisfactionStats>();
+    let appeal = ((satisfaction.city() - NEUTRAL_SATISFACTION) * SATISFACTION_PULL) as i64;
+    let unhappy: Vec<bool> = households
+        .keys()
+        .map(|&house| satisfaction.building(house).is_some_and(|s| s < UNHAPPY))
+        .collect();
+    drop(satisfaction);
+    let pull = jobs - unemployed;
+
+    let n_immigrating = vacant
+        .len()
+        .min(MAX_IMMIGRANTS_PER_HOUR)
+        .min((pull + appeal + BASE_IMMIGRANTS).max(0) as usize);
+    let mut n_immigrants = 0;
+    for &house in &vacant[..n_immigrating] {
+        let (adults, children) = {
+            let mut rng = goria.write::<RandProvider>();
+            let adults = 1 + (rng.next_f32() < 0.5) as usize;
+            let children = (rng.next_f32() * (MAX_HOUSEHOLD_SIZE - adults + 1) as f32) as usize;
+            let children: Vec<i32> = (0..children)
+                .map(|_| (rng.next_f32() * ADULT_AGE as f32) as i32)
+                .collect();
+            (adults, children)
+        };
+        for _ in 0..adults {
+            n_immigrants += spawn_human(goria, house).is_some() as u32;
+        }
+        for years in children {
+            n_immigrants += spawn_resident(goria, house, years).is_some() as u32;
+        }
+    }
+
+    let mut leaving = vec![];
+    let mut lacking = -pull;
+    for ((members, jobless, has_worker), unhappy) in households.into_values().zip(unhappy) {
+        let jobs_lacking = lacking > 0 && !has_worker;
+        if !(jobs_lacking || unhappy)
+            || goria.write::<RandProvider>().next_f32() >= EMIGRATION_CHANCE
+        {
+            continue;
+        }
+        lacking -= jobless;
+        leaving.extend(members);
+    }
+    goria
+        .write::<ParCommandBuffer<HumanEnt>>()
+        .kill_all(&leaving);
+
+    let mut stats = goria.write::<PopulationStats>();
+    stats.record(PopulationEvent::Immigration, n_immigrants);
+    stats.record(PopulationEvent::Emigration, leaving.len() as u32);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PopulationEvent, PopulationStats, MAX_AGE};
+    use crate::map_dynamic::BuildingInfos;
+    use crate::tests::TestCtx;
+    use crate::utils::time::{GameTime, SECONDS_PER_DAY, SECONDS_PER_HOUR};
+    use crate::WorldCommand;
+    use geom::{vec2, vec3};
+
+    fn skip_time(test: &mut TestCtx, seconds: i32) {
+        let t = test.g.read::<GameTime>().timestamp;
+        test.apply(&[WorldCommand::SetGameTime(GameTime::new(
+            0.0,
+            t + seconds as f64,
+        ))]);
+        test.tick();
+    }
+
+    #[test]
+    fn test_immigrants_move_in_and_the_old_die() {
+        let mut test = TestCtx::new();
+
+        test.build_roads(&[vec3(0.0, -500.0, 0.0), vec3(100.0, -500.0, 0.0)]);
+        let house = test.build_house_near(vec2(50.0, -450.0));
+        test.tick();
+        assert!(test.g.world.humans.is_empty());
+
+        skip_time(&mut test, SECONDS_PER_HOUR);
+        let residents = test
+            .g
+            .read::<BuildingInfos>()
+            .get(house)
+            .unwrap()
+            .inside
+            .len();
+        assert!(residents > 0);
+        assert_eq!(test.g.world.humans.len(), residents);
+        assert!(test.g.read::<BuildingInfos>().owner(house).is_some());
+        assert_eq!(
+            test.g
+                .read::<PopulationStats>()
+                .total(PopulationEvent::Immigration),
+            residents as u32
+        );
+
+        let day = test.g.read::<GameTime>().daytime.day;
+        for h in test.g.world.humans.values_mut() {
+            h.age.born = day + 1 - MAX_AGE;
+        }
+        skip_time(&mut test, SECONDS_PER_DAY);
+        test.tick();
+
+        assert!(test.g.world.humans.is_empty());
+        assert!(test.g.read::<BuildingInfos>().owner(house).is_none());
+        assert!(test
+            .g
+            .read::<BuildingInfos>()
+            .get(house)
+            .unwrap()
+            .inside
+            .is_empty());
+        assert_eq!(
+            test.g
+                .read::<PopulationStats>()
+                .total(PopulationEvent::Death),
+            residents as u32
+        );
+    }
+}
@@ -0,0 +1,243 @@
+//! How satisfied the humans are with their life in the city.
+//!
+//! Each human rates how well fed they are, how long their commute is, whether they have a job
+//! and how quiet their home is. The ratings are aggregated per building and district so that
+//! migration and the productivity of the companies can depend on them.
+
+use crate::map::{BuildingID, LaneKind, Map, ProjectFilter, ProjectKind};
+use crate::souls::desire::{BuyFood, Work};
+use crate::utils::resources::Resources;
+use crate::utils::time::{GameTime, Tick, SECONDS_PER_HOUR};
+use crate::world::HumanEnt;
+use crate::World;
+use egui_inspect::Inspect;
+use geom::{Vec2, Vec3};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Satisfaction of a human arriving in the city
+pub const NEUTRAL_SATISFACTION: f32 = 0.5;
+/// Side in meters of the square districts the satisfaction is aggregated over
+pub const DISTRICT_SIZE: f32 = 500.0;
+/// Ticks between two updates of the satisfaction
+const UPDATE_FREQ: u32 = 50;
+/// Share of the gap to the current ratings closed at each update
+const SMOOTHING: f32 = 0.05;
+/// Commutes this long or longer are rated 0
+const MAX_COMMUTE: f64 = SECONDS_PER_HOUR as f64;
+/// Roads further away from a house are not heard
+const NOISE_RADIUS: f32 = 60.0;
+/// Sum of the speed limits of the lanes of a road making the most noise, a 4 lanes highway
+const MAX_NOISE: f32 = 100.0;
+
+const FOOD_WEIGHT: f32 = 0.35;
+const EMPLOYMENT_WEIGHT: f32 = 0.25;
+const COMMUTE_WEIGHT: f32 = 0.2;
+const QUIET_WEIGHT: f32 = 0.2;
+
+/// How well the needs of a human are met, every rating being between 0 and 1
+#[derive(Inspect, Copy, Clone, Debug, Serialize, Deserialize)]
+pub struct Satisfaction {
+    pub food: f32,
+    pub commute: f32,
+    pub employment: f32,
+    pub quiet: f32,
+    /// Weighted average of the ratings, following them slowly
+    pub overall: f32,
+}
+
+impl Default for Satisfaction {
+    fn default() -> Self {
+        Self {
+            food: 1.0,
+            commute: 1.0,
+            employment: 1.0,
+            quiet: 1.0,
+            overall: NEUTRAL_SATISFACTION,
+        }
+    }
+}
+
+impl Satisfaction {
+    pub fn update(
+        &mut self,
+        time: &GameTime,
+        food: &BuyFood,
+        work: Option<&Work>,
+        adult: bool,
+        quiet: f32,
+    ) {
+        self.food = (1.0 - food.hunger(time)).clamp(0.0, 1.0);
+        self.commute = work.and_then(Work::last_commute).map_or(1.0, |secs| {
+            (1.0 - secs / MAX_COMMUTE).clamp(0.0, 1.0) as f32
+        });
+        self.employment = if !adult || work.is_some() { 1.0 } else { 0.0 };
+        self.quiet = quiet;
+
+        let target = FOOD_WEIGHT * self.food
+            + EMPLOYMENT_WEIGHT * self.employment
+            + COMMUTE_WEIGHT * self.commute
+            + QUIET_WEIGHT * self.quiet;
+        self.overall += (target - self.overall) * SMOOTHING;
+    }
+}
+
+/// Districts are the cells of a grid over the map
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct District(pub i32, pub i32);
+
+impl District {
+    pub fn of(pos: Vec2) -> Self {
+        Self(
+            (pos.x / DISTRICT_SIZE).floor() as i32,
+            (pos.y / DISTRICT_SIZE).floor() as i32,
+        )
+    }
+}
+
+/// Average satisfaction of the residents and workers of each building,
+/// of the residents of each district and of the whole city
+#[derive(Default, Serialize, Deserialize)]
+pub struct SatisfactionStats {
+    buildings: BTreeMap<BuildingID, f32>,
+    districts: BTreeMap<District, f32>,
+    city: Option<f32>,
+}
+
+impl SatisfactionStats {
+    pub fn building(&self, id: BuildingID) -> Option<f32> {
+        self.buildings.get(&id).copied()
+    }
+
+    pub fn district(&self, district: District) -> Option<f32> {
+        self.districts.get(&district).copied()
+    }
+
+    pub fn city(&self) -> f32 {
+        self.city.unwrap_or(NEUTRAL_SATISFACTION)
+    }
+
+    /// Factor applied to the productivity of a company, 1 when its workers are neutral
+    pub fn productivity(&self, workplace: BuildingID) -> f32 {
+        0.5 + self.building(workplace).unwrap_or(NEUTRAL_SATISFACTION)
+    }
+}
+
+/// Noise heard at `pos` from the nearby roads, between 0 and 1
+pub fn noise(map: &Map, pos: Vec3) -> f32 {
+    map.spatial_map()
+        .query_around(pos.xy(), NOISE_RADIUS, ProjectFilter::ROAD)
+        .filter_map(|kind| match kind {
+            ProjectKind::Road(id) => map.roads().get(id),
+            _ => None,
+        })
+        .map(|road| {
+            let loudness: f32 = road
+                .lanes_iter()
+                .filter(|&(_, kind)| kind.vehicles() || kind == LaneKind::Rail)
+                .filter_map(|(id, _)| map.lanes().get(id))
+                .map(|lane| lane.speed_limit)
+                .sum();
+            let dist = road.points.project_dist(pos) - road.width * 0.5;
+            loudness / MAX_NOISE * (1.0 - dist.max(0.0) / NOISE_RADIUS).max(0.0)
+        })
+        .fold(0.0, f32::max)
+        .min(1.0)
+}
+
+#[profiling::function]
+pub fn satisfaction_system(world: &mut World, resources: &mut Resources) {
+    let tick = resources.get::<Tick>().unwrap().0;
+    if !tick.is_multiple_of(UPDATE_FREQ) {
+        return;
+    }
+    let time = &*resources.get::<GameTime>().unwrap();
+    let map = &*resources.get::<Map>().unwrap();
+
+    let mut quiet: BTreeMap<BuildingID, f32> = BTreeMap::new();
+    // sum and count of the satisfactions
+    let mut buildings: BTreeMap<BuildingID, (f32, u32)> = BTreeMap::new();
+    let mut districts: BTreeMap<District, (f32, u32)> = BTreeMap::new();
+    let mut city = (0.0, 0);
+
+    world.humans.values_mut().for_each(|h: &mut HumanEnt| {
+        let house = h.desires.home.house();
+        let Some(b) = map.buildings().get(house) else { return };
+        let home_quiet = *quiet
+            .entry(house)
+            .or_insert_with(|| 1.0 - noise(map, b.door_pos));
+
+        if let Some(ref mut work) = h.desires.work {
+            work.update_commute(&h.location, time);
+        }
+        let adult = h.age.is_adult(time.daytime.day);
+        h.satisfaction
+            .update(time, &h.desires.food, h.desires.work.as_ref(), adult, home_quiet);
+
+        let s = h.satisfaction.overall;
+        let add = |acc: &mut (f32, u32)| {
+            acc.0 += s;
+            acc.1 += 1;
+        };
+        add(buildings.entry(house).or_default());
+        if let Some(ref work) = h.desires.work {
+            add(buildings.entry(work.workplace()).or_default());
+        }
+        add(districts.entry(District::of(b.door_pos.xy())).or_default());
+        add(&mut city);
+    });
+
+    let avg = |(sum, n): (f32, u32)| sum / n as f32;
+    let mut stats = resources.get_mut::<SatisfactionStats>().unwrap();
+    stats.buildings = buildings.into_iter().map(|(k, v)| (k, avg(v))).collect();
+    stats.districts = districts.into_iter().map(|(k, v)| (k, avg(v))).collect();
+    stats.city = (city.1 > 0).then(|| avg(city));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{District, Satisfaction, SatisfactionStats, NEUTRAL_SATISFACTION};
+    use crate::economy::ItemRegistry;
+    use crate::souls::desire::BuyFood;
+    use crate::souls::human::spawn_human;
+    use crate::tests::TestCtx;
+    use crate::utils::time::{GameTime, SECONDS_PER_DAY};
+    use geom::{vec2, vec3};
+
+    #[test]
+    fn test_satisfaction_of_residents() {
+        let mut test = TestCtx::new();
+
+        test.build_roads(&[vec3(0.0, -500.0, 0.0), vec3(100.0, -500.0, 0.0)]);
+        let house = test.build_house_near(vec2(50.0, -450.0));
+        let human = spawn_human(&mut test.g, house).unwrap();
+        for _ in 0..100 {
+            test.tick();
+        }
+
+        let s = test.g.world.humans.get(human).unwrap().satisfaction;
+        assert_eq!(s.food, 1.0);
+        assert_eq!(s.employment, 0.0);
+        assert!(s.quiet > 0.5 && s.quiet < 1.0);
+        assert!(s.overall > NEUTRAL_SATISFACTION);
+
+        let stats = test.g.read::<SatisfactionStats>();
+        assert_eq!(stats.building(house), Some(s.overall));
+        let door = test.g.map().buildings()[house].door_pos.xy();
+        assert_eq!(stats.district(District::of(door)), Some(s.overall));
+        assert_eq!(stats.city(), s.overall);
+        drop(stats);
+
+        let food = BuyFood::new(
+            GameTime::new(0.0, 0.0).instant(),
+            &test.g.read::<ItemRegistry>(),
+        );
+        let starving = GameTime::new(0.0, 3.0 * SECONDS_PER_DAY as f64);
+        let mut s = Satisfaction::default();
+        for _ in 0..100 {
+            s.update(&starving, &food, None, true, 1.0);
+        }
+        assert_eq!(s.food, 0.0);
+        assert!(s.overall < NEUTRAL_SATISFACTION);
+    }
+}
@@ -0,0 +1,410 @@
+use crate::map::{BuildingID, LaneKind, Map, PathKind};
+use crate::map_dynamic::{
+    BuildingInfos, DispatchID, DispatchKind, DispatchQueryTarget, Dispatcher, Itinerary,
+};
+use crate::utils::resources::Resources;
+use crate::utils::time::{GameTime, Tick};
+use crate::world::{TrainID, TrainStationEnt, TrainStationID};
+use crate::World;
+use crate::{Egregoria, ParCommandBuffer, SoulID};
+use geom::{Transform, Vec3};
+use serde::{Deserialize, Serialize};
+use slotmapd::HopSlotMap;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Inspect)]
+pub enum PassengerTrainState {
+    /// The train is coming to the station
+    Arriving,
+    /// The train is waiting at the platform for passengers to board
+    Boarding,
+    /// The train is going to the destination station
+    Moving,
+    /// The train is waiting at the destination platform for passengers to alight
+    Alighting,
+}
+
+/// Seconds between two departures from the same station
+pub const DEPARTURE_INTERVAL: f64 = 120.0;
+/// Seconds a train waits at a platform for passengers to board or alight
+pub const BOARDING_TIME: f64 = 20.0;
+pub const PASSENGERS_PER_WAGON: u32 = 40;
+/// Humans won't walk further than this to reach a station
+const MAX_WALK_TO_STATION: f32 = 600.0;
+const MAX_TRAINS_PER_STATION: usize = 2;
+
+#[derive(Debug, Serialize, Deserialize, Inspect)]
+pub struct PassengerTrain {
+    pub id: TrainID,
+    pub state: PassengerTrainState,
+    pub dest: TrainStationID,
+    pub passengers: u32,
+    pub capacity: u32,
+}
+
+/// A component that identifies train station souls, sending passenger trains
+/// to the other stations on a schedule.
+#[derive(Serialize, Deserialize, Inspect)]
+pub struct TrainStation {
+    pub building: BuildingID,
+    pub trains: Vec<PassengerTrain>,
+    pub next_departure: f64,
+    /// Used to go through the other stations in turn
+    pub n_departures: u32,
+}
+
+/// The best way to go from a to b by train, as found by [`best_rail_trip`]
+#[derive(Debug, Copy, Clone)]
+pub struct RailTrip {
+    pub from: TrainStationID,
+    pub from_pos: Vec3,
+    pub to: TrainStationID,
+    pub to_pos: Vec3,
+    /// Estimated duration of the whole trip in seconds, walking included
+    pub est_time: f32,
+}
+
+impl TrainStation {
+    /// Returns the train waiting at the platform to go to `dest`, if it still has room
+    pub fn boarding_train(&self, dest: TrainStationID) -> Option<TrainID> {
+        self.trains
+            .iter()
+            .find(|t| {
+                t.state == PassengerTrainState::Boarding
+                    && t.dest == dest
+                    && t.passengers < t.capacity
+            })
+            .map(|t| t.id)
+    }
+}
+
+/// Returns the passenger train entry of the given train, if it is in service
+pub fn passenger_train(
+    stations: &HopSlotMap<TrainStationID, TrainStationEnt>,
+    train: TrainID,
+) -> Option<&PassengerTrain> {
+    stations
+        .values()
+        .find_map(|st| st.s.trains.iter().find(|t| t.id == train))
+}
+
+pub fn passenger_train_mut(
+    stations: &mut HopSlotMap<TrainStationID, TrainStationEnt>,
+    train: TrainID,
+) -> Option<&mut PassengerTrain> {
+    stations
+        .values_mut()
+        .find_map(|st| st.s.trains.iter_mut().find(|t| t.id == train))
+}
+
+/// Where trains stop at the station, on the nearest rail
+pub fn platform_pos(map: &Map, building: BuildingID) -> Option<Vec3> {
+    let center = map.buildings().get(building)?.obb.center().z(0.0);
+    let lane = map.nearest_lane(center, LaneKind::Rail, Some(100.0))?;
+    Some(map.lanes().get(lane)?.points.project(center))
+}
+
+/// Finds the fastest train trip from start to end, if there are stations nearby
+pub fn best_rail_trip(
+    stations: &HopSlotMap<TrainStationID, TrainStationEnt>,
+    map: &Map,
+    start: Vec3,
+    end: Vec3,
+    walking_speed: f32,
+) -> Option<RailTrip> {
+    const TRAIN_SPEED: f32 = 25.0;
+
+    let doors: Vec<(TrainStationID, Vec3)> = stations
+        .iter()
+        .filter_map(|(id, st)| Some((id, map.buildings().get(st.s.building)?.door_pos)))
+        .collect();
+    if doors.len() < 2 {
+        return None;
+    }
+    // each station sends a train to a given destination every that many seconds
+    let wait = DEPARTURE_INTERVAL as f32 * (doors.len() - 1) as f32 * 0.5;
+
+    let mut best: Option<RailTrip> = None;
+    for &(from, from_pos) in &doors {
+        let walk_to = from_pos.distance(start);
+        if walk_to > MAX_WALK_TO_STATION {
+            continue;
+        }
+        for &(to, to_pos) in &doors {
+            let walk_from = to_pos.distance(end);
+            if to == from || walk_from > MAX_WALK_TO_STATION {
+                continue;
+            }
+
+            let est_time = (walk_to + walk_from) / walking_speed
+                + wait
+                + from_pos.distance(to_pos) / TRAIN_SPEED
+                + 2.0 * BOARDING_TIME as f32;
+
+            if best.map(|b| est_time < b.est_time).unwrap_or(true) {
+                best = Some(RailTrip {
+                    from,
+                    from_pos,
+                    to,
+                    to_pos,
+                    est_time,
+                });
+            }
+        }
+    }
+    best
+}
+
+pub fn train_station_soul(goria: &mut Egregoria, building: BuildingID) -> Option<TrainStationID> {
+    let map = goria.map();
+
+    let s = TrainStation {
+        building,
+        trains: Vec::with_capacity(MAX_TRAINS_PER_STATION),
+        next_departure: 0.0,
+        n_departures: 0,
+    };
+    let b = map.buildings.get(building)?;
+
+    let height = b.height;
+    let obb = b.obb;
+    let pos = obb.center();
+    let axis = obb.axis();
+
+    drop(map);
+
+    let id = goria.world.insert(TrainStationEnt {
+        s,
+        trans: Transform::new_dir(pos.z(height), axis[1].z(0.0).normalize()),
+    });
+
+    goria
+        .write::<BuildingInfos>()
+        .set_owner(building, SoulID::TrainStation(id));
+
+    Some(id)
+}
+
+pub fn train_station_system(world: &mut World, resources: &mut Resources) {
+    let cbuf = resources
+        .get::<ParCommandBuffer<TrainStationEnt>>()
+        .unwrap();
+    let mut dispatch = resources.get_mut::<Dispatcher>().unwrap();
+    let map = resources.get::<Map>().unwrap();
+    let time = resources.get::<GameTime>().unwrap();
+    let tick = *resources.get::<Tick>().unwrap();
+
+    let platforms: Vec<(TrainStationID, Vec3)> = world
+        .train_stations
+        .iter()
+        .filter_map(|(id, st)| Some((id, platform_pos(&map, st.s.building)?)))
+        .collect();
+    let platform_of = |id: TrainStationID| platforms.iter().find(|x| x.0 == id).map(|x| x.1);
+
+    for (me, st) in world.train_stations.iter_mut() {
+        let station = &mut st.s;
+        if !map.buildings.contains_key(station.building) {
+            cbuf.kill(me);
+            continue;
+        }
+        let Some(platform) = platform_of(me) else { continue };
+
+        // update our trains, and remove the ones that are done
+        let mut to_clean = vec![];
+        for ptrain in &mut station.trains {
+            let Some(train) = world.trains.get_mut(ptrain.id) else {
+                to_clean.push(ptrain.id);
+                continue;
+            };
+            let itin = &mut train.it;
+
+            match ptrain.state {
+                PassengerTrainState::Arriving => {
+                    if itin.has_ended(0.0) {
+                        ptrain.state = PassengerTrainState::Boarding;
+                        *itin = Itinerary::wait_until(time.timestamp + BOARDING_TIME);
+                    }
+                }
+                PassengerTrainState::Boarding => {
+                    if itin.has_ended(time.timestamp) {
+                        let Some(dest) = platform_of(ptrain.dest) else {
+                            to_clean.push(ptrain.id);
+                            continue;
+                        };
+
+                        *itin = if let Some(r) =
+                            Itinerary::route(tick, train.trans.position, dest, &map, PathKind::Rail)
+                        {
+                            r
+                        } else {
+                            Itinerary::wait_until(time.timestamp + 10.0);
+                            continue;
+                        };
+                        ptrain.state = PassengerTrainState::Moving;
+                    }
+                }
+                PassengerTrainState::Moving => {
+                    if itin.has_ended(0.0) {
+                        ptrain.state = PassengerTrainState::Alighting;
+                        *itin = Itinerary::wait_until(time.timestamp + BOARDING_TIME);
+                    }
+                }
+                PassengerTrainState::Alighting => {
+                    if itin.has_ended(time.timestamp) {
+                        to_clean.push(ptrain.id);
+                    }
+                }
+            }
+        }
+        for v in to_clean {
+            station.trains.retain(|x| x.id != v);
+            dispatch.free(DispatchID::PassengerTrain(v))
+        }
+
+        // Send a train to the next station when it's time
+        if time.timestamp < station.next_departure || station.trains.len() >= MAX_TRAINS_PER_STATION
+        {
+            continue;
+        }
+        let others: Vec<TrainStationID> = platforms
+            .iter()
+            .map(|(id, _)| *id)
+            .filter(|id| *id != me)
+            .collect();
+        if others.is_empty() {
+            continue;
+        }
+        station.next_departure = time.timestamp + DEPARTURE_INTERVAL;
+
+        let Some(DispatchID::PassengerTrain(trainid)) = dispatch.query(
+            &map,
+            DispatchKind::PassengerTrain,
+            DispatchQueryTarget::Pos(platform),
+        ) else {
+            continue;
+        };
+
+        let train = world.trains.get_mut(trainid).unwrap();
+
+        train.it = unwrap_or!(
+            Itinerary::route(tick, train.trans.position, platform, &map, PathKind::Rail),
+            {
+                dispatch.free(DispatchID::PassengerTrain(trainid));
+                continue;
+            }
+        );
+
+        let dest = others[station.n_departures as usize % others.len()];
+        station.n_departures += 1;
+
+        station.trains.push(PassengerTrain {
+            id: trainid,
+            state: PassengerTrainState::Arriving,
+            dest,
+            passengers: 0,
+            capacity: train.locomotive.n_wagons * PASSENGERS_PER_WAGON,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PassengerTrainState;
+    use crate::map::{BuildingGen, LaneKind, LanePatternBuilder, MapProject};
+    use crate::tests::TestCtx;
+    use crate::transportation::train::RailWagonKind;
+    use crate::transportation::Location;
+    use crate::world::{HumanEnt, TrainID};
+    use crate::{BuildingKind, ParCommandBuffer, WorldCommand};
+    use geom::{vec2, vec3, Vec2, OBB};
+
+    /// Two stations on a one way rail, with a passenger train next to the first one
+    fn two_stations_with_train(test: &mut TestCtx) {
+        test.apply(&[WorldCommand::MapMakeConnection {
+            from: MapProject::ground(vec3(0.0, 0.0, 0.0)),
+            to: MapProject::ground(vec3(600.0, 0.0, 0.0)),
+            inter: None,
+            pat: LanePatternBuilder::new().rail(true).one_way(true).build(),
+        }]);
+
+        for x in [150.0, 450.0] {
+            test.apply(&[WorldCommand::MapBuildSpecialBuilding {
+                pos: OBB::new(vec2(x, 40.0), vec2(1.0, 0.0), 20.0, 20.0),
+                kind: BuildingKind::TrainStation,
+                gen: BuildingGen::NoWalkway {
+                    door_pos: Vec2::ZERO,
+                },
+                zone: None,
+            }]);
+            test.tick();
+        }
+        assert_eq!(test.g.world().train_stations.len(), 2);
+
+        let rail = test
+            .g
+            .map()
+            .nearest_lane(vec3(100.0, 0.0, 0.0), LaneKind::Rail, Some(20.0))
+            .unwrap();
+        test.apply(&[WorldCommand::AddTrain {
+            dist: 80.0,
+            n_wagons: 2,
+            lane: rail,
+            kind: RailWagonKind::Passenger,
+        }]);
+    }
+
+    #[test]
+    fn test_passenger_train_goes_to_other_station() {
+        let mut test = TestCtx::new();
+        two_stations_with_train(&mut test);
+
+        for _ in 0..1000 {
+            test.tick();
+            let arrived = test.g.world().train_stations.values().any(|st| {
+                st.s.trains
+                    .iter()
+                    .any(|t| t.state == PassengerTrainState::Alighting)
+            });
+            if arrived {
+                return;
+            }
+        }
+
+        panic!("passenger train did not reach the other station after 1000 ticks");
+    }
+
+    #[test]
+    fn test_passenger_dying_on_train_leaves_a_seat() {
+        let mut test = TestCtx::new();
+        let (_, human) = test.build_house_with_resident();
+        two_stations_with_train(&mut test);
+
+        let mut train = None;
+        for _ in 0..1000 {
+            test.tick();
+            let stations = &test.g.world().train_stations;
+            train = stations
+                .values()
+                .find_map(|st| st.s.trains.first())
+                .map(|t| t.id);
+            if train.is_some() {
+                break;
+            }
+        }
+        let train: TrainID = train.expect("no train in service after 1000 ticks");
+
+        let passengers = |test: &TestCtx| {
+            super::passenger_train(&test.g.world().train_stations, train)
+                .unwrap()
+                .passengers
+        };
+        let before = passengers(&test);
+        super::passenger_train_mut(&mut test.g.world.train_stations, train)
+            .unwrap()
+            .passengers += 1;
+        test.g.world.humans.get_mut(human).unwrap().location = Location::Train(train);
+
+        test.g.write::<ParCommandBuffer<HumanEnt>>().kill(human);
+        test.tick();
+        assert_eq!(passengers(&test), before);
+    }
+}
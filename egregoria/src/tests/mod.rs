@@ -2,14 +2,17 @@
 #![cfg(test)]
 
 use crate::engine_interaction::{WorldCommand, WorldCommands};
-use crate::map::{BuildingID, LanePatternBuilder, ProjectFilter};
+use crate::map::{BuildingGen, BuildingID, BuildingKind, LanePatternBuilder, ProjectFilter};
 use crate::map_dynamic::BuildingInfos;
+use crate::souls::goods_company::{GoodsCompanyID, GoodsCompanyRegistry};
+use crate::souls::human::spawn_human;
 use crate::utils::scheduler::SeqSchedule;
 use crate::utils::time::Tick;
+use crate::world::HumanID;
 use crate::{Egregoria, EgregoriaOptions};
 use common::logger::MyLog;
 use common::saveload::Encoder;
-use geom::{Vec2, Vec3};
+use geom::{vec2, vec3, Vec2, Vec3, OBB};
 
 mod test_iso;
 mod vehicles;
@@ -58,6 +61,40 @@ impl TestCtx {
         b
     }
 
+    /// Builds a short street with a house next to it
+    pub(crate) fn build_street_with_house(&self) -> BuildingID {
+        self.build_roads(&[vec3(0.0, -500.0, 0.0), vec3(100.0, -500.0, 0.0)]);
+        self.build_house_near(vec2(50.0, -450.0))
+    }
+
+    /// Same as [`Self::build_street_with_house`] with an adult living in the house
+    pub(crate) fn build_house_with_resident(&mut self) -> (BuildingID, HumanID) {
+        let house = self.build_street_with_house();
+        let human = spawn_human(&mut self.g, house).unwrap();
+        (house, human)
+    }
+
+    /// The kind of company with that name in the registry
+    pub(crate) fn company_kind(&self, name: &str) -> GoodsCompanyID {
+        self.g
+            .read::<GoodsCompanyRegistry>()
+            .descriptions
+            .iter()
+            .find(|(_, d)| d.name == name)
+            .unwrap()
+            .0
+    }
+
+    /// A small building of that kind with its door at pos, away from any road
+    pub(crate) fn build_special(kind: BuildingKind, pos: Vec2) -> WorldCommand {
+        WorldCommand::MapBuildSpecialBuilding {
+            pos: OBB::new(pos, vec2(1.0, 0.0), 5.0, 5.0),
+            kind,
+            gen: BuildingGen::NoWalkway { door_pos: pos },
+            zone: None,
+        }
+    }
+
     pub(crate) fn apply(&mut self, commands: &[WorldCommand]) {
         for c in commands {
             c.apply(&mut self.g);
@@ -0,0 +1,444 @@
+use crate::map::{LaneKind, LanePatternBuilder, Map, PathKind};
+use crate::map_dynamic::{drop_off_passengers, Itinerary};
+use crate::transportation::{make_vehicle_entity, Vehicle, VehicleKind, VehicleState};
+use crate::utils::resources::Resources;
+use crate::utils::time::GameTime;
+use crate::world::{HumanEnt, VehicleEnt, VehicleID};
+use crate::{Egregoria, ParCommandBuffer, World};
+use geom::{Color, Transform, Vec3};
+use serde::{Deserialize, Serialize};
+use slotmapd::{new_key_type, SlotMap};
+
+new_key_type! {
+    pub struct BusStopID;
+    pub struct BusLineID;
+}
+
+debug_inspect_impl!(BusStopID);
+debug_inspect_impl!(BusLineID);
+
+/// Time a bus waits at each stop for passengers to board and alight
+pub const BUS_DWELL_TIME: f64 = 15.0;
+/// A departure is skipped if the line already has that many buses running
+const MAX_BUSES_PER_LINE: usize = 8;
+/// Humans a bus can carry
+pub const BUS_CAPACITY: u32 = 50;
+/// Humans won't walk further than this to reach a stop
+const MAX_WALK_TO_STOP: f32 = 400.0;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BusStop {
+    pub id: BusStopID,
+    /// Where the bus stops, on the driving lane
+    pub pos: Vec3,
+    /// Where humans wait for the bus, on the sidewalk
+    pub walk_pos: Vec3,
+}
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+pub enum BusRunState {
+    /// The bus is driving to `stop_idx`
+    Driving,
+    /// The bus is waiting at `stop_idx` for passengers
+    Dwelling,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BusRun {
+    pub bus: VehicleID,
+    pub stop_idx: usize,
+    pub state: BusRunState,
+    pub passengers: u32,
+    pub capacity: u32,
+}
+
+/// A bus line goes through its stops in order.
+/// A bus departs from the first stop every `interval` seconds and is removed once it reaches the last stop.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BusLine {
+    pub id: BusLineID,
+    pub stops: Vec<BusStopID>,
+    pub interval: f64,
+    pub next_departure: f64,
+    pub runs: Vec<BusRun>,
+}
+
+impl BusLine {
+    /// Estimated seconds to wait at the stop at `stop_idx` for a bus with room left,
+    /// None if the buses coming are full and no other can depart
+    fn wait_at(&self, stop_idx: usize) -> Option<f32> {
+        let coming: Vec<&BusRun> = self
+            .runs
+            .iter()
+            .filter(|r| r.stop_idx <= stop_idx)
+            .collect();
+        if coming.is_empty() || coming.iter().any(|r| r.passengers < r.capacity) {
+            return Some(self.interval as f32 * 0.5);
+        }
+        if self.runs.len() >= MAX_BUSES_PER_LINE {
+            return None;
+        }
+        // the next departure
+        Some(self.interval as f32)
+    }
+}
+
+/// The best way to go from a to b using a bus line, as found by [`BusLines::best_trip`]
+#[derive(Debug, Copy, Clone)]
+pub struct BusTrip {
+    pub line: BusLineID,
+    pub board: BusStopID,
+    pub board_pos: Vec3,
+    pub alight: BusStopID,
+    pub alight_pos: Vec3,
+    /// Estimated duration of the whole trip in seconds, walking included
+    pub est_time: f32,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct BusLines {
+    stops: SlotMap<BusStopID, BusStop>,
+    lines: SlotMap<BusLineID, BusLine>,
+}
+
+impl BusLines {
+    pub fn stops(&self) -> &SlotMap<BusStopID, BusStop> {
+        &self.stops
+    }
+
+    pub fn lines(&self) -> &SlotMap<BusLineID, BusLine> {
+        &self.lines
+    }
+
+    /// Adds a stop on the driving lane nearest to pos
+    pub fn add_stop(&mut self, map: &Map, pos: Vec3) -> Option<BusStopID> {
+        let lane = map.nearest_lane(pos, LaneKind::Driving, Some(20.0))?;
+        let pos = map.lanes().get(lane)?.points.project(pos);
+        let walk_pos = map
+            .nearest_lane(pos, LaneKind::Walking, Some(20.0))
+            .and_then(|l| map.lanes().get(l))
+            .map(|l| l.points.project(pos))
+            .unwrap_or(pos);
+
+        Some(
+            self.stops
+                .insert_with_key(|id| BusStop { id, pos, walk_pos }),
+        )
+    }
+
+    /// Removes the stop and the lines going through it.
+    /// Returns the buses that were running on those lines.
+    pub fn remove_stop(&mut self, id: BusStopID) -> Vec<VehicleID> {
+        if self.stops.remove(id).is_none() {
+            return vec![];
+        }
+        let to_remove: Vec<_> = self
+            .lines
+            .values()
+            .filter(|l| l.stops.contains(&id))
+            .map(|l| l.id)
+            .collect();
+        to_remove
+            .into_iter()
+            .flat_map(|l| self.remove_line(l))
+            .collect()
+    }
+
+    pub fn add_line(&mut self, stops: Vec<BusStopID>, interval: f64) -> Option<BusLineID> {
+        if stops.len() < 2 || !stops.iter().all(|s| self.stops.contains_key(*s)) {
+            return None;
+        }
+        Some(self.lines.insert_with_key(|id| BusLine {
+            id,
+            stops,
+            interval: interval.max(1.0),
+            next_departure: 0.0,
+            runs: vec![],
+        }))
+    }
+
+    /// Removes the line, returns the buses that were running on it
+    pub fn remove_line(&mut self, id: BusLineID) -> Vec<VehicleID> {
+        self.lines
+            .remove(id)
+            .map(|l| l.runs.into_iter().map(|r| r.bus).collect())
+            .unwrap_or_default()
+    }
+
+    /// Returns the bus of this line currently waiting at the given stop, if it still has room
+    pub fn bus_at(&self, line: BusLineID, stop: BusStopID) -> Option<VehicleID> {
+        let line = self.lines.get(line)?;
+        line.runs
+            .iter()
+            .find(|r| {
+                matches!(r.state, BusRunState::Dwelling)
+                    && line.stops.get(r.stop_idx) == Some(&stop)
+                    && r.passengers < r.capacity
+            })
+            .map(|r| r.bus)
+    }
+
+    pub fn run_mut(&mut self, bus: VehicleID) -> Option<&mut BusRun> {
+        self.lines
+            .values_mut()
+            .find_map(|l| l.runs.iter_mut().find(|r| r.bus == bus))
+    }
+
+    /// Returns the line of the bus and the stop it is going to or waiting at
+    pub fn next_stop_of(&self, bus: VehicleID) -> Option<(BusLineID, BusStopID, BusRunState)> {
+        self.lines.values().find_map(|l| {
+            let run = l.runs.iter().find(|r| r.bus == bus)?;
+            Some((l.id, *l.stops.get(run.stop_idx)?, run.state))
+        })
+    }
+
+    /// Finds the fastest bus trip from start to end, if any line goes there with room left
+    pub fn best_trip(&self, start: Vec3, end: Vec3, walking_speed: f32) -> Option<BusTrip> {
+        const BUS_SPEED: f32 = LanePatternBuilder::new().speed_limit * 0.8;
+
+        let mut best: Option<BusTrip> = None;
+        for line in self.lines.values() {
+            let stops: Vec<&BusStop> = line
+                .stops
+                .iter()
+                .filter_map(|id| self.stops.get(*id))
+                .collect();
+
+            for (i, board) in stops.iter().enumerate() {
+                let walk_to = board.walk_pos.distance(start);
+                if walk_to > MAX_WALK_TO_STOP {
+                    continue;
+                }
+                let Some(wait) = line.wait_at(i) else {
+                    continue;
+                };
+
+                let mut ride = 0.0;
+                for w in stops[i..].windows(2) {
+                    let alight = w[1];
+                    ride += w[0].pos.distance(alight.pos);
+
+                    let walk_from = alight.walk_pos.distance(end);
+                    if walk_from > MAX_WALK_TO_STOP {
+                        continue;
+                    }
+
+                    let est_time = (walk_to + walk_from) / walking_speed
+                        + wait
+                        + ride / BUS_SPEED
+                        + BUS_DWELL_TIME as f32;
+
+                    if best.map(|b| est_time < b.est_time).unwrap_or(true) {
+                        best = Some(BusTrip {
+                            line: line.id,
+                            board: board.id,
+                            board_pos: board.walk_pos,
+                            alight: alight.id,
+                            alight_pos: alight.walk_pos,
+                            est_time,
+                        });
+                    }
+                }
+            }
+        }
+        best
+    }
+}
+
+/// Drops the passengers off the buses of a removed line before removing them
+pub(crate) fn remove_buses(goria: &mut Egregoria, buses: &[VehicleID]) {
+    let (world, res) = goria.world_res();
+    let cbuf = res.get::<ParCommandBuffer<HumanEnt>>().unwrap();
+    for &bus in buses {
+        drop_off_passengers(world, &cbuf, bus);
+    }
+    res.get::<ParCommandBuffer<VehicleEnt>>()
+        .unwrap()
+        .kill_all(buses);
+}
+
+/// Spawns the buses of every line according to their timetable
+pub fn bus_spawn_system(goria: &mut Egregoria) {
+    let now = goria.read::<GameTime>().timestamp;
+
+    let mut departures = vec![];
+    {
+        let mut buslines = goria.write::<BusLines>();
+        let BusLines {
+            ref stops,
+            ref mut lines,
+        } = *buslines;
+        for line in lines.values_mut() {
+            if now < line.next_departure {
+                continue;
+            }
+            line.next_departure = now + line.interval;
+            if line.runs.len() >= MAX_BUSES_PER_LINE {
+                continue;
+            }
+            let first = unwrap_cont!(line.stops.first().and_then(|s| stops.get(*s)));
+            departures.push((line.id, first.pos));
+        }
+    }
+
+    for (line, pos) in departures {
+        let map = goria.map();
+        let lane = unwrap_cont!(map
+            .nearest_lane(pos, LaneKind::Driving, Some(20.0))
+            .and_then(|l| map.lanes().get(l)));
+        let (proj, _, dir) = lane.points.project_segment_dir(pos);
+        drop(map);
+
+        let bus = make_vehicle_entity(
+            goria,
+            Transform::new_dir(proj, dir),
+            Vehicle {
+                ang_velocity: 0.0,
+                wait_time: 0.0,
+                state: VehicleState::Driving,
+                kind: VehicleKind::Bus,
+                tint: Color::from_hex(0xd4_a0_17),
+                flag: 0,
+            },
+            Itinerary::wait_until(now + BUS_DWELL_TIME),
+            true,
+        );
+
+        let mut buslines = goria.write::<BusLines>();
+        let line = unwrap_cont!(buslines.lines.get_mut(line));
+        line.runs.push(BusRun {
+            bus,
+            stop_idx: 0,
+            state: BusRunState::Dwelling,
+            passengers: 0,
+            capacity: BUS_CAPACITY,
+        });
+    }
+}
+
+/// Moves the buses from stop to stop and removes them at the end of the line,
+/// dropping off the passengers still aboard
+pub fn bus_system(world: &mut World, resources: &mut Resources) {
+    let mut buslines = resources.get_mut::<BusLines>().unwrap();
+    let cbuf = resources.get::<ParCommandBuffer<VehicleEnt>>().unwrap();
+    let cbuf_human = resources.get::<ParCommandBuffer<HumanEnt>>().unwrap();
+    let time = resources.get::<GameTime>().unwrap();
+    let mut ended = vec![];
+
+    let BusLines {
+        ref stops,
+        ref mut lines,
+    } = *buslines;
+
+    for line in lines.values_mut() {
+        let line_stops = &line.stops;
+        line.runs.retain_mut(|run| {
+            let Some(bus) = world.vehicles.get_mut(run.bus) else {
+                return false;
+            };
+
+            match run.state {
+                BusRunState::Driving => {
+                    if bus.it.has_ended(0.0) {
+                        run.state = BusRunState::Dwelling;
+                        bus.it = Itinerary::wait_until(time.timestamp + BUS_DWELL_TIME);
+                    }
+                }
+                BusRunState::Dwelling => {
+                    if bus.it.has_ended(time.timestamp) {
+                        run.stop_idx += 1;
+                        let stop = line_stops.get(run.stop_idx).and_then(|s| stops.get(*s));
+                        let Some(stop) = stop else {
+                            ended.push(run.bus);
+                            return false;
+                        };
+                        run.state = BusRunState::Driving;
+                        bus.it = Itinerary::wait_for_reroute(PathKind::Vehicle, stop.pos);
+                    }
+                }
+            }
+            true
+        });
+    }
+
+    for bus in ended {
+        drop_off_passengers(world, &cbuf_human, bus);
+        cbuf.kill(bus);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BusLineID, BusLines};
+    use crate::tests::TestCtx;
+    use crate::WorldCommand;
+    use geom::vec3;
+
+    /// Builds a road with a line between two stops and lets its first bus depart
+    fn line_with_two_stops(test: &mut TestCtx) -> BusLineID {
+        test.build_roads(&[vec3(0., 0., 0.), vec3(100., 0., 0.), vec3(200., 0., 0.)]);
+
+        test.apply(&[
+            WorldCommand::AddBusStop(vec3(20.0, 0.0, 0.0)),
+            WorldCommand::AddBusStop(vec3(180.0, 0.0, 0.0)),
+        ]);
+        let stops: Vec<_> = test.g.read::<BusLines>().stops().keys().collect();
+        assert_eq!(stops.len(), 2);
+
+        test.apply(&[WorldCommand::AddBusLine {
+            stops,
+            interval: 1000.0,
+        }]);
+        test.tick();
+
+        test.g
+            .read::<BusLines>()
+            .lines()
+            .values()
+            .next()
+            .unwrap()
+            .id
+    }
+
+    #[test]
+    fn test_bus_runs_line_to_the_end() {
+        let mut test = TestCtx::new();
+        let line = line_with_two_stops(&mut test);
+        let bus = test.g.read::<BusLines>().lines()[line].runs[0].bus;
+
+        let mut seen_at_end = false;
+        for _ in 0..3000 {
+            test.tick();
+            let Some(v) = test.g.world().vehicles.get(bus) else {
+                assert!(seen_at_end);
+                return;
+            };
+            seen_at_end |= v.trans.position.distance(vec3(180.0, 0.0, 0.0)) < 10.0;
+        }
+
+        panic!("bus has not finished its line after 3000 ticks");
+    }
+
+    #[test]
+    fn test_full_bus_takes_no_passenger() {
+        let mut test = TestCtx::new();
+        let line = line_with_two_stops(&mut test);
+
+        let buslines = &mut *test.g.write::<BusLines>();
+        let stops = buslines.lines()[line].stops.clone();
+        let (from, to) = (
+            buslines.stops()[stops[0]].walk_pos,
+            buslines.stops()[stops[1]].walk_pos,
+        );
+        let bus = buslines.lines()[line].runs[0].bus;
+        let trip = buslines.best_trip(from, to, 1.0).unwrap();
+        assert_eq!(buslines.bus_at(line, stops[0]), Some(bus));
+
+        let run = buslines.run_mut(bus).unwrap();
+        run.passengers = run.capacity;
+
+        assert_eq!(buslines.bus_at(line, stops[0]), None);
+        let full_trip = buslines.best_trip(from, to, 1.0).unwrap();
+        assert!(full_trip.est_time > trip.est_time);
+    }
+}
@@ -1,12 +1,13 @@
 use crate::map::BuildingID;
 use serde::{Deserialize, Serialize};
 
+pub mod bus;
 pub mod pedestrian;
 pub mod road;
 pub mod train;
 mod vehicle;
 
-use crate::world::VehicleID;
+use crate::world::{TrainID, VehicleID};
 pub use pedestrian::*;
 pub use vehicle::*;
 
@@ -15,5 +16,6 @@ pub enum Location {
     Outside,
     Vehicle(VehicleID),
     Building(BuildingID),
+    Train(TrainID),
 }
 debug_inspect_impl!(Location);
@@ -1,4 +1,4 @@
-use crate::map::{Map, TrafficBehavior, Traversable, TraverseKind};
+use crate::map::{ring_ccw, Lane, Map, RoadClass, TrafficBehavior, Traversable, TraverseKind};
 use crate::map_dynamic::{Itinerary, OBJECTIVE_OK_DIST};
 use crate::physics::Speed;
 use crate::physics::{Collider, CollisionWorld, PhysicsGroup, PhysicsObject};
@@ -8,8 +8,9 @@ use crate::utils::time::GameTime;
 use crate::world::{VehicleEnt, VehicleID};
 use crate::ParCommandBuffer;
 use crate::World;
-use geom::{angle_lerpxy, Ray, Transform, Vec2, Vec3};
+use geom::{angle_lerpxy, vec2, Ray, Transform, Vec2, Vec3};
 use slotmapd::Key;
+use std::f32::consts::{FRAC_PI_2, TAU};
 
 #[profiling::function]
 pub fn vehicle_decision_system(world: &mut World, resources: &mut Resources) {
@@ -59,7 +60,7 @@ pub fn vehicle_decision(
         let objs =
             neighbors.map(|(id, pos)| (pos, cow.get(id).expect("Handle not in collision world").1));
 
-        let (s, d) = calc_decision(me, vehicle, map, time, trans, self_obj, it, objs);
+        let (s, d) = calc_decision(me, vehicle, map, time, cow, trans, self_obj, it, objs);
         desired_speed = s;
         desired_dir = d;
     }
@@ -193,6 +194,7 @@ pub fn calc_decision<'a>(
     vehicle: &mut Vehicle,
     map: &Map,
     time: &GameTime,
+    cow: &CollisionWorld,
     trans: &Transform,
     self_obj: &PhysicsObject,
     it: &Itinerary,
@@ -251,7 +253,17 @@ pub fn calc_decision<'a>(
         }
     }
 
-    let mut speed = 9.0;
+    let mut speed = RoadClass::Residential.speed_limit();
+
+    if let Some(Traversable {
+        kind: TraverseKind::Turn(t),
+        ..
+    }) = it.get_travers()
+    {
+        if let (Some(src), Some(dst)) = (map.lanes().get(t.src), map.lanes().get(t.dst)) {
+            speed = src.max_speed().min(dst.max_speed());
+        }
+    }
 
     if let Some(Traversable {
         kind: TraverseKind::Lane(l_id),
@@ -259,12 +271,25 @@ pub fn calc_decision<'a>(
     }) = it.get_travers()
     {
         if let Some(l) = map.lanes().get(*l_id) {
-            speed = l.speed_limit;
+            speed = l.max_speed();
 
             let light = l.control_point();
 
-            match l.control.get_behavior(time.seconds) {
-                TrafficBehavior::RED | TrafficBehavior::ORANGE => {
+            let mut behavior = l.control.get_behavior(time.seconds);
+            if let TrafficBehavior::LEFT = behavior {
+                let turning_left = matches!(
+                    it.peek_travers(),
+                    Some(Traversable { kind: TraverseKind::Turn(t), .. }) if t.is_left(map.lanes())
+                );
+                behavior = if turning_left {
+                    TrafficBehavior::GREEN
+                } else {
+                    TrafficBehavior::RED
+                };
+            }
+
+            match behavior {
+                TrafficBehavior::RED | TrafficBehavior::ORANGE | TrafficBehavior::LEFT => {
                     if light.is_close(
                         position,
                         OBJECTIVE_OK_DIST * 1.05
@@ -286,16 +311,59 @@ pub fn calc_decision<'a>(
                         return (0.0, dir_to_pos);
                     }
                 }
+                TrafficBehavior::YIELD => {
+                    if light.is_close(position, OBJECTIVE_OK_DIST * 1.05 + 2.0 + stop_dist)
+                        && !ring_is_clear(map, cow, l)
+                    {
+                        return (0.0, dir_to_pos);
+                    }
+                }
             }
         }
     }
 
     // Not facing the objective
     if dir_to_pos.dot(trans.dir) < 0.8 {
-        return (6.0, dir_to_pos);
+        return (6.0f32.min(vehicle.kind.max_speed()), dir_to_pos);
     }
 
-    (vehicle.kind.speed_factor() * speed, dir_to_pos)
+    (
+        vehicle.kind.speed_factor() * speed.min(vehicle.kind.max_speed()),
+        dir_to_pos,
+    )
+}
+
+/// Whether a vehicle can enter the roundabout at the end of the lane: no vehicle going around
+/// the ring is within a quarter turn before the entry of the lane.
+/// Vehicles entering or leaving the ring, and those already past the entry, don't block it.
+fn ring_is_clear(map: &Map, cow: &CollisionWorld, lane: &Lane) -> bool {
+    let Some(inter) = map.intersections().get(lane.dst) else { return true };
+    let center = inter.pos.xy();
+    let radius = inter.roundabout_radius(map.roads());
+
+    let entry = lane.control_point().xy() - center;
+    let sign = if ring_ccw(-lane.orientation_from(inter.id), entry) {
+        1.0
+    } else {
+        -1.0
+    };
+    let entry_ang = f32::atan2(entry.y, entry.x);
+
+    cow.query_around(center, radius + 3.0).all(|(h, pos)| {
+        let Some((_, obj)) = cow.get(h) else { return true };
+        let rel = pos - center;
+        let Some(d) = rel.try_normalize() else { return true };
+        if obj.group != PhysicsGroup::Vehicles || (rel.mag() - radius).abs() > 3.0 {
+            return true;
+        }
+        // going around the ring rather than entering or leaving it
+        let tangent = vec2(-d.y, d.x) * sign;
+        if obj.dir.dot(tangent) < 0.5 {
+            return true;
+        }
+        let to_entry = (sign * (entry_ang - f32::atan2(rel.y, rel.x))).rem_euclid(TAU);
+        to_entry > FRAC_PI_2
+    })
 }
 
 /// Calculates the distance to the closest problematic object in front of the car.
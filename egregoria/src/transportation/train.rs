@@ -27,6 +27,8 @@ pub struct Locomotive {
     pub dec_force: f32,
     /// m
     pub length: f32,
+    pub n_wagons: u32,
+    pub wagons_kind: RailWagonKind,
 }
 
 #[derive(Serialize, Deserialize, Inspect)]
@@ -37,10 +39,11 @@ pub struct LocomotiveReservation {
     upcoming_inters: Vec<IntersectionID>,
 }
 
-#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum RailWagonKind {
     Locomotive,
     Passenger,
+    #[default]
     Freight,
 }
 
@@ -116,6 +119,8 @@ pub fn spawn_train(
             acc_force: 1.0,
             dec_force: 2.5,
             length: trainlength,
+            n_wagons,
+            wagons_kind: kind,
         },
         res: LocomotiveReservation {
             cur_travers_dist: dist,
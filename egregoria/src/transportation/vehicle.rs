@@ -1,3 +1,4 @@
+use crate::map::{PathKind, Pathfinder};
 use crate::map_dynamic::{Itinerary, ParkingManagement, SpotReservation};
 use crate::physics::{Collider, CollisionWorld, PhysicsGroup, PhysicsObject};
 use crate::utils::rand_provider::RandProvider;
@@ -11,6 +12,10 @@ use serde::{Deserialize, Serialize};
 
 /// The duration for the parking animation.
 pub const TIME_TO_PARK: f32 = 4.0;
+/// Cruising speed of a bike in m/s
+pub const BIKE_SPEED: f32 = 5.5;
+/// Riding in car traffic is slower and less pleasant than riding on a bike lane
+pub const BIKE_TRAFFIC_PENALTY: f32 = 1.5;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub enum VehicleState {
@@ -28,6 +33,7 @@ pub enum VehicleKind {
     Car,
     Truck,
     Bus,
+    Bike,
 }
 
 #[derive(Debug, Serialize, Deserialize, Inspect)]
@@ -62,6 +68,7 @@ impl VehicleKind {
             VehicleKind::Car => 4.5,
             VehicleKind::Truck => 6.0,
             VehicleKind::Bus => 9.0,
+            VehicleKind::Bike => 1.5,
         }
     }
 
@@ -70,12 +77,14 @@ impl VehicleKind {
             VehicleKind::Car => 3.0,
             VehicleKind::Truck => 2.5,
             VehicleKind::Bus => 2.0,
+            VehicleKind::Bike => 1.0,
         }
     }
 
     pub fn deceleration(self) -> f32 {
         match self {
             VehicleKind::Car | VehicleKind::Bus | VehicleKind::Truck => 6.0,
+            VehicleKind::Bike => 3.0,
         }
     }
 
@@ -84,21 +93,31 @@ impl VehicleKind {
             VehicleKind::Car => 1.5,
             VehicleKind::Truck => 3.0,
             VehicleKind::Bus => 4.0,
+            VehicleKind::Bike => 0.5,
         }
     }
 
     pub fn speed_factor(self) -> f32 {
         match self {
-            VehicleKind::Car => 1.0,
+            VehicleKind::Car | VehicleKind::Bike => 1.0,
             VehicleKind::Truck | VehicleKind::Bus => 0.8,
         }
     }
 
+    /// m/s, whatever the speed limit is
+    pub fn max_speed(self) -> f32 {
+        match self {
+            VehicleKind::Bike => BIKE_SPEED,
+            VehicleKind::Car | VehicleKind::Truck | VehicleKind::Bus => f32::INFINITY,
+        }
+    }
+
     pub fn ang_acc(self) -> f32 {
         match self {
             VehicleKind::Car => 1.0,
             VehicleKind::Truck => 0.9,
             VehicleKind::Bus => 0.8,
+            VehicleKind::Bike => 2.0,
         }
     }
 }
@@ -154,6 +173,32 @@ pub fn spawn_parked_vehicle(
     ))
 }
 
+/// Spawns a bike on the lane nearest to pos, riding to dest.
+/// Bikes are kept inside buildings so they only exist while they're ridden.
+pub fn spawn_bike(goria: &mut Egregoria, pos: Vec3, dest: Vec3) -> Option<VehicleID> {
+    let map = goria.map();
+    let lane = PathKind::Bike
+        .nearest_lane(&map, pos)
+        .and_then(|l| map.lanes().get(l))?;
+    let (proj, _, dir) = lane.points.project_segment_dir(pos);
+    drop(map);
+
+    Some(make_vehicle_entity(
+        goria,
+        Transform::new_dir(proj, dir),
+        Vehicle {
+            ang_velocity: 0.0,
+            wait_time: 0.0,
+            state: VehicleState::Driving,
+            kind: VehicleKind::Bike,
+            tint: Color::WHITE,
+            flag: 0,
+        },
+        Itinerary::wait_for_reroute(PathKind::Bike, dest),
+        true,
+    ))
+}
+
 pub fn make_vehicle_entity(
     goria: &mut Egregoria,
     trans: Transform,
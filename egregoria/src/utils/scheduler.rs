@@ -1,4 +1,4 @@
-use crate::world::{CompanyEnt, HumanEnt, TrainEnt, VehicleEnt, WagonEnt};
+use crate::world::{CompanyEnt, HumanEnt, TrainEnt, TrainStationEnt, VehicleEnt, WagonEnt};
 use crate::{Egregoria, FreightStationEnt, ParCommandBuffer};
 use common::History;
 use ordered_float::OrderedFloat;
@@ -49,6 +49,7 @@ impl SeqSchedule {
             ParCommandBuffer::<WagonEnt>::apply(goria);
             ParCommandBuffer::<FreightStationEnt>::apply(goria);
             ParCommandBuffer::<CompanyEnt>::apply(goria);
+            ParCommandBuffer::<TrainStationEnt>::apply(goria);
 
             let elapsed = start.elapsed();
 
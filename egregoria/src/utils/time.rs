@@ -6,6 +6,7 @@ pub const SECONDS_PER_HOUR: i32 = 3600;
 pub const HOURS_PER_DAY: i32 = 24;
 pub const SECONDS_PER_DAY: i32 = SECONDS_PER_HOUR * HOURS_PER_DAY;
 pub const TICKS_PER_SECOND: u32 = 50;
+pub const DAYS_PER_WEEK: i32 = 7;
 
 /// The amount of time the game was updated
 /// Used as a resource
@@ -49,6 +50,92 @@ pub struct DayTime {
     pub second: i32,
 }
 
+/// Day of the week, the game starts on a monday
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Weekday {
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+
+impl Weekday {
+    pub const ALL: [Weekday; DAYS_PER_WEEK as usize] = [
+        Weekday::Monday,
+        Weekday::Tuesday,
+        Weekday::Wednesday,
+        Weekday::Thursday,
+        Weekday::Friday,
+        Weekday::Saturday,
+        Weekday::Sunday,
+    ];
+
+    /// The day of the week of the given day since the start of the game
+    pub fn of(day: i32) -> Weekday {
+        Self::ALL[day.rem_euclid(DAYS_PER_WEEK) as usize]
+    }
+
+    pub fn short_name(self) -> &'static str {
+        match self {
+            Weekday::Monday => "Mon",
+            Weekday::Tuesday => "Tue",
+            Weekday::Wednesday => "Wed",
+            Weekday::Thursday => "Thu",
+            Weekday::Friday => "Fri",
+            Weekday::Saturday => "Sat",
+            Weekday::Sunday => "Sun",
+        }
+    }
+
+    pub fn is_weekend(self) -> bool {
+        matches!(self, Weekday::Saturday | Weekday::Sunday)
+    }
+}
+
+/// A set of days of the week
+#[derive(Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Weekdays {
+    mask: u8,
+}
+
+debug_inspect_impl!(Weekdays);
+
+impl std::fmt::Debug for Weekdays {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+impl Weekdays {
+    pub const ALL: Weekdays = Weekdays { mask: 0b111_1111 };
+    /// Monday to friday
+    pub const WORKDAYS: Weekdays = Weekdays { mask: 0b001_1111 };
+
+    pub fn contains(self, day: Weekday) -> bool {
+        self.mask & (1 << day as u8) != 0
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.mask == 0
+    }
+
+    pub fn iter(self) -> impl Iterator<Item = Weekday> {
+        Weekday::ALL.into_iter().filter(move |&d| self.contains(d))
+    }
+}
+
+impl FromIterator<Weekday> for Weekdays {
+    fn from_iter<T: IntoIterator<Item = Weekday>>(iter: T) -> Self {
+        Weekdays {
+            mask: iter.into_iter().fold(0, |mask, d| mask | (1 << d as u8)),
+        }
+    }
+}
+
 /// An interval of in-game time
 #[derive(Copy, Clone, Serialize, Deserialize)]
 pub struct TimeInterval {
@@ -125,6 +212,16 @@ impl RecTimeInterval {
             0
         }
     }
+
+    /// Day the last occurrence of the interval started on, the day before when it goes
+    /// through midnight and `t` is in the morning part
+    pub fn start_day(&self, t: DayTime) -> i32 {
+        if t.daysec() < self.start_hour * SECONDS_PER_HOUR + self.start_second {
+            t.day - 1
+        } else {
+            t.day
+        }
+    }
 }
 
 impl DayTime {
@@ -149,6 +246,10 @@ impl DayTime {
     pub fn gamesec(&self) -> i32 {
         self.day * SECONDS_PER_DAY + self.daysec()
     }
+
+    pub fn weekday(&self) -> Weekday {
+        Weekday::of(self.day)
+    }
 }
 
 impl GameTime {
@@ -197,10 +298,31 @@ impl GameInstant {
 
 #[cfg(test)]
 mod test {
+    use super::{DayTime, RecTimeInterval, Weekday, Weekdays, SECONDS_PER_HOUR};
     use common::timestep::UP_DT;
 
     #[test]
     fn assert_up_dt_ticks_per_second_match() {
         assert!((1.0 / UP_DT.as_secs_f64() - super::TICKS_PER_SECOND as f64).abs() < 0.0001);
     }
+
+    #[test]
+    fn test_night_shift_belongs_to_the_day_it_starts() {
+        assert_eq!(Weekday::of(0), Weekday::Monday);
+        assert_eq!(Weekday::of(12), Weekday::Saturday);
+        assert!(!Weekdays::WORKDAYS.contains(Weekday::Saturday));
+
+        let night = RecTimeInterval::new((22, 0), (6, 0));
+        // friday at 23:00 and saturday at 2:00 are both in the friday shift
+        let friday = DayTime::new(4 * 24 * SECONDS_PER_HOUR + 23 * SECONDS_PER_HOUR);
+        let saturday = DayTime::new(5 * 24 * SECONDS_PER_HOUR + 2 * SECONDS_PER_HOUR);
+        assert_eq!(night.dist_until(friday), 0);
+        assert_eq!(night.dist_until(saturday), 0);
+        assert_eq!(Weekday::of(night.start_day(friday)), Weekday::Friday);
+        assert_eq!(Weekday::of(night.start_day(saturday)), Weekday::Friday);
+
+        let day = RecTimeInterval::new((8, 0), (18, 0));
+        assert_eq!(Weekday::of(day.start_day(saturday)), Weekday::Friday);
+        assert!(day.dist_until(saturday) > 0);
+    }
 }
@@ -1,18 +1,23 @@
-use crate::economy::{Bought, Market, Sold, Workers};
+use crate::economy::{Bought, Market, Sold, Wallet, Workers};
 use crate::map_dynamic::{
-    DispatchID, Dispatcher, Itinerary, ItineraryFollower, ItineraryLeader, ParkingManagement,
-    Router,
+    BuildingInfos, DispatchID, Dispatcher, Itinerary, ItineraryFollower, ItineraryLeader,
+    ParkingManagement, Router,
 };
 use crate::physics::{Collider, CollisionWorld, Speed};
-use crate::souls::desire::{BuyFood, Home, Work};
+use crate::souls::desire::Desires;
 use crate::souls::freight_station::FreightStation;
 use crate::souls::goods_company::GoodsCompany;
 use crate::souls::human::HumanDecision;
+use crate::souls::labor::Skill;
+use crate::souls::population::Age;
+use crate::souls::satisfaction::Satisfaction;
+use crate::souls::train_station::{passenger_train_mut, TrainStation};
+use crate::transportation::bus::BusLines;
 use crate::transportation::train::{Locomotive, LocomotiveReservation, RailWagon};
 use crate::transportation::{Location, Pedestrian, Vehicle, VehicleKind, VehicleState};
 use crate::utils::par_command_buffer::GoriaDrop;
 use crate::utils::resources::Resources;
-use crate::{impl_entity, impl_trans, SoulID};
+use crate::{impl_entity, impl_trans, ParCommandBuffer, SoulID};
 use derive_more::{From, TryInto};
 use geom::{Transform, Vec2, Vec3};
 use serde::Deserialize;
@@ -26,6 +31,7 @@ new_key_type! {
     pub struct WagonID;
     pub struct FreightStationID;
     pub struct CompanyID;
+    pub struct TrainStationID;
 }
 
 impl_entity!(VehicleID, VehicleEnt, vehicles);
@@ -34,6 +40,7 @@ impl_entity!(TrainID, TrainEnt, trains);
 impl_entity!(WagonID, WagonEnt, wagons);
 impl_entity!(FreightStationID, FreightStationEnt, freight_stations);
 impl_entity!(CompanyID, CompanyEnt, companies);
+impl_entity!(TrainStationID, TrainStationEnt, train_stations);
 
 impl_trans!(HumanID);
 impl_trans!(VehicleID);
@@ -41,6 +48,7 @@ impl_trans!(TrainID);
 impl_trans!(WagonID);
 impl_trans!(FreightStationID);
 impl_trans!(CompanyID);
+impl_trans!(TrainStationID);
 
 #[derive(PartialEq, Eq, Copy, Clone, Debug, From, TryInto)]
 pub enum AnyEntity {
@@ -50,6 +58,7 @@ pub enum AnyEntity {
     FreightStationID(FreightStationID),
     CompanyID(CompanyID),
     HumanID(HumanID),
+    TrainStationID(TrainStationID),
 }
 
 #[derive(Inspect, Serialize, Deserialize)]
@@ -92,12 +101,14 @@ pub struct HumanEnt {
     pub speed: Speed,
     pub collider: Option<Collider>,
 
+    pub age: Age,
+    pub skill: Skill,
+    pub satisfaction: Satisfaction,
     pub decision: HumanDecision,
-    pub home: Home,
-    pub food: BuyFood,
+    pub desires: Desires,
     pub bought: Bought,
+    pub wallet: Wallet,
     pub router: Router,
-    pub work: Option<Work>,
 }
 
 impl GoriaDrop for HumanEnt {
@@ -109,6 +120,32 @@ impl GoriaDrop for HumanEnt {
         }
 
         res.get_mut::<Market>().unwrap().remove(SoulID::Human(id));
+        res.get_mut::<BuildingInfos>()
+            .unwrap()
+            .leave(self.desires.home.house(), SoulID::Human(id));
+
+        let cbuf_vehicle = res.get::<ParCommandBuffer<VehicleEnt>>().unwrap();
+        if let Location::Vehicle(vehicle) = self.location {
+            if self.router.riding_bike() {
+                cbuf_vehicle.kill(vehicle);
+            } else if let Some(run) = res.get_mut::<BusLines>().unwrap().run_mut(vehicle) {
+                run.passengers = run.passengers.saturating_sub(1);
+            }
+        }
+        if let Some(car) = self.router.personal_car {
+            cbuf_vehicle.kill(car);
+        }
+        drop(cbuf_vehicle);
+
+        if let Location::Train(train) = self.location {
+            res.get::<ParCommandBuffer<TrainEnt>>()
+                .unwrap()
+                .exec_ent(train, move |goria| {
+                    if let Some(t) = passenger_train_mut(&mut goria.world.train_stations, train) {
+                        t.passengers = t.passengers.saturating_sub(1);
+                    }
+                });
+        }
 
         self.router
             .clear_steps(&mut res.get_mut::<ParkingManagement>().unwrap())
@@ -130,7 +167,7 @@ impl GoriaDrop for TrainEnt {
     fn goria_drop(self, id: TrainID, res: &mut Resources) {
         res.get_mut::<Dispatcher>()
             .unwrap()
-            .unregister(DispatchID::FreightTrain(id));
+            .unregister(DispatchID::train(id, self.locomotive.wagons_kind));
     }
 }
 
@@ -166,6 +203,21 @@ impl GoriaDrop for FreightStationEnt {
     }
 }
 
+#[derive(Inspect, Serialize, Deserialize)]
+pub struct TrainStationEnt {
+    pub trans: Transform,
+    pub s: TrainStation,
+}
+
+impl GoriaDrop for TrainStationEnt {
+    fn goria_drop(self, _: TrainStationID, res: &mut Resources) {
+        let mut d = res.get_mut::<Dispatcher>().unwrap();
+        for train in self.s.trains {
+            d.free(DispatchID::PassengerTrain(train.id));
+        }
+    }
+}
+
 #[derive(Inspect, Serialize, Deserialize)]
 pub struct CompanyEnt {
     pub trans: Transform,
@@ -191,6 +243,7 @@ pub struct World {
     pub wagons: HopSlotMap<WagonID, WagonEnt>,
     pub freight_stations: HopSlotMap<FreightStationID, FreightStationEnt>,
     pub companies: HopSlotMap<CompanyID, CompanyEnt>,
+    pub train_stations: HopSlotMap<TrainStationID, TrainStationEnt>,
 }
 
 impl World {
@@ -218,6 +271,7 @@ impl World {
             AnyEntity::FreightStationID(id) => self.storage_id(id).contains_key(id),
             AnyEntity::CompanyID(id) => self.storage_id(id).contains_key(id),
             AnyEntity::HumanID(id) => self.storage_id(id).contains_key(id),
+            AnyEntity::TrainStationID(id) => self.storage_id(id).contains_key(id),
         }
     }
 
@@ -302,6 +356,7 @@ impl World {
                     .keys()
                     .map(AnyEntity::FreightStationID),
                 self.companies.keys().map(AnyEntity::CompanyID),
+                self.train_stations.keys().map(AnyEntity::TrainStationID),
             )),
         ))
     }
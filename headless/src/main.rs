@@ -1,8 +1,11 @@
 use common::logger::MyLog;
 use common::unwrap_or;
+use egregoria::economy::validate_mods;
 use egregoria::engine_interaction::WorldCommands;
+use egregoria::mods::Mods;
 use egregoria::Egregoria;
 use networking::{Frame, Server, ServerConfiguration, ServerPollResult};
+use std::path::Path;
 use std::time::{Duration, Instant};
 use structopt::StructOpt;
 
@@ -27,11 +30,34 @@ struct Opt {
     /// i.e. 20ms = 50FPS
     #[structopt(long, default_value = "20")]
     timestep: u64,
+
+    /// Validate assets/items.json and assets/companies.json with the mods applied then exit,
+    /// with a non-zero code if they have errors
+    #[structopt(long)]
+    check_data: bool,
 }
 
 fn main() {
     let opt: Opt = Opt::from_args();
     MyLog::init();
+
+    if opt.check_data {
+        let mut mods = Mods::load(Path::new("."));
+        let errors = validate_mods(&mut mods);
+        for conflict in &mods.conflicts {
+            eprintln!("warning: {conflict}");
+        }
+        for error in &errors {
+            eprintln!("error: {error}");
+        }
+        if !errors.is_empty() {
+            eprintln!("{} errors found in the data files", errors.len());
+            std::process::exit(1);
+        }
+        println!("data files are valid");
+        return;
+    }
+
     egregoria::init::init();
 
     log::info!("starting server with version: {}", VERSION);
@@ -5,19 +5,21 @@ use crate::rendering::immediate::ImmediateDraw;
 use crate::uiworld::UiWorld;
 use egregoria::engine_interaction::WorldCommand;
 use egregoria::map::LaneKind;
-use egregoria::transportation::train::{train_length, wagons_positions_for_render};
+use egregoria::transportation::train::{train_length, wagons_positions_for_render, RailWagonKind};
 use egregoria::Egregoria;
 use geom::{Color, OBB};
 use std::option::Option::None;
 
 /// Addtrain handles the "Adding a train" tool
-/// It allows to add a train to any rail lane
+/// It allows to add a freight or passenger train to any rail lane
 #[profiling::function]
 pub fn addtrain(goria: &Egregoria, uiworld: &mut UiWorld) {
     let tool = *uiworld.read::<Tool>();
-    if !matches!(tool, Tool::Train) {
-        return;
-    }
+    let kind = match tool {
+        Tool::Train => RailWagonKind::Freight,
+        Tool::PassengerTrain => RailWagonKind::Passenger,
+        _ => return,
+    };
 
     let inp = uiworld.read::<InputMap>();
     let mut potential = uiworld.write::<PotentialCommands>();
@@ -63,6 +65,7 @@ pub fn addtrain(goria: &Egregoria, uiworld: &mut UiWorld) {
         dist,
         n_wagons,
         lane: nearbylane.id,
+        kind,
     };
     if inp.just_act.contains(&InputAction::Select) {
         commands.push(cmd);
@@ -3,8 +3,8 @@ use crate::uiworld::UiWorld;
 use egregoria::economy::{ItemRegistry, Market};
 use egregoria::transportation::Location;
 use egregoria::{
-    AnyEntity, CompanyEnt, Egregoria, FreightStationEnt, HumanEnt, SoulID, TrainEnt, VehicleEnt,
-    WagonEnt,
+    AnyEntity, CompanyEnt, Egregoria, FreightStationEnt, HumanEnt, SoulID, TrainEnt,
+    TrainStationEnt, VehicleEnt, WagonEnt,
 };
 use egui::Ui;
 use egui_inspect::{Inspect, InspectArgs};
@@ -43,6 +43,12 @@ impl InspectRenderer {
                     &args,
                 )
             }
+            AnyEntity::TrainStationID(x) => <TrainStationEnt as Inspect<TrainStationEnt>>::render(
+                goria.get(x).unwrap(),
+                "",
+                ui,
+                &args,
+            ),
             AnyEntity::CompanyID(x) => {
                 <CompanyEnt as Inspect<CompanyEnt>>::render(goria.get(x).unwrap(), "", ui, &args)
             }
@@ -64,6 +70,19 @@ impl InspectRenderer {
             }
         }
 
+        if let AnyEntity::TrainID(id) = entity {
+            for (hid, h) in goria.world().humans.iter() {
+                if h.location == Location::Train(id)
+                    && ui
+                        .small_button(&*format!("inspect inside train: {hid:?}"))
+                        .clicked()
+                {
+                    self.entity = hid.into();
+                    return;
+                }
+            }
+        }
+
         /*
         if let Some(coll) = goria.comp::<Collider>(self.entity) {
             if let Some((pos, po)) = goria.read::<CollisionWorld>().get(coll.0) {
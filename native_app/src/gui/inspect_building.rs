@@ -1,5 +1,5 @@
 use crate::uiworld::UiWorld;
-use egregoria::economy::{ItemRegistry, Market};
+use egregoria::economy::{ExternalTrades, ItemRegistry, Market};
 use egregoria::engine_interaction::WorldCommand;
 use egregoria::{Egregoria, SoulID};
 use egui::{Context, Ui, Widget};
@@ -8,7 +8,10 @@ use crate::gui::{item_icon, InspectedEntity};
 use egregoria::map::{Building, BuildingID, BuildingKind, Zone, MAX_ZONE_AREA};
 use egregoria::map_dynamic::BuildingInfos;
 use egregoria::souls::freight_station::FreightTrainState;
-use egregoria::souls::goods_company::{GoodsCompanyRegistry, Recipe};
+use egregoria::souls::goods_company::{CompanyKind, GoodsCompany, GoodsCompanyRegistry, Recipe};
+use egregoria::souls::labor::Skill;
+use egregoria::souls::satisfaction::{District, SatisfactionStats};
+use egregoria::souls::train_station::PassengerTrainState;
 use egui_inspect::{Inspect, InspectArgs, InspectVec2Rotation};
 
 /// Inspect a specific building, showing useful information about it
@@ -34,15 +37,23 @@ pub fn inspect_building(uiworld: &mut UiWorld, goria: &Egregoria, ui: &Context,
             }
 
             match building.kind {
-                BuildingKind::House => render_house(ui, uiworld, goria, building),
+                BuildingKind::House => {
+                    render_satisfaction(ui, goria, building);
+                    render_house(ui, uiworld, goria, building);
+                }
                 BuildingKind::GoodsCompany(_) => {
+                    render_satisfaction(ui, goria, building);
                     render_goodscompany(ui, uiworld, goria, building);
                 }
                 BuildingKind::RailFreightStation => {
                     render_freightstation(ui, uiworld, goria, building);
                 }
-                BuildingKind::TrainStation => {}
-                BuildingKind::ExternalTrading => {}
+                BuildingKind::TrainStation => {
+                    render_trainstation(ui, uiworld, goria, building);
+                }
+                BuildingKind::ExternalTrading => {
+                    render_external_connection(ui, goria, building);
+                }
             };
 
             if let Some(ref zone) = building.zone {
@@ -69,6 +80,19 @@ pub fn inspect_building(uiworld: &mut UiWorld, goria: &Egregoria, ui: &Context,
         });
 }
 
+fn render_satisfaction(ui: &mut Ui, goria: &Egregoria, b: &Building) {
+    let stats = goria.read::<SatisfactionStats>();
+    if let Some(s) = stats.building(b.id) {
+        egui::ProgressBar::new(s)
+            .text(format!("satisfaction: {:.0}%", s * 100.0))
+            .desired_width(200.0)
+            .ui(ui);
+    }
+    if let Some(s) = stats.district(District::of(b.door_pos.xy())) {
+        ui.label(format!("District satisfaction: {:.0}%", s * 100.0));
+    }
+}
+
 fn render_house(ui: &mut Ui, uiworld: &mut UiWorld, goria: &Egregoria, b: &Building) {
     let binfos = goria.read::<BuildingInfos>();
     let Some(info) = binfos.get(b.id) else { return; };
@@ -115,6 +139,47 @@ fn render_freightstation(ui: &mut Ui, _uiworld: &mut UiWorld, goria: &Egregoria,
     }
 }
 
+fn render_trainstation(ui: &mut Ui, _uiworld: &mut UiWorld, goria: &Egregoria, b: &Building) {
+    let Some(SoulID::TrainStation(owner)) = goria.read::<BuildingInfos>().owner(b.id) else { return; };
+    let Some(station) = goria.world().get(owner) else { return; };
+
+    ui.label("Trains:");
+    for train in &station.s.trains {
+        ui.horizontal(|ui| {
+            ui.label(format!("{:?} to {:?} ", train.id, train.dest));
+            match train.state {
+                PassengerTrainState::Arriving => {
+                    ui.label("Arriving");
+                }
+                PassengerTrainState::Boarding => {
+                    ui.label("Boarding");
+                }
+                PassengerTrainState::Moving => {
+                    ui.label("Moving");
+                }
+                PassengerTrainState::Alighting => {
+                    ui.label("Alighting");
+                }
+            }
+            ui.label(format!("{}/{}", train.passengers, train.capacity));
+        });
+    }
+}
+
+fn render_external_connection(ui: &mut Ui, goria: &Egregoria, b: &Building) {
+    let external = goria.read::<ExternalTrades>();
+    let Some(c) = external.get(b.id) else { return; };
+
+    ui.label(format!("{:?} connection", c.kind));
+    ui.label(format!("Throughput today: {}/{}", c.used, c.daily_capacity));
+    ui.label(format!("Import price: {:+.0}%", c.import_offset * 100.0));
+    ui.label(format!("Export price: {:+.0}%", -c.export_offset * 100.0));
+    ui.label(format!(
+        "Transport cost: {:.0}% per km",
+        c.transport_cost * 100.0
+    ));
+}
+
 fn render_goodscompany(ui: &mut Ui, uiworld: &mut UiWorld, goria: &Egregoria, b: &Building) {
     let owner = goria.read::<BuildingInfos>().owner(b.id);
 
@@ -130,7 +195,22 @@ fn render_goodscompany(ui: &mut Ui, uiworld: &mut UiWorld, goria: &Egregoria, b:
         .text(format!("workers: {}/{}", workers.0.len(), max_workers))
         .desired_width(200.0)
         .ui(ui);
-    let productivity = goods.productivity(workers.0.len(), b.zone.as_ref());
+    let shifts: Vec<String> = goods
+        .schedule
+        .shifts
+        .iter()
+        .map(|s| format!("{}h-{}h", s.start_hour, s.end_hour))
+        .collect();
+    ui.label(format!("Shifts: {}", shifts.join(", ")));
+    let days: Vec<&str> = goods.schedule.days.iter().map(|d| d.short_name()).collect();
+    ui.label(format!("Working days: {}", days.join(" ")));
+    let wages = goods.skill.wage().inner() as f32 / Skill::Basic.wage().inner() as f32;
+    ui.label(format!(
+        "Required skill: {} (wages x{wages})",
+        goods.skill.name()
+    ));
+    let productivity = goods.productivity(workers.0.len(), b.zone.as_ref())
+        * goria.read::<SatisfactionStats>().productivity(b.id);
     let productivity = (productivity * 100.0).round();
     if productivity < 100.0 {
         egui::ProgressBar::new(productivity)
@@ -139,26 +219,104 @@ fn render_goodscompany(ui: &mut Ui, uiworld: &mut UiWorld, goria: &Egregoria, b:
             .ui(ui);
     }
 
-    render_recipe(ui, uiworld, goria, &goods.recipe);
+    if let CompanyKind::Warehouse { .. } = goods.kind {
+        render_warehouse_capacity(ui, uiworld, goria, goods, b.id);
+    } else {
+        if goods.recipes.len() > 1 {
+            render_recipe_choice(ui, uiworld, goods, b.id);
+        }
+        if let Some(recipe) = goods.recipe() {
+            render_recipe(ui, uiworld, goria, recipe);
+        }
 
-    egui::ProgressBar::new(goods.progress)
-        .show_percentage()
-        .desired_width(200.0)
-        .ui(ui);
+        egui::ProgressBar::new(goods.progress)
+            .show_percentage()
+            .desired_width(200.0)
+            .ui(ui);
+    }
 
     ui.add_space(10.0);
     ui.label("Storage");
 
-    let jobopening = itemregistry.id("job-opening");
     for (&id, m) in market.iter() {
         let Some(v) = m.capital(c_id.into()) else { continue };
-        if id == jobopening && v == 0 {
-            continue;
-        }
         let Some(item) = itemregistry.get(id) else { continue };
 
         item_icon(ui, uiworld, item, v);
     }
+
+    ui.add_space(10.0);
+    render_pnl(ui, goods);
+}
+
+fn render_pnl(ui: &mut Ui, goods: &GoodsCompany) {
+    ui.label("Finances");
+    if goods.insolvent_since.is_some() {
+        ui.colored_label(egui::Color32::RED, "In debt, going bankrupt soon");
+    }
+
+    let pnl = &goods.pnl;
+    egui::Grid::new("pnl").num_columns(2).show(ui, |ui| {
+        for (label, v) in [
+            ("Cash", goods.money),
+            ("Revenue", pnl.revenue),
+            ("Purchases", -pnl.purchases),
+            ("Wages", -pnl.wages),
+            ("Taxes", -pnl.taxes),
+            ("Profit", pnl.profit()),
+        ] {
+            ui.label(label);
+            ui.label(v.to_string());
+            ui.end_row();
+        }
+    });
+}
+
+fn render_warehouse_capacity(
+    ui: &mut Ui,
+    uiworld: &UiWorld,
+    goria: &Egregoria,
+    goods: &GoodsCompany,
+    id: BuildingID,
+) {
+    let registry = goria.read::<ItemRegistry>();
+
+    ui.label("Capacity");
+    egui::Grid::new("warehouse_capacity")
+        .num_columns(2)
+        .show(ui, |ui| {
+            for &(item, capacity) in &goods.storage {
+                let Some(it) = registry.get(item) else { continue };
+                ui.label(it.label.as_str());
+                let mut cpy = capacity;
+                if egui::DragValue::new(&mut cpy)
+                    .clamp_range(0..=1000)
+                    .ui(ui)
+                    .changed()
+                {
+                    uiworld.commands().set_warehouse_capacity(id, item, cpy);
+                }
+                ui.end_row();
+            }
+        });
+}
+
+fn render_recipe_choice(ui: &mut Ui, uiworld: &UiWorld, goods: &GoodsCompany, id: BuildingID) {
+    ui.horizontal(|ui| {
+        ui.label("Recipe");
+        if ui.selectable_label(goods.auto_recipe, "Auto").clicked() {
+            uiworld.commands().set_company_recipe(id, None);
+        }
+        for i in 0..goods.recipes.len() {
+            let selected = !goods.auto_recipe && goods.active_recipe == i;
+            if ui
+                .selectable_label(selected, format!("{}", i + 1))
+                .clicked()
+            {
+                uiworld.commands().set_company_recipe(id, Some(i));
+            }
+        }
+    });
 }
 
 fn render_recipe(ui: &mut Ui, uiworld: &UiWorld, goria: &Egregoria, recipe: &Recipe) {
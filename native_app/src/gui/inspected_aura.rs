@@ -22,6 +22,7 @@ pub fn inspected_aura(goria: &Egregoria, uiworld: &mut UiWorld) {
             match *loc {
                 Location::Outside => {}
                 Location::Vehicle(v) => pos = goria.pos(v),
+                Location::Train(t) => pos = goria.pos(t),
                 Location::Building(b) => pos = map.buildings().get(b).map(|b| b.door_pos),
             }
         }
@@ -114,6 +114,7 @@ pub enum Tool {
     LotBrush,
     SpecialBuilding,
     Train,
+    PassengerTrain,
 }
 
 impl Tool {
@@ -129,6 +130,7 @@ impl Tool {
                 | Tool::RoadEditor
                 | Tool::Bulldozer
                 | Tool::Train
+                | Tool::PassengerTrain
         )
     }
 }
@@ -2,7 +2,7 @@ use crate::gui::Tool;
 use crate::inputmap::{InputAction, InputMap};
 use crate::rendering::immediate::ImmediateDraw;
 use crate::uiworld::UiWorld;
-use egregoria::map::{IntersectionID, LightPolicy, TurnPolicy};
+use egregoria::map::{IntersectionID, LaneID, LaneTurns, LightPolicy, TurnPolicy};
 use egregoria::map::{ProjectFilter, ProjectKind};
 use egregoria::Egregoria;
 use geom::Color;
@@ -12,6 +12,8 @@ pub struct IntersectionComponent {
     pub id: IntersectionID,
     pub turn_policy: TurnPolicy,
     pub light_policy: LightPolicy,
+    /// Allowed directions of each incoming vehicle lane
+    pub lane_turns: Vec<(LaneID, LaneTurns)>,
 }
 
 #[derive(Default)]
@@ -82,7 +84,14 @@ pub fn roadeditor(goria: &Egregoria, uiworld: &mut UiWorld) {
             state.inspect = Some(IntersectionComponent {
                 id,
                 turn_policy: inter.turn_policy,
-                light_policy: inter.light_policy,
+                light_policy: inter.light_policy.clone(),
+                lane_turns: inter
+                    .roads
+                    .iter()
+                    .flat_map(|&r| map.roads()[r].incoming_lanes_to(id))
+                    .filter(|(_, kind)| kind.vehicles())
+                    .map(|&(l, _)| (l, inter.lane_turns.get(&l).copied().unwrap_or_default()))
+                    .collect(),
             });
             state.dirty = false;
         }
@@ -95,8 +104,15 @@ pub fn roadeditor(goria: &Egregoria, uiworld: &mut UiWorld) {
             commands.map_update_intersection_policy(
                 interc.id,
                 interc.turn_policy,
-                interc.light_policy,
+                interc.light_policy.clone(),
             );
+            if let Some(inter) = map.intersections().get(interc.id) {
+                for &(lane, turns) in &interc.lane_turns {
+                    if inter.lane_turns.get(&lane).copied().unwrap_or_default() != turns {
+                        commands.map_set_lane_turns(interc.id, lane, turns);
+                    }
+                }
+            }
         }
         state.dirty = false;
     }
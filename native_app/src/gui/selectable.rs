@@ -11,6 +11,7 @@ pub fn select_radius(id: AnyEntity) -> f32 {
         AnyEntity::TrainID(_) => 10.0,
         AnyEntity::WagonID(_) => 10.0,
         AnyEntity::FreightStationID(_) => 0.0,
+        AnyEntity::TrainStationID(_) => 0.0,
         AnyEntity::CompanyID(_) => 0.0,
         AnyEntity::HumanID(_) => 3.0,
     }
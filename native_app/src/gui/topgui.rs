@@ -12,10 +12,11 @@ use crate::gui::{
 use crate::inputmap::{InputAction, InputMap};
 use crate::uiworld::{SaveLoadState, UiWorld};
 use common::saveload::Encoder;
-use egregoria::economy::{Government, Item, ItemRegistry, Money};
+use egregoria::economy::{ExternalTradeKind, Government, Item, ItemRegistry, Money};
 use egregoria::engine_interaction::WorldCommand;
 use egregoria::map::{
-    BuildingGen, BuildingKind, LanePatternBuilder, LightPolicy, MapProject, TurnPolicy, Zone,
+    BuildingGen, BuildingKind, LanePatternBuilder, LaneTurns, MapProject, RoadClass, TurnPolicy,
+    Zone,
 };
 use egregoria::souls::goods_company::GoodsCompanyRegistry;
 use egregoria::utils::time::{GameTime, SECONDS_PER_HOUR};
@@ -219,22 +220,26 @@ impl Gui {
                 Window::new("Editor")
                     .fixed_size([150.0, 200.0])
                     .fixed_pos([w - 150.0 - toolbox_w, h * 0.5 - 30.0])
-                    .vscroll(false)
+                    .vscroll(true)
                     .title_bar(true)
                     .collapsible(false)
                     .resizable(false)
                     .show(ui, |ui| {
                         ui.label("Light policy");
-                        *dirty |= <LightPolicy as Inspect<LightPolicy>>::render_mut(
-                            &mut v.light_policy,
-                            "",
-                            ui,
-                            &InspectArgs {
-                                header: Some(false),
-                                indent_children: Some(false),
-                                ..Default::default()
-                            },
-                        );
+                        let map = goria.map();
+                        if let Some(inter) = map.intersections().get(v.id) {
+                            *dirty |= v.light_policy.render_editor(
+                                inter,
+                                map.roads(),
+                                "",
+                                ui,
+                                &InspectArgs {
+                                    header: Some(false),
+                                    indent_children: Some(false),
+                                    ..Default::default()
+                                },
+                            );
+                        }
                         ui.add_space(10.0);
                         ui.label("Turn policy");
                         *dirty |= <TurnPolicy as Inspect<TurnPolicy>>::render_mut(
@@ -247,6 +252,16 @@ impl Gui {
                                 ..Default::default()
                             },
                         );
+                        ui.add_space(10.0);
+                        ui.label("Lane turns");
+                        for (i, (_, turns)) in v.lane_turns.iter_mut().enumerate() {
+                            *dirty |= <LaneTurns as Inspect<LaneTurns>>::render_mut(
+                                turns,
+                                &format!("Lane {}", i + 1),
+                                ui,
+                                &InspectArgs::default(),
+                            );
+                        }
                     });
             }
         }
@@ -254,7 +269,7 @@ impl Gui {
         if matches!(*uiworld.read::<Tab>(), Tab::Train) {
             let rbw = 150.0;
             Window::new("Trains")
-                .fixed_size([rbw, 83.0])
+                .fixed_size([rbw, 155.0])
                 .fixed_pos([w - rbw - toolbox_w, h * 0.5 - 30.0])
                 .hscroll(false)
                 .title_bar(true)
@@ -271,6 +286,14 @@ impl Gui {
                         *uiworld.write::<Tool>() = Tool::Train;
                     }
 
+                    let mut addpassenger = RichText::new("Add Passenger Train");
+                    if *uiworld.read::<Tool>() == Tool::PassengerTrain {
+                        addpassenger = addpassenger.strong();
+                    };
+                    if ui.button(addpassenger).clicked() {
+                        *uiworld.write::<Tool>() = Tool::PassengerTrain;
+                    }
+
                     /*
                     if ui.button_with_size("Trainstation", [rbw, 30.0]) {
                         *uiworld.write::<Tool>() = Tool::SpecialBuilding;
@@ -335,6 +358,68 @@ impl Gui {
                             road_snap: false,
                         });
                     }
+
+                    if ui.button("Train station").clicked() {
+                        *uiworld.write::<Tool>() = Tool::SpecialBuilding;
+
+                        uiworld.write::<SpecialBuildingResource>().opt = Some(SpecialBuildKind {
+                            make: Box::new(move |args| {
+                                let obb = args.obb;
+                                let c = obb.center().z(args.mpos.z + 0.3);
+
+                                let [offx, offy] = obb.axis().map(|x| x.normalize().z(0.0));
+
+                                let pat = LanePatternBuilder::new().rail(true).build();
+
+                                vec![
+                                    WorldCommand::MapMakeConnection {
+                                        from: MapProject::ground(c - offx * 45.0 - offy * 100.0),
+                                        to: MapProject::ground(c - offx * 45.0 + offy * 100.0),
+                                        inter: None,
+                                        pat,
+                                    },
+                                    WorldCommand::MapBuildSpecialBuilding {
+                                        pos: args.obb,
+                                        kind: BuildingKind::TrainStation,
+                                        gen: BuildingGen::NoWalkway {
+                                            door_pos: Vec2::ZERO,
+                                        },
+                                        zone: None,
+                                    },
+                                ]
+                            }),
+                            w: 160.0,
+                            h: 200.0,
+                            asset: "rail_freight_station.glb".to_string(),
+                            road_snap: false,
+                        });
+                    }
+
+                    for (label, kind) in [
+                        ("Highway connection", ExternalTradeKind::Highway),
+                        ("Port", ExternalTradeKind::Port),
+                    ] {
+                        if ui.button(label).clicked() {
+                            *uiworld.write::<Tool>() = Tool::SpecialBuilding;
+
+                            uiworld.write::<SpecialBuildingResource>().opt =
+                                Some(SpecialBuildKind {
+                                    make: Box::new(move |args| {
+                                        vec![WorldCommand::MapBuildExternalConnection {
+                                            pos: args.obb,
+                                            kind,
+                                            gen: BuildingGen::CenteredDoor {
+                                                vertical_factor: 1.0,
+                                            },
+                                        }]
+                                    }),
+                                    w: 60.0,
+                                    h: 60.0,
+                                    asset: "external_trading.glb".to_string(),
+                                    road_snap: true,
+                                });
+                        }
+                    }
                 });
         }
 
@@ -369,23 +454,29 @@ impl Gui {
                         ),
                         ("Street", LanePatternBuilder::new()),
                         ("Street one-way", LanePatternBuilder::new().one_way(true)),
+                        (
+                            "Street with bike lanes",
+                            LanePatternBuilder::new().bike_lanes(true),
+                        ),
                         (
                             "Avenue",
-                            LanePatternBuilder::new().n_lanes(2).speed_limit(13.0),
+                            LanePatternBuilder::new()
+                                .n_lanes(2)
+                                .class(RoadClass::Arterial),
                         ),
                         (
                             "Avenue one-way",
                             LanePatternBuilder::new()
                                 .n_lanes(2)
                                 .one_way(true)
-                                .speed_limit(13.0),
+                                .class(RoadClass::Arterial),
                         ),
                         (
                             "Drive",
                             LanePatternBuilder::new()
                                 .parking(false)
                                 .sidewalks(false)
-                                .speed_limit(13.0),
+                                .class(RoadClass::Arterial),
                         ),
                         (
                             "Drive one-way",
@@ -393,13 +484,13 @@ impl Gui {
                                 .parking(false)
                                 .sidewalks(false)
                                 .one_way(true)
-                                .speed_limit(13.0),
+                                .class(RoadClass::Arterial),
                         ),
                         (
                             "Highway",
                             LanePatternBuilder::new()
                                 .n_lanes(3)
-                                .speed_limit(25.0)
+                                .class(RoadClass::Highway)
                                 .parking(false)
                                 .sidewalks(false),
                         ),
@@ -407,7 +498,7 @@ impl Gui {
                             "Highway one-way",
                             LanePatternBuilder::new()
                                 .n_lanes(3)
-                                .speed_limit(25.0)
+                                .class(RoadClass::Highway)
                                 .parking(false)
                                 .sidewalks(false)
                                 .one_way(true),
@@ -570,26 +661,38 @@ impl Gui {
                             .resizable(false)
                             .show(ui.ctx(), |ui| {
                                 ui.label(format!("workers: {}", descr.n_workers));
-                                ui.add_space(10.0);
-                                if !descr.recipe.consumption.is_empty() {
-                                    ui.label("consumption:");
-                                    for (kind, n) in &descr.recipe.consumption {
-                                        item_icon(ui, uiworld, &iregistry[*kind], *n);
-                                    }
+                                if !descr.storage.is_empty() {
                                     ui.add_space(10.0);
-                                }
-                                if !descr.recipe.production.is_empty() {
-                                    ui.label("production:");
-                                    for (kind, n) in &descr.recipe.production {
+                                    ui.label("stores:");
+                                    for (kind, n) in &descr.storage {
                                         item_icon(ui, uiworld, &iregistry[*kind], *n);
                                     }
+                                }
+                                for (i, recipe) in descr.recipes.iter().enumerate() {
                                     ui.add_space(10.0);
+                                    if descr.recipes.len() > 1 {
+                                        ui.label(format!("recipe {}", i + 1));
+                                    }
+                                    if !recipe.consumption.is_empty() {
+                                        ui.label("consumption:");
+                                        for (kind, n) in &recipe.consumption {
+                                            item_icon(ui, uiworld, &iregistry[*kind], *n);
+                                        }
+                                        ui.add_space(10.0);
+                                    }
+                                    if !recipe.production.is_empty() {
+                                        ui.label("production:");
+                                        for (kind, n) in &recipe.production {
+                                            item_icon(ui, uiworld, &iregistry[*kind], *n);
+                                        }
+                                        ui.add_space(10.0);
+                                    }
+                                    ui.label(format!("time: {}s", recipe.complexity));
+                                    ui.label(format!(
+                                        "storage multiplier: {}",
+                                        recipe.storage_multiplier
+                                    ));
                                 }
-                                ui.label(format!("time: {}s", descr.recipe.complexity));
-                                ui.label(format!(
-                                    "storage multiplier: {}",
-                                    descr.recipe.storage_multiplier
-                                ));
                             });
                     }
                 });
@@ -654,8 +757,8 @@ impl Gui {
             .anchor(Align2::LEFT_BOTTOM, [0.0, 0.0])
             .show(ui, |ui| {
                 ui.horizontal(|ui| {
-                    ui.label(format!(" Day {}", time.day));
-                    ui.add_space(40.0);
+                    ui.label(format!(" {} Day {}", time.weekday().short_name(), time.day));
+                    ui.add_space(15.0);
                     const OFF: i32 = SECONDS_PER_HOUR / 60;
                     ui.label(format!(
                         "{:02}:{:02}:{:02}",
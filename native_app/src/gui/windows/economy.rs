@@ -1,8 +1,11 @@
 use crate::uiworld::UiWorld;
 use common::timestep::UP_DT;
 use egregoria::economy::{
-    EcoStats, ItemHistories, ItemRegistry, Market, HISTORY_SIZE, LEVEL_FREQS, LEVEL_NAMES,
+    BudgetLedger, EcoStats, Government, ItemHistories, ItemRegistry, LedgerCategory, Maintenance,
+    Market, Money, Tax, HISTORY_SIZE, LEVEL_FREQS, LEVEL_NAMES,
 };
+use egregoria::souls::labor::LaborMarket;
+use egregoria::souls::population::{PopulationEvent, PopulationStats};
 use egregoria::Egregoria;
 use egui::plot::{Line, PlotPoints};
 use egui::{Align2, Color32, Ui};
@@ -15,6 +18,8 @@ enum EconomyTab {
     ImportExports,
     InternalTrade,
     MarketPrices,
+    Budget,
+    Population,
 }
 
 #[derive(Copy, Clone, Default)]
@@ -38,6 +43,7 @@ pub fn economy(window: egui::Window<'_>, ui: &egui::Context, uiw: &mut UiWorld,
         tab: EconomyTab::ImportExports,
         hist_type: Default::default(),
     });
+    let uiw: &UiWorld = uiw;
     let mut state = uiw.write::<EconomyState>();
     let ecostats = goria.read::<EcoStats>();
     let registry = goria.read::<ItemRegistry>();
@@ -74,6 +80,18 @@ pub fn economy(window: egui::Window<'_>, ui: &egui::Context, uiw: &mut UiWorld,
                 {
                     state.tab = EconomyTab::MarketPrices;
                 }
+                if ui
+                    .selectable_label(matches!(state.tab, EconomyTab::Budget), "Budget")
+                    .clicked()
+                {
+                    state.tab = EconomyTab::Budget;
+                }
+                if ui
+                    .selectable_label(matches!(state.tab, EconomyTab::Population), "Population")
+                    .clicked()
+                {
+                    state.tab = EconomyTab::Population;
+                }
             });
 
             ui.horizontal(|ui| {
@@ -282,7 +300,17 @@ pub fn economy(window: egui::Window<'_>, ui: &egui::Context, uiw: &mut UiWorld,
                 }
                 EconomyTab::MarketPrices => {
                     ui.push_id(3, |ui| {
-                        render_market_prices(goria, ui);
+                        render_market_prices(goria, ui, &ecostats, curlevel, &xs);
+                    });
+                }
+                EconomyTab::Budget => {
+                    ui.push_id(4, |ui| {
+                        render_budget(goria, uiw, ui, curlevel, &xs);
+                    });
+                }
+                EconomyTab::Population => {
+                    ui.push_id(5, |ui| {
+                        render_population(goria, ui, curlevel, &xs);
                     });
                 }
             }
@@ -290,13 +318,247 @@ pub fn economy(window: egui::Window<'_>, ui: &egui::Context, uiw: &mut UiWorld,
         });
 }
 
-fn render_market_prices(goria: &Egregoria, ui: &mut Ui) {
+fn render_market_prices(
+    goria: &Egregoria,
+    ui: &mut Ui,
+    ecostats: &EcoStats,
+    curlevel: usize,
+    xs: &[f64],
+) {
     let registry = goria.read::<ItemRegistry>();
     let market = goria.read::<Market>();
-    egui::Grid::new("marketprices").show(ui, |ui| {
-        for (id, market) in market.iter() {
-            ui.label(&registry[*id].name);
-            ui.label(market.ext_value.to_string());
+
+    egui::plot::Plot::new("priceplot")
+        .height(200.0)
+        .allow_boxed_zoom(false)
+        .include_y(0.0)
+        .include_x(0.0)
+        .allow_drag(false)
+        .allow_scroll(false)
+        .allow_zoom(false)
+        .show(ui, |ui| {
+            let cursor = ecostats.prices.cursors()[curlevel];
+            let c_next = (cursor + 1) % HISTORY_SIZE;
+            for (id, history) in ecostats.prices.iter_histories(curlevel) {
+                let ring = &history.past_ring;
+                if ring.iter().all(|x| *x == Money::ZERO) {
+                    continue;
+                }
+
+                let h = common::hash_u64(id.data().as_ffi());
+                let random_col = Color::new(
+                    0.5 + 0.5 * common::rand::rand2(h as f32, 0.0),
+                    0.5 + 0.5 * common::rand::rand2(h as f32, 1.0),
+                    0.5 + 0.5 * common::rand::rand2(h as f32, 2.0),
+                    1.0,
+                );
+
+                let points = ring[c_next..HISTORY_SIZE]
+                    .iter()
+                    .chain(ring[0..c_next].iter())
+                    .zip(xs.iter())
+                    .filter(|(v, _)| **v != Money::ZERO)
+                    .map(|(v, x)| [*x, v.cents() as f64 / 100.0]);
+
+                ui.line(
+                    Line::new(PlotPoints::from_iter(points))
+                        .color(Color32::from_rgba_unmultiplied(
+                            (random_col.r * 255.0) as u8,
+                            (random_col.g * 255.0) as u8,
+                            (random_col.b * 255.0) as u8,
+                            (random_col.a * 255.0) as u8,
+                        ))
+                        .name(&registry[id].name),
+                );
+            }
+        });
+
+    egui::ScrollArea::vertical()
+        .max_height(250.0)
+        .show(ui, |ui| {
+            egui::Grid::new("marketprices").show(ui, |ui| {
+                ui.label("Item");
+                ui.label("Price");
+                ui.label("Import");
+                ui.label("Export");
+                ui.end_row();
+                for (id, market) in market.iter() {
+                    ui.label(&registry[*id].name);
+                    ui.label(market.price.to_string());
+                    ui.label(market.import_price().to_string());
+                    ui.label(market.export_price().to_string());
+                    ui.end_row();
+                }
+            });
+        });
+}
+
+fn render_budget(goria: &Egregoria, uiw: &UiWorld, ui: &mut Ui, curlevel: usize, xs: &[f64]) {
+    let ledger = goria.read::<BudgetLedger>();
+    let gvt = goria.read::<Government>();
+    let maintenance = goria.read::<Maintenance>();
+
+    let cursor = ledger.cursors()[curlevel];
+    let c_next = (cursor + 1) % HISTORY_SIZE;
+    let mut revenue = [Money::ZERO; HISTORY_SIZE];
+    let mut spending = [Money::ZERO; HISTORY_SIZE];
+    for (cat, history) in ledger.iter_histories(curlevel) {
+        let sums = if cat.is_revenue() {
+            &mut revenue
+        } else {
+            &mut spending
+        };
+        for (sum, v) in sums.iter_mut().zip(history.past_ring.iter()) {
+            *sum += *v;
+        }
+    }
+
+    egui::plot::Plot::new("budgetplot")
+        .height(200.0)
+        .allow_boxed_zoom(false)
+        .include_y(0.0)
+        .include_x(0.0)
+        .allow_drag(false)
+        .allow_scroll(false)
+        .allow_zoom(false)
+        .show(ui, |ui| {
+            for (ring, name, color) in [
+                (&revenue, "Revenue", Color32::GREEN),
+                (&spending, "Spending", Color32::RED),
+            ] {
+                let points = ring[c_next..HISTORY_SIZE]
+                    .iter()
+                    .chain(ring[0..c_next].iter())
+                    .zip(xs.iter())
+                    .map(|(v, x)| [*x, v.cents() as f64 / 100.0]);
+
+                ui.line(
+                    Line::new(PlotPoints::from_iter(points))
+                        .color(color)
+                        .name(name),
+                );
+            }
+        });
+
+    ui.columns(2, |ui| {
+        egui::Grid::new("budgetgrid").show(&mut ui[0], |ui| {
+            ui.label("Category");
+            ui.label(LEVEL_NAMES[curlevel]);
+            ui.label("Total");
+            ui.end_row();
+            for cat in LedgerCategory::ALL {
+                let sum: Money = ledger
+                    .iter_histories(curlevel)
+                    .filter(|(c, _)| *c == cat)
+                    .flat_map(|(_, h)| h.past_ring.iter().copied())
+                    .sum();
+                let sign = if cat.is_revenue() { "+" } else { "-" };
+                ui.label(cat.name());
+                ui.label(format!("{sign}{sum}"));
+                ui.label(format!("{sign}{}", ledger.total(cat)));
+                ui.end_row();
+            }
+        });
+
+        let ui = &mut ui[1];
+        ui.label(format!("Treasury: {}", gvt.money));
+        ui.label(format!("Maintenance: {}/day", maintenance.daily_cost));
+        if maintenance.unpaid_days > 0 {
+            ui.colored_label(
+                Color32::RED,
+                format!(
+                    "Maintenance unpaid for {} days, the roads are wearing",
+                    maintenance.unpaid_days
+                ),
+            );
+        }
+        ui.add_space(10.0);
+        for (tax, name) in [
+            (Tax::Income, "Income tax"),
+            (Tax::Corporate, "Corporate tax"),
+            (Tax::Tariff, "Tariffs"),
+        ] {
+            let mut rate = gvt.taxes.get(tax) * 100.0;
+            if ui
+                .add(
+                    egui::Slider::new(&mut rate, 0.0..=100.0)
+                        .text(name)
+                        .suffix("%"),
+                )
+                .changed()
+            {
+                uiw.commands().set_tax_rate(tax, rate / 100.0);
+            }
+        }
+    });
+}
+
+fn render_population(goria: &Egregoria, ui: &mut Ui, curlevel: usize, xs: &[f64]) {
+    let stats = goria.read::<PopulationStats>();
+    let labor = goria.read::<LaborMarket>();
+    let Some(population) = stats.population(curlevel) else { return; };
+    let Some(unemployed) = labor.history(curlevel) else { return; };
+
+    // oldest to newest
+    let ordered = |ring: &[u32; HISTORY_SIZE], cursor: usize| {
+        let c_next = (cursor + 1) % HISTORY_SIZE;
+        ring[c_next..HISTORY_SIZE]
+            .iter()
+            .chain(ring[0..c_next].iter())
+            .zip(xs.iter())
+            .map(|(v, x)| [*x, *v as f64])
+            .collect::<PlotPoints>()
+    };
+
+    egui::plot::Plot::new("populationplot")
+        .height(200.0)
+        .allow_boxed_zoom(false)
+        .include_y(0.0)
+        .include_x(0.0)
+        .allow_drag(false)
+        .allow_scroll(false)
+        .allow_zoom(false)
+        .show(ui, |ui| {
+            let points = ordered(&population.past_ring, stats.cursors()[curlevel]);
+            ui.line(
+                Line::new(points)
+                    .color(Color32::LIGHT_BLUE)
+                    .name("Population"),
+            );
+            let points = ordered(&unemployed.past_ring, labor.cursors()[curlevel]);
+            ui.line(
+                Line::new(points)
+                    .color(Color32::LIGHT_RED)
+                    .name("Unemployed"),
+            );
+        });
+
+    ui.label(format!("Population: {}", goria.world().humans.len()));
+    ui.label(format!(
+        "Unemployed: {} ({:.1}%)",
+        labor.unemployed(),
+        labor.unemployment_rate() * 100.0
+    ));
+    ui.label(format!("Open jobs: {}", labor.vacancies()));
+    ui.label(format!(
+        "Hires: {}, job switches: {}",
+        labor.hires(),
+        labor.switches()
+    ));
+    egui::Grid::new("populationgrid").show(ui, |ui| {
+        ui.label("Event");
+        ui.label(LEVEL_NAMES[curlevel]);
+        ui.label("Total");
+        ui.end_row();
+        for event in PopulationEvent::ALL {
+            let sum: u32 = stats
+                .iter_histories(curlevel)
+                .filter(|(e, _)| *e == event)
+                .flat_map(|(_, h)| h.past_ring.iter().copied())
+                .sum();
+            ui.label(event.name());
+            ui.label(format!("{sum}"));
+            ui.label(format!("{}", stats.total(event)));
             ui.end_row();
         }
     });
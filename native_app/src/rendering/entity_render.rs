@@ -1,10 +1,11 @@
 use egregoria::transportation::train::RailWagonKind;
 use egregoria::transportation::{Location, VehicleKind};
 use egregoria::Egregoria;
-use geom::{LinearColor, Vec3, V3};
+use geom::{vec3, LinearColor, Vec3, V3};
 use wgpu_engine::meshload::load_mesh;
 use wgpu_engine::{
-    FrameContext, GfxContext, InstancedMeshBuilder, MeshInstance, SpriteBatchBuilder,
+    FrameContext, GfxContext, InstancedMeshBuilder, Material, Mesh, MeshBuilder, MeshInstance,
+    MeshVertex, MetallicRoughness, SpriteBatchBuilder,
 };
 
 /// Render all entities using instanced rendering for performance
@@ -16,6 +17,69 @@ pub struct InstancedRender {
     pub wagons_freight: InstancedMeshBuilder,
     pub trucks: InstancedMeshBuilder,
     pub pedestrians: InstancedMeshBuilder,
+    pub bikes: InstancedMeshBuilder,
+}
+
+/// There is no bike model, so it is made of boxes: two wheels, the frame and the rider
+fn bike_mesh(gfx: &mut GfxContext) -> Mesh {
+    let mat = gfx.register_material(Material::new(
+        gfx,
+        gfx.palette(),
+        MetallicRoughness {
+            metallic: 0.0,
+            roughness: 1.0,
+            tex: None,
+        },
+        None,
+    ));
+    let mut b = MeshBuilder::new(mat);
+
+    let wheel = [0.1, 0.1, 0.1, 1.0];
+    let frame = [0.6, 0.6, 0.6, 1.0];
+    let rider = [1.0; 4];
+    // center and half extents along the direction, the side and up
+    let boxes = [
+        (vec3(0.55, 0.0, 0.35), vec3(0.35, 0.03, 0.35), wheel),
+        (vec3(-0.55, 0.0, 0.35), vec3(0.35, 0.03, 0.35), wheel),
+        (vec3(0.0, 0.0, 0.7), vec3(0.55, 0.04, 0.05), frame),
+        (vec3(-0.1, 0.0, 1.3), vec3(0.15, 0.2, 0.45), rider),
+    ];
+
+    for (center, half, color) in boxes {
+        for (n, u, v) in [
+            (Vec3::X, Vec3::Y, Vec3::Z),
+            (Vec3::Y, Vec3::Z, Vec3::X),
+            (Vec3::Z, Vec3::X, Vec3::Y),
+        ] {
+            for normal in [n, -n] {
+                let c = center + normal * half;
+                let (u, v) = (u * half, v * half);
+                // keep the faces counter clockwise seen from outside
+                let corners = if normal == n {
+                    [c - u - v, c + u - v, c + u + v, c - u + v]
+                } else {
+                    [c - u - v, c - u + v, c + u + v, c + u - v]
+                };
+
+                b.extend_with(|vertices, add_index| {
+                    for p in corners {
+                        vertices.push(MeshVertex {
+                            position: p.into(),
+                            normal,
+                            uv: [0.0; 2],
+                            color,
+                            tangent: [0.0; 4],
+                        });
+                    }
+                    for i in [0, 1, 2, 0, 2, 3] {
+                        add_index(i);
+                    }
+                });
+            }
+        }
+    }
+
+    b.build(gfx).unwrap()
 }
 
 impl InstancedRender {
@@ -34,6 +98,7 @@ impl InstancedRender {
             wagons_passenger: InstancedMeshBuilder::new(load_mesh(gfx, "wagon.glb").unwrap()),
             trucks: InstancedMeshBuilder::new(load_mesh(gfx, "truck.glb").unwrap()),
             pedestrians: InstancedMeshBuilder::new(load_mesh(gfx, "pedestrian.glb").unwrap()),
+            bikes: InstancedMeshBuilder::new(bike_mesh(gfx)),
         }
     }
 
@@ -42,6 +107,7 @@ impl InstancedRender {
         self.cars.instances.clear();
         self.trucks.instances.clear();
         self.pedestrians.instances.clear();
+        self.bikes.instances.clear();
         for v in goria.world().vehicles.values() {
             let trans = &v.trans;
             let instance = MeshInstance {
@@ -52,8 +118,8 @@ impl InstancedRender {
 
             match v.vehicle.kind {
                 VehicleKind::Car => self.cars.instances.push(instance),
-                VehicleKind::Truck => self.trucks.instances.push(instance),
-                _ => {}
+                VehicleKind::Truck | VehicleKind::Bus => self.trucks.instances.push(instance),
+                VehicleKind::Bike => self.bikes.instances.push(instance),
             }
         }
 
@@ -125,6 +191,9 @@ impl InstancedRender {
         if let Some(x) = self.pedestrians.build(fctx.gfx) {
             fctx.objs.push(Box::new(x));
         }
+        if let Some(x) = self.bikes.build(fctx.gfx) {
+            fctx.objs.push(Box::new(x));
+        }
         if let Some(x) = self.locomotives.build(fctx.gfx) {
             fctx.objs.push(Box::new(x));
         }
@@ -4,13 +4,14 @@ use egregoria::map::{
     BuildingKind, Intersection, LaneKind, LotKind, Map, PylonPosition, Road, Roads, Terrain,
     TurnKind, CROSSWALK_WIDTH,
 };
+use egregoria::mods::Mods;
 use egregoria::souls::goods_company::GoodsCompanyRegistry;
 use egregoria::Egregoria;
 use geom::{minmax, vec2, vec3, Color, LinearColor, PolyLine3, Polygon, Spline, Vec2, Vec3};
 use std::ops::{Mul, Neg};
 use std::sync::Arc;
 use wgpu_engine::earcut::earcut;
-use wgpu_engine::meshload::load_mesh;
+use wgpu_engine::meshload::load_mesh_from_path;
 use wgpu_engine::{
     Drawable, FrameContext, GfxContext, InstancedMeshBuilder, Material, MeshBuilder, MeshInstance,
     MeshVertex, MetallicRoughness, SpriteBatchBuilder, Tesselator,
@@ -45,6 +46,8 @@ impl MapMeshHandler {
         let mut buildmeshes = FastMap::default();
         let mut zonemeshes = FastMap::default();
 
+        let mods = goria.read::<Mods>();
+
         for descr in goria.read::<GoodsCompanyRegistry>().descriptions.values() {
             let asset = &descr.asset_location;
             if !asset.ends_with(".png") && !asset.ends_with(".jpg") {
@@ -55,7 +58,10 @@ impl MapMeshHandler {
             }
             buildsprites.insert(
                 BuildingKind::GoodsCompany(descr.id),
-                SpriteBatchBuilder::new(gfx.texture(asset, "goods_company_tex"), gfx),
+                SpriteBatchBuilder::new(
+                    gfx.texture(mods.asset_path(asset), "goods_company_tex"),
+                    gfx,
+                ),
             );
         }
 
@@ -71,13 +77,14 @@ impl MapMeshHandler {
             })
             .chain([
                 ("rail_freight_station.glb", BuildingKind::RailFreightStation),
+                ("rail_freight_station.glb", BuildingKind::TrainStation),
                 ("external_trading.glb", BuildingKind::ExternalTrading),
             ])
         {
             if !asset.ends_with(".glb") {
                 continue;
             }
-            let m = match load_mesh(gfx, asset) {
+            let m = match load_mesh_from_path(gfx, &mods.asset_path(asset)) {
                 Ok(m) => m,
                 Err(e) => {
                     log::error!("Failed to load mesh {}: {:?}", asset, e);
@@ -93,7 +100,7 @@ impl MapMeshHandler {
             let floor = &z.floor;
             let filler = &z.filler;
 
-            let floor_tex = gfx.texture(floor, "zone_floor_tex");
+            let floor_tex = gfx.texture(mods.asset_path(floor), "zone_floor_tex");
             let floor_mat = gfx.register_material(Material::new(
                 gfx,
                 floor_tex,
@@ -106,7 +113,7 @@ impl MapMeshHandler {
             ));
             let floor_mesh = MeshBuilder::new(floor_mat);
 
-            let m = match load_mesh(gfx, filler) {
+            let m = match load_mesh_from_path(gfx, &mods.asset_path(filler)) {
                 Ok(m) => m,
                 Err(e) => {
                     log::error!("Failed to load mesh for zone {}: {:?}", filler, e);
@@ -247,6 +254,36 @@ impl MapBuilders {
                 }
             }
         }
+
+        // show the allowed directions right before the stop line of restricted lanes
+        for inter in map.intersections().values() {
+            for (&id, turns) in &inter.lane_turns {
+                let lane = unwrap_cont!(lanes.get(id));
+                let l = lane.points.length();
+                if l < 10.0 {
+                    continue;
+                }
+                let (pos, dir) = lane.points.point_dir_along(l - 6.0);
+                let dir = dir.xy();
+
+                for (allowed, arrow_dir) in [
+                    (turns.straight, dir),
+                    (turns.left, -dir.perpendicular()),
+                    (turns.right, dir.perpendicular()),
+                    (turns.back, -dir),
+                ] {
+                    if !allowed {
+                        continue;
+                    }
+                    self.arrow_builder.push(
+                        pos.up(0.03) + arrow_dir.z0(),
+                        arrow_dir.z0(),
+                        LinearColor::gray(0.4),
+                        (3.0, 3.0),
+                    );
+                }
+            }
+        }
     }
 
     fn crosswalks(&mut self, map: &Map) {
@@ -601,6 +638,17 @@ impl MapBuilders {
             inter_pylon(&mut tess.meshbuilder, terrain, inter, roads);
             intersection_mesh(&mut tess.meshbuilder, inter, roads);
 
+            // Roundabout island
+            if inter.turn_policy.roundabout {
+                let island = inter.roundabout_radius(roads) - LaneKind::Driving.width() * 0.5;
+
+                tess.set_color(line_col);
+                tess.draw_circle(inter.pos.up(0.01), island + 0.25);
+
+                tess.set_color(hig_col);
+                tess.draw_circle(inter.pos.up(0.02), island);
+            }
+
             // Walking corners
             for turn in inter
                 .turns()
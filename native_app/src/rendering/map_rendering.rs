@@ -80,7 +80,7 @@ impl MapRenderer {
     }
 
     fn render_lane_signals(n: &Lane, draw: &mut ImmediateDraw, time: u32) {
-        if n.control.is_always() {
+        if n.control.is_always() || n.control.is_yield() {
             return;
         }
 
@@ -97,7 +97,9 @@ impl MapRenderer {
         let mesh = match n.control.get_behavior(time) {
             TrafficBehavior::RED | TrafficBehavior::STOP => "traffic_light_red.glb",
             TrafficBehavior::ORANGE => "traffic_light_orange.glb",
-            TrafficBehavior::GREEN => "traffic_light_green.glb",
+            TrafficBehavior::GREEN | TrafficBehavior::LEFT | TrafficBehavior::YIELD => {
+                "traffic_light_green.glb"
+            }
         };
 
         draw.mesh(mesh, r_center, dir_perp.z(0.0));
@@ -10,7 +10,7 @@ use gltf::Document;
 use image::{DynamicImage, ImageBuffer};
 use smallvec::SmallVec;
 use std::collections::hash_map::Entry;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::Instant;
 use wgpu::{AddressMode, FilterMode};
@@ -232,7 +232,11 @@ pub fn load_mesh(gfx: &mut GfxContext, asset_name: &str) -> Result<Mesh, LoadMes
     let mut path = PathBuf::new();
     path.push("assets/models/");
     path.push(asset_name);
+    load_mesh_from_path(gfx, &path)
+}
 
+/// Same as [`load_mesh`] but the gltf file can be anywhere, like in a mod
+pub fn load_mesh_from_path(gfx: &mut GfxContext, path: &Path) -> Result<Mesh, LoadMeshError> {
     let t = Instant::now();
 
     let mut flat_vertices: Vec<MeshVertex> = vec![];